@@ -0,0 +1,110 @@
+//! Pooling and normalization microbenchmarks
+//!
+//! Compares the slice-based pooling in `models::pooling` with the previous
+//! implementation, which indexed the ndarray output element by element and
+//! only handled one sequence per call.
+//!
+//! Run with `cargo bench --bench pooling`. No benchmark framework is
+//! involved: each case is timed with `Instant` over enough iterations to
+//! take about half a second, after a short warmup.
+
+use embedding_server::models::pooling::{self, PoolingMode};
+use ndarray::{Array3, ArrayViewD};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Batch shapes to measure: small-model single request, small-model batch, base-model request
+const SHAPES: [(usize, usize, usize); 3] = [(1, 128, 384), (32, 128, 384), (1, 512, 768)];
+
+/// Mean pooling as it was written before the slice-based rewrite
+fn indexed_mean_pooling(output_tensor: &ArrayViewD<f32>, attention_mask: &[u32]) -> Vec<f32> {
+    let shape = output_tensor.shape();
+    let (seq_len, hidden_size) = (shape[1], shape[2]);
+    let mut pooled = vec![0.0f32; hidden_size];
+    let mut valid_tokens = 0;
+    for seq_idx in 0..seq_len {
+        if attention_mask[seq_idx] == 1 {
+            for hidden_idx in 0..hidden_size {
+                pooled[hidden_idx] += output_tensor[[0, seq_idx, hidden_idx]];
+            }
+            valid_tokens += 1;
+        }
+    }
+    for val in &mut pooled {
+        *val /= valid_tokens as f32;
+    }
+    pooled
+}
+
+/// Normalization as it was written before the slice-based rewrite
+fn iterator_normalize(embedding: &[f32]) -> Vec<f32> {
+    let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    embedding.iter().map(|x| x / norm).collect()
+}
+
+/// Time `f` and print the mean time per call
+fn bench(name: &str, mut f: impl FnMut()) {
+    for _ in 0..10 {
+        f();
+    }
+
+    let mut iterations = 0u64;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(500) {
+        f();
+        iterations += 1;
+    }
+    let per_call = start.elapsed() / iterations as u32;
+    println!("{:<48} {:>12?}/iter ({} iterations)", name, per_call, iterations);
+}
+
+/// Deterministic pseudo-random hidden state and a mask with a quarter of padding
+fn inputs(batch_size: usize, seq_len: usize, hidden_size: usize) -> (Vec<f32>, Vec<u32>) {
+    let mut state = 0x2545_f491_u32;
+    let hidden = (0..batch_size * seq_len * hidden_size)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32 - 0.5
+        })
+        .collect();
+    let valid = seq_len - seq_len / 4;
+    let mask = (0..batch_size)
+        .flat_map(|_| (0..seq_len).map(move |idx| u32::from(idx < valid)))
+        .collect();
+    (hidden, mask)
+}
+
+fn main() {
+    for (batch_size, seq_len, hidden_size) in SHAPES {
+        let (hidden, mask) = inputs(batch_size, seq_len, hidden_size);
+        let array = Array3::from_shape_vec((batch_size, seq_len, hidden_size), hidden.clone()).unwrap();
+        let label = format!("[{}, {}, {}]", batch_size, seq_len, hidden_size);
+
+        bench(&format!("mean pooling, indexed {}", label), || {
+            for (idx, mask) in mask.chunks(seq_len).enumerate() {
+                let sequence = array.slice(ndarray::s![idx..idx + 1, .., ..]).into_dyn();
+                black_box(indexed_mean_pooling(&sequence, black_box(mask)));
+            }
+        });
+        bench(&format!("mean pooling, slices {}", label), || {
+            let dims = [batch_size, seq_len, hidden_size];
+            black_box(pooling::pool_batch(PoolingMode::Mean, black_box(&hidden), &dims, &mask).unwrap());
+        });
+        bench(&format!("max pooling, slices {}", label), || {
+            let dims = [batch_size, seq_len, hidden_size];
+            black_box(pooling::pool_batch(PoolingMode::Max, black_box(&hidden), &dims, &mask).unwrap());
+        });
+
+        let embedding = &hidden[..hidden_size];
+        bench(&format!("normalize, iterator [{}]", hidden_size), || {
+            black_box(iterator_normalize(black_box(embedding)));
+        });
+        let mut buffer = embedding.to_vec();
+        bench(&format!("normalize, in place [{}]", hidden_size), || {
+            buffer.copy_from_slice(embedding);
+            pooling::normalize_in_place(black_box(&mut buffer)).unwrap();
+        });
+    }
+}
@@ -0,0 +1,125 @@
+//! Build script
+//!
+//! With the `embedded-model` feature, bakes one model's ONNX file,
+//! tokenizer.json and configuration into the binary. The model is chosen at
+//! build time:
+//!
+//! - `EMBEDDED_MODEL` - model id (TOML key) to embed, defaults to the
+//!   configured `default_model`
+//! - `EMBEDDED_MODELS_CONFIG` - models configuration to read it from,
+//!   defaults to `embeddingmodels.toml` next to Cargo.toml
+//!
+//! Relative `model_path` / `tokenizer_path` values are resolved against the
+//! directory of the models configuration file.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Paths the embedded model config points at instead of files on disk
+const EMBEDDED_MODEL_PATH: &str = "embedded://model.onnx";
+const EMBEDDED_TOKENIZER_PATH: &str = "embedded://tokenizer.json";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    if env::var_os("CARGO_FEATURE_EMBEDDED_MODEL").is_none() {
+        return;
+    }
+
+    println!("cargo:rerun-if-env-changed=EMBEDDED_MODEL");
+    println!("cargo:rerun-if-env-changed=EMBEDDED_MODELS_CONFIG");
+
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let config_path = env::var("EMBEDDED_MODELS_CONFIG")
+        .map(|path| manifest_dir.join(path))
+        .unwrap_or_else(|_| manifest_dir.join("embeddingmodels.toml"));
+    println!("cargo:rerun-if-changed={}", config_path.display());
+
+    let content = fs::read_to_string(&config_path)
+        .unwrap_or_else(|e| panic!("Failed to read models config {}: {}", config_path.display(), e));
+    let config: toml::Table = content
+        .parse()
+        .unwrap_or_else(|e| panic!("Failed to parse models config {}: {}", config_path.display(), e));
+
+    let model_id = env::var("EMBEDDED_MODEL").unwrap_or_else(|_| {
+        config
+            .get("global")
+            .and_then(|global| global.get("default_model"))
+            .and_then(|name| name.as_str())
+            .expect("EMBEDDED_MODEL is not set and the models config has no global.default_model")
+            .to_string()
+    });
+
+    let models = config
+        .get("models")
+        .and_then(|models| models.as_table())
+        .expect("Models config has no [models] table");
+    // Accept the TOML key, the display name or an alias, like the server does
+    let (model_key, model) = models
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(&model_id))
+        .or_else(|| {
+            models.iter().find(|(_, model)| {
+                let aliases = model.get("aliases").and_then(|aliases| aliases.as_array());
+                model.get("name").into_iter()
+                    .chain(aliases.into_iter().flatten())
+                    .filter_map(|name| name.as_str())
+                    .any(|name| name.eq_ignore_ascii_case(&model_id))
+            })
+        })
+        .unwrap_or_else(|| panic!("Model '{}' not found in {}", model_id, config_path.display()));
+    let mut model = model.as_table().expect("Model entry is not a table").clone();
+
+    let backend = model.get("backend").and_then(|backend| backend.as_str()).unwrap_or("onnx");
+    if backend != "onnx" {
+        panic!("Only onnx models can be embedded, '{}' uses the {} backend", model_key, backend);
+    }
+
+    let base_dir = config_path.parent().unwrap_or(Path::new("."));
+    let onnx_file = asset_path(&model, "model_path", base_dir);
+    let tokenizer_file = asset_path(&model, "tokenizer_path", base_dir);
+
+    model.insert("model_path".to_string(), EMBEDDED_MODEL_PATH.into());
+    model.insert("tokenizer_path".to_string(), EMBEDDED_TOKENIZER_PATH.into());
+    model.insert("enabled".to_string(), true.into());
+    let model_config = toml::to_string(&model).expect("Failed to serialize embedded model config");
+
+    let generated = format!(
+        "/// Id of the embedded model\n\
+         pub const MODEL_ID: &str = {:?};\n\
+         /// Model config (TOML) with paths rewritten to the embedded assets\n\
+         pub const MODEL_CONFIG: &str = {:?};\n\
+         /// `model_path` of the embedded model\n\
+         pub const MODEL_PATH: &str = {:?};\n\
+         /// `tokenizer_path` of the embedded model\n\
+         pub const TOKENIZER_PATH: &str = {:?};\n\
+         /// ONNX model bytes\n\
+         pub static MODEL_ONNX: &[u8] = include_bytes!({:?});\n\
+         /// tokenizer.json bytes\n\
+         pub static TOKENIZER_JSON: &[u8] = include_bytes!({:?});\n",
+        model_key,
+        model_config,
+        EMBEDDED_MODEL_PATH,
+        EMBEDDED_TOKENIZER_PATH,
+        onnx_file.display().to_string(),
+        tokenizer_file.display().to_string(),
+    );
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out_dir.join("embedded_model.rs"), generated).expect("Failed to write embedded_model.rs");
+}
+
+/// Resolve and check an asset path from the model table
+fn asset_path(model: &toml::Table, key: &str, base_dir: &Path) -> PathBuf {
+    let path = model
+        .get(key)
+        .and_then(|path| path.as_str())
+        .unwrap_or_else(|| panic!("Embedded model has no {}", key));
+    let path = base_dir.join(path);
+    let path = path
+        .canonicalize()
+        .unwrap_or_else(|e| panic!("Embedded model {} {} is not readable: {}", key, path.display(), e));
+    println!("cargo:rerun-if-changed={}", path.display());
+    path
+}
@@ -0,0 +1,335 @@
+//! # Candle Embedding Engine
+//!
+//! Pure Rust inference for models that are only published as safetensors,
+//! using Hugging Face's candle instead of ONNX Runtime.
+//!
+//! ## Supported architectures
+//!
+//! The encoder is chosen from `model_type` in config.json:
+//!
+//! - `bert` - BERT and sentence-transformers MiniLM/MPNet-style BERT exports
+//! - `xlm-roberta` / `roberta` - XLM-RoBERTa (e5, bge-m3, ...)
+//! - `nomic_bert` - NomicBERT (nomic-embed-text)
+//!
+//! Tokenization uses the same `tokenizers` integration as the ONNX engine and
+//! pooling goes through [`crate::models::pooling`].
+
+use crate::models::intermediates::Intermediates;
+use crate::models::pooling::{self, PoolingMode};
+use crate::models::tokenizer_loader::load_tokenizer;
+use crate::models::weights::Matrix;
+use crate::models::EmbeddingError;
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::{bert, xlm_roberta};
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer, TruncationParams};
+use tracing::{debug, info, instrument};
+
+use super::nomic_bert;
+
+/// Encoder architectures the candle engine can load
+enum EncoderModel {
+    Bert(bert::BertModel),
+    XlmRoberta(xlm_roberta::XLMRobertaModel),
+    NomicBert(nomic_bert::NomicBertModel),
+}
+
+impl EncoderModel {
+    /// Run the encoder and return `last_hidden_state` [batch_size, seq_len, hidden_size]
+    fn forward(&self, input_ids: &Tensor, token_type_ids: &Tensor, attention_mask: &Tensor) -> candle_core::Result<Tensor> {
+        match self {
+            EncoderModel::Bert(model) => model.forward(input_ids, token_type_ids, Some(attention_mask)),
+            EncoderModel::XlmRoberta(model) => {
+                model.forward(input_ids, attention_mask, token_type_ids, None, None, None)
+            }
+            EncoderModel::NomicBert(model) => model.forward(input_ids, token_type_ids, attention_mask),
+        }
+    }
+}
+
+/// Candle-based embedding engine for safetensors models
+pub struct CandleEmbeddingEngine {
+    /// Loaded encoder
+    model: EncoderModel,
+    /// HuggingFace tokenizer for text preprocessing
+    tokenizer: Tokenizer,
+    /// Device the weights live on
+    device: Device,
+    /// Pooling strategy from the model configuration
+    pooling_mode: PoolingMode,
+    /// Architecture name from config.json (`model_type`)
+    architecture: String,
+}
+
+impl CandleEmbeddingEngine {
+    /// Create a new candle embedding engine
+    ///
+    /// # Arguments
+    /// * `model_path` - Path to the weights file (model.safetensors)
+    /// * `tokenizer_path` - Path to the tokenizer configuration file (tokenizer.json)
+    /// * `config_path` - Path to the Hugging Face model configuration (config.json)
+    /// * `pooling_mode` - Pooling mode from embeddingmodels.toml (mean, cls, max)
+    /// * `max_seq_length` - Inputs are truncated to this many tokens
+    /// * `use_gpu` - Place the weights on the first CUDA device when available
+    ///
+    /// # Returns
+    /// A new CandleEmbeddingEngine instance or an EmbeddingError
+    pub fn new(
+        model_path: &str,
+        tokenizer_path: &str,
+        config_path: &str,
+        pooling_mode: &str,
+        max_seq_length: usize,
+        use_gpu: bool,
+    ) -> Result<Self, EmbeddingError> {
+        info!("Initializing candle embedding engine with model: {}", model_path);
+
+        let pooling_mode = PoolingMode::from_config(pooling_mode)?;
+
+        let device = if use_gpu {
+            Device::cuda_if_available(0).map_err(load_error)?
+        } else {
+            Device::Cpu
+        };
+
+        // Read config.json once; the architecture-specific config is parsed from it below
+        let config_json = std::fs::read_to_string(config_path)
+            .map_err(|e| EmbeddingError::ModelLoadFailed {
+                error: format!("Failed to read model config {}: {}", config_path, e),
+            })?;
+        let config_value: serde_json::Value = serde_json::from_str(&config_json)
+            .map_err(|e| EmbeddingError::ModelLoadFailed {
+                error: format!("Invalid model config {}: {}", config_path, e),
+            })?;
+        let architecture = config_value
+            .get("model_type")
+            .and_then(|v| v.as_str())
+            .unwrap_or("bert")
+            .to_string();
+        let pad_token_id = config_value
+            .get("pad_token_id")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as u32;
+
+        // SAFETY: the weights file is memory-mapped read-only and must not be
+        // modified while the engine is alive, as with any mmap-based loader.
+        let vb = unsafe { VarBuilder::from_mmaped_safetensors(&[model_path], DType::F32, &device) }
+            .map_err(|e| EmbeddingError::ModelLoadFailed {
+                error: format!("Failed to load safetensors weights: {}", e),
+            })?;
+
+        let model = match architecture.as_str() {
+            "bert" => {
+                let config: bert::Config = parse_config(&config_json)?;
+                EncoderModel::Bert(bert::BertModel::load(vb, &config).map_err(load_error)?)
+            }
+            "xlm-roberta" | "roberta" => {
+                let config: xlm_roberta::Config = parse_config(&config_json)?;
+                // Checkpoints exported from *ForMaskedLM keep the encoder under `roberta.`
+                let model = xlm_roberta::XLMRobertaModel::new(&config, vb.clone())
+                    .or_else(|_| xlm_roberta::XLMRobertaModel::new(&config, vb.pp("roberta")))
+                    .map_err(load_error)?;
+                EncoderModel::XlmRoberta(model)
+            }
+            "nomic_bert" => {
+                let config: nomic_bert::Config = parse_config(&config_json)?;
+                EncoderModel::NomicBert(nomic_bert::NomicBertModel::load(vb, &config).map_err(load_error)?)
+            }
+            other => {
+                return Err(EmbeddingError::ModelLoadFailed {
+                    error: format!("Unsupported model_type '{}' for candle backend (expected bert, xlm-roberta or nomic_bert)", other),
+                });
+            }
+        };
+
+        // Load tokenizer and pad each batch to its longest sequence
+        let mut tokenizer = load_tokenizer(tokenizer_path)?;
+        if tokenizer.get_padding().is_none() {
+            tokenizer.with_padding(Some(PaddingParams {
+                strategy: PaddingStrategy::BatchLongest,
+                pad_id: pad_token_id,
+                ..Default::default()
+            }));
+        }
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: max_seq_length,
+                ..Default::default()
+            }))
+            .map_err(|e| EmbeddingError::ModelLoadFailed {
+                error: format!("Failed to configure tokenizer truncation: {}", e),
+            })?;
+
+        info!("Candle embedding engine initialized successfully ({} on {:?})", architecture, device);
+        Ok(Self {
+            model,
+            tokenizer,
+            device,
+            pooling_mode,
+            architecture,
+        })
+    }
+
+    /// Architecture name from config.json (`model_type`)
+    pub fn architecture(&self) -> &str {
+        &self.architecture
+    }
+
+    /// Generate embeddings for a batch of texts
+    ///
+    /// The whole batch is padded to its longest sequence and run through the
+    /// encoder in a single forward pass.
+    ///
+    /// # Arguments
+    /// * `texts` - Vector of text strings to embed
+    ///
+    /// # Returns
+    /// Vector of L2-normalized embeddings (one per input text) or an EmbeddingError
+    #[instrument(skip(self), fields(text_count = texts.len()))]
+    pub async fn embed_texts(&mut self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        if texts.is_empty() {
+            return Err(EmbeddingError::InvalidInput {
+                message: "Cannot embed empty text list".to_string(),
+            });
+        }
+
+        debug!("Generating embeddings for {} texts", texts.len());
+
+        let encodings = self.tokenizer.encode_batch(texts, true)
+            .map_err(|e| EmbeddingError::EmbeddingFailed {
+                error: format!("Tokenization failed: {}", e),
+            })?;
+
+        let batch_size = encodings.len();
+        let seq_len = encodings[0].get_ids().len();
+
+        let mut input_ids = Vec::with_capacity(batch_size * seq_len);
+        let mut type_ids = Vec::with_capacity(batch_size * seq_len);
+        let mut attention_mask = Vec::with_capacity(batch_size * seq_len);
+        for encoding in &encodings {
+            input_ids.extend_from_slice(encoding.get_ids());
+            type_ids.extend_from_slice(encoding.get_type_ids());
+            attention_mask.extend_from_slice(encoding.get_attention_mask());
+        }
+
+        let embeddings = self.forward_pooled(batch_size, seq_len, input_ids, type_ids, attention_mask)?;
+        debug!("Successfully generated {} embeddings", embeddings.len());
+        Ok(embeddings)
+    }
+
+    /// Generate an embedding for already tokenized input, bypassing the tokenizer
+    pub async fn embed_token_ids(&mut self, input_ids: Vec<u32>) -> Result<Vec<f32>, EmbeddingError> {
+        if input_ids.is_empty() {
+            return Err(EmbeddingError::InvalidInput {
+                message: "Cannot embed empty input_ids".to_string(),
+            });
+        }
+
+        let seq_len = input_ids.len();
+        let embeddings = self.forward_pooled(1, seq_len, input_ids, vec![0; seq_len], vec![1; seq_len])?;
+        Ok(embeddings.into_iter().next().unwrap_or_default())
+    }
+
+    /// Generate an embedding for one text, capturing the token ids, attention
+    /// mask, raw `last_hidden_state` and pooled vector along the way
+    pub async fn trace_text(&mut self, text: &str) -> Result<Intermediates, EmbeddingError> {
+        let encoding = self.tokenizer.encode(text, true)
+            .map_err(|e| EmbeddingError::EmbeddingFailed {
+                error: format!("Tokenization failed: {}", e),
+            })?;
+        let input_ids = encoding.get_ids().to_vec();
+        let attention_mask = encoding.get_attention_mask().to_vec();
+        let seq_len = input_ids.len();
+
+        let (hidden, hidden_size) = self.forward_hidden(1, seq_len, input_ids.clone(), encoding.get_type_ids().to_vec(), &attention_mask)?;
+        let pooled = pooling::pool_sequence(self.pooling_mode, &hidden, hidden_size, &attention_mask)?;
+        let embedding = pooling::normalize_embedding(&pooled)?;
+
+        Ok(Intermediates {
+            text: text.to_string(),
+            token_ids: Some(input_ids),
+            attention_mask: Some(attention_mask),
+            hidden_state: Some(Matrix::new(seq_len, hidden_size, hidden)?),
+            pooled: Some(pooled),
+            embedding,
+            ..Default::default()
+        })
+    }
+
+    /// Number of entries in the tokenizer vocabulary, including added tokens
+    pub fn vocab_size(&self) -> usize {
+        self.tokenizer.get_vocab_size(true)
+    }
+
+    /// Tokenizer used to prepare model inputs
+    pub fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
+
+    /// Run a padded [batch_size, seq_len] batch through the encoder, then pool and normalize each row
+    fn forward_pooled(
+        &self,
+        batch_size: usize,
+        seq_len: usize,
+        input_ids: Vec<u32>,
+        type_ids: Vec<u32>,
+        attention_mask: Vec<u32>,
+    ) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let (hidden, hidden_size) = self.forward_hidden(batch_size, seq_len, input_ids, type_ids, &attention_mask)?;
+
+        let mut embeddings = pooling::pool_batch(self.pooling_mode, &hidden, &[batch_size, seq_len, hidden_size], &attention_mask)?;
+        for embedding in &mut embeddings {
+            pooling::normalize_in_place(embedding)?;
+        }
+        Ok(embeddings)
+    }
+
+    /// Run a padded batch through the encoder, returning `last_hidden_state`
+    /// as a flat row-major buffer together with its hidden size
+    fn forward_hidden(
+        &self,
+        batch_size: usize,
+        seq_len: usize,
+        input_ids: Vec<u32>,
+        type_ids: Vec<u32>,
+        attention_mask: &[u32],
+    ) -> Result<(Vec<f32>, usize), EmbeddingError> {
+        let to_tensor = |data: Vec<u32>| {
+            Tensor::from_vec(data, (batch_size, seq_len), &self.device).map_err(inference_error)
+        };
+        let input_ids_tensor = to_tensor(input_ids)?;
+        let type_ids_tensor = to_tensor(type_ids)?;
+        let attention_mask_tensor = to_tensor(attention_mask.to_vec())?;
+
+        let output = self.model
+            .forward(&input_ids_tensor, &type_ids_tensor, &attention_mask_tensor)
+            .map_err(inference_error)?;
+
+        let (_, _, hidden_size) = output.dims3().map_err(inference_error)?;
+        let data = output
+            .to_dtype(DType::F32)
+            .and_then(|t| t.flatten_all())
+            .and_then(|t| t.to_vec1::<f32>())
+            .map_err(inference_error)?;
+        Ok((data, hidden_size))
+    }
+}
+
+/// Parse an architecture-specific config from config.json
+fn parse_config<T: serde::de::DeserializeOwned>(config_json: &str) -> Result<T, EmbeddingError> {
+    serde_json::from_str(config_json).map_err(|e| EmbeddingError::ModelLoadFailed {
+        error: format!("Failed to parse model config: {}", e),
+    })
+}
+
+fn load_error(error: candle_core::Error) -> EmbeddingError {
+    EmbeddingError::ModelLoadFailed {
+        error: format!("Failed to load candle model: {}", error),
+    }
+}
+
+fn inference_error(error: candle_core::Error) -> EmbeddingError {
+    EmbeddingError::EmbeddingFailed {
+        error: format!("Candle inference failed: {}", error),
+    }
+}
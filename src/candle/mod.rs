@@ -0,0 +1,7 @@
+//! Candle Module for Embedding Server
+//!
+//! This module provides safetensors-based embedding functionality using candle
+
+pub mod candle_engine;
+pub mod nomic_bert;
+pub use candle_engine::CandleEmbeddingEngine;
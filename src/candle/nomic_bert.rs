@@ -0,0 +1,314 @@
+//! NomicBERT encoder
+//!
+//! candle-transformers ships BERT and XLM-RoBERTa but no NomicBERT, so the
+//! architecture used by nomic-embed-text is defined here: a post-norm BERT
+//! variant with rotary position embeddings, a fused QKV projection and a
+//! SwiGLU feed-forward block.
+
+use candle_core::{DType, Module, Result, Tensor, D};
+use candle_nn::{embedding, layer_norm, linear_b, Embedding, LayerNorm, Linear, VarBuilder};
+use serde::Deserialize;
+
+/// NomicBERT configuration as found in config.json
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub vocab_size: usize,
+    pub n_embd: usize,
+    pub n_head: usize,
+    pub n_layer: usize,
+    #[serde(default)]
+    pub n_inner: Option<usize>,
+    #[serde(default = "default_type_vocab_size")]
+    pub type_vocab_size: usize,
+    #[serde(default = "default_layer_norm_epsilon")]
+    pub layer_norm_epsilon: f64,
+    #[serde(default = "default_rotary_emb_base")]
+    pub rotary_emb_base: f64,
+    #[serde(default = "default_rotary_emb_fraction")]
+    pub rotary_emb_fraction: f64,
+    #[serde(default)]
+    pub rotary_emb_interleaved: bool,
+    #[serde(default)]
+    pub qkv_proj_bias: bool,
+    #[serde(default)]
+    pub mlp_fc1_bias: bool,
+    #[serde(default)]
+    pub mlp_fc2_bias: bool,
+    #[serde(default)]
+    pub prenorm: bool,
+    #[serde(default = "default_activation_function")]
+    pub activation_function: String,
+}
+
+fn default_type_vocab_size() -> usize {
+    2
+}
+
+fn default_layer_norm_epsilon() -> f64 {
+    1e-12
+}
+
+fn default_rotary_emb_base() -> f64 {
+    1000.0
+}
+
+fn default_rotary_emb_fraction() -> f64 {
+    1.0
+}
+
+fn default_activation_function() -> String {
+    "swiglu".to_string()
+}
+
+impl Config {
+    fn head_dim(&self) -> usize {
+        self.n_embd / self.n_head
+    }
+
+    fn rotary_dim(&self) -> usize {
+        (self.head_dim() as f64 * self.rotary_emb_fraction) as usize
+    }
+}
+
+/// Rotary position embedding tables for one forward pass
+struct RotaryEmbedding {
+    cos: Tensor,
+    sin: Tensor,
+    rotary_dim: usize,
+    interleaved: bool,
+}
+
+impl RotaryEmbedding {
+    fn new(config: &Config, seq_len: usize, dtype: DType, device: &candle_core::Device) -> Result<Self> {
+        let rotary_dim = config.rotary_dim();
+        let inv_freq: Vec<f32> = (0..rotary_dim)
+            .step_by(2)
+            .map(|i| 1.0 / config.rotary_emb_base.powf(i as f64 / rotary_dim as f64) as f32)
+            .collect();
+        let inv_freq = Tensor::from_vec(inv_freq, (1, rotary_dim / 2), device)?;
+        let positions = Tensor::arange(0u32, seq_len as u32, device)?
+            .to_dtype(DType::F32)?
+            .reshape((seq_len, 1))?;
+        let freqs = positions.broadcast_mul(&inv_freq)?;
+
+        Ok(Self {
+            cos: freqs.cos()?.to_dtype(dtype)?,
+            sin: freqs.sin()?.to_dtype(dtype)?,
+            rotary_dim,
+            interleaved: config.rotary_emb_interleaved,
+        })
+    }
+
+    /// Rotate the first `rotary_dim` channels of a `[batch, heads, seq, head_dim]` tensor
+    fn apply(&self, xs: &Tensor) -> Result<Tensor> {
+        let head_dim = xs.dim(D::Minus1)?;
+        let rotated_part = xs.narrow(D::Minus1, 0, self.rotary_dim)?.contiguous()?;
+        let rotated = if self.interleaved {
+            candle_nn::rotary_emb::rope_i(&rotated_part, &self.cos, &self.sin)?
+        } else {
+            candle_nn::rotary_emb::rope(&rotated_part, &self.cos, &self.sin)?
+        };
+
+        if self.rotary_dim == head_dim {
+            Ok(rotated)
+        } else {
+            let pass_through = xs.narrow(D::Minus1, self.rotary_dim, head_dim - self.rotary_dim)?;
+            Tensor::cat(&[&rotated, &pass_through], D::Minus1)?.contiguous()
+        }
+    }
+}
+
+struct NomicBertAttention {
+    wqkv: Linear,
+    out_proj: Linear,
+    n_head: usize,
+    head_dim: usize,
+}
+
+impl NomicBertAttention {
+    fn load(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let wqkv = linear_b(config.n_embd, 3 * config.n_embd, config.qkv_proj_bias, vb.pp("Wqkv"))?;
+        let out_proj = linear_b(config.n_embd, config.n_embd, config.qkv_proj_bias, vb.pp("out_proj"))?;
+        Ok(Self {
+            wqkv,
+            out_proj,
+            n_head: config.n_head,
+            head_dim: config.head_dim(),
+        })
+    }
+
+    fn forward(&self, hidden_states: &Tensor, attention_mask: &Tensor, rotary: &RotaryEmbedding) -> Result<Tensor> {
+        let (batch_size, seq_len, _) = hidden_states.dims3()?;
+        let qkv = self
+            .wqkv
+            .forward(hidden_states)?
+            .reshape((batch_size, seq_len, 3, self.n_head, self.head_dim))?;
+
+        let split = |idx: usize| -> Result<Tensor> { qkv.narrow(2, idx, 1)?.squeeze(2)?.transpose(1, 2)?.contiguous() };
+        let query = rotary.apply(&split(0)?)?;
+        let key = rotary.apply(&split(1)?)?;
+        let value = split(2)?;
+
+        let scale = 1.0 / (self.head_dim as f64).sqrt();
+        let scores = (query.matmul(&key.t()?)? * scale)?.broadcast_add(attention_mask)?;
+        let probs = candle_nn::ops::softmax_last_dim(&scores)?;
+        let context = probs
+            .matmul(&value)?
+            .transpose(1, 2)?
+            .reshape((batch_size, seq_len, self.n_head * self.head_dim))?;
+
+        self.out_proj.forward(&context)
+    }
+}
+
+enum NomicBertMlp {
+    /// `fc2(fc11(x) * silu(fc12(x)))`
+    SwiGlu { fc11: Linear, fc12: Linear, fc2: Linear },
+    /// `fc2(gelu(fc1(x)))`
+    Gelu { fc1: Linear, fc2: Linear },
+}
+
+impl NomicBertMlp {
+    fn load(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let inner = config.n_inner.unwrap_or(4 * config.n_embd);
+        let fc2 = linear_b(inner, config.n_embd, config.mlp_fc2_bias, vb.pp("fc2"))?;
+        match config.activation_function.as_str() {
+            "swiglu" => Ok(NomicBertMlp::SwiGlu {
+                fc11: linear_b(config.n_embd, inner, config.mlp_fc1_bias, vb.pp("fc11"))?,
+                fc12: linear_b(config.n_embd, inner, config.mlp_fc1_bias, vb.pp("fc12"))?,
+                fc2,
+            }),
+            "gelu" | "gelu_new" | "gelu_fast" => Ok(NomicBertMlp::Gelu {
+                fc1: linear_b(config.n_embd, inner, config.mlp_fc1_bias, vb.pp("fc1"))?,
+                fc2,
+            }),
+            other => candle_core::bail!("Unsupported NomicBERT activation function: {}", other),
+        }
+    }
+}
+
+impl Module for NomicBertMlp {
+    fn forward(&self, xs: &Tensor) -> Result<Tensor> {
+        match self {
+            NomicBertMlp::SwiGlu { fc11, fc12, fc2 } => {
+                let gate = candle_nn::ops::silu(&fc12.forward(xs)?)?;
+                fc2.forward(&(fc11.forward(xs)? * gate)?)
+            }
+            NomicBertMlp::Gelu { fc1, fc2 } => fc2.forward(&fc1.forward(xs)?.gelu()?),
+        }
+    }
+}
+
+struct NomicBertBlock {
+    attn: NomicBertAttention,
+    mlp: NomicBertMlp,
+    norm1: LayerNorm,
+    norm2: LayerNorm,
+}
+
+impl NomicBertBlock {
+    fn load(vb: VarBuilder, config: &Config) -> Result<Self> {
+        Ok(Self {
+            attn: NomicBertAttention::load(vb.pp("attn"), config)?,
+            mlp: NomicBertMlp::load(vb.pp("mlp"), config)?,
+            norm1: layer_norm(config.n_embd, config.layer_norm_epsilon, vb.pp("norm1"))?,
+            norm2: layer_norm(config.n_embd, config.layer_norm_epsilon, vb.pp("norm2"))?,
+        })
+    }
+
+    fn forward(&self, hidden_states: &Tensor, attention_mask: &Tensor, rotary: &RotaryEmbedding) -> Result<Tensor> {
+        // Post-norm residual blocks (config.prenorm = false)
+        let attn_output = self.attn.forward(hidden_states, attention_mask, rotary)?;
+        let hidden_states = self.norm1.forward(&(attn_output + hidden_states)?)?;
+        let mlp_output = self.mlp.forward(&hidden_states)?;
+        self.norm2.forward(&(mlp_output + hidden_states)?)
+    }
+}
+
+/// NomicBERT encoder producing `last_hidden_state`
+pub struct NomicBertModel {
+    word_embeddings: Embedding,
+    token_type_embeddings: Embedding,
+    emb_ln: LayerNorm,
+    layers: Vec<NomicBertBlock>,
+    config: Config,
+}
+
+impl NomicBertModel {
+    /// Load the encoder weights
+    pub fn load(vb: VarBuilder, config: &Config) -> Result<Self> {
+        if config.prenorm {
+            candle_core::bail!("Pre-norm NomicBERT checkpoints are not supported");
+        }
+
+        let embeddings = vb.pp("embeddings");
+        let word_embeddings = embedding(config.vocab_size, config.n_embd, embeddings.pp("word_embeddings"))?;
+        let token_type_embeddings =
+            embedding(config.type_vocab_size, config.n_embd, embeddings.pp("token_type_embeddings"))?;
+        let emb_ln = layer_norm(config.n_embd, config.layer_norm_epsilon, vb.pp("emb_ln"))?;
+
+        let layers = (0..config.n_layer)
+            .map(|idx| NomicBertBlock::load(vb.pp(format!("encoder.layers.{}", idx)), config))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            word_embeddings,
+            token_type_embeddings,
+            emb_ln,
+            layers,
+            config: config.clone(),
+        })
+    }
+
+    /// Run the encoder
+    ///
+    /// # Arguments
+    /// * `input_ids` - Token ids [batch_size, seq_len]
+    /// * `token_type_ids` - Segment ids [batch_size, seq_len]
+    /// * `attention_mask` - 1 for real tokens, 0 for padding [batch_size, seq_len]
+    pub fn forward(&self, input_ids: &Tensor, token_type_ids: &Tensor, attention_mask: &Tensor) -> Result<Tensor> {
+        let (_, seq_len) = input_ids.dims2()?;
+
+        let embeddings = (self.word_embeddings.forward(input_ids)? + self.token_type_embeddings.forward(token_type_ids)?)?;
+        let mut hidden_states = self.emb_ln.forward(&embeddings)?;
+        let dtype = hidden_states.dtype();
+
+        // Additive mask broadcast over heads and query positions: [batch, 1, 1, seq]
+        let mask = attention_mask.to_dtype(DType::F32)?.unsqueeze(1)?.unsqueeze(1)?;
+        let mask = ((mask.ones_like()? - mask)? * f32::MIN as f64)?.to_dtype(dtype)?;
+
+        let rotary = RotaryEmbedding::new(&self.config, seq_len, dtype, input_ids.device())?;
+        for layer in &self.layers {
+            hidden_states = layer.forward(&hidden_states, &mask, &rotary)?;
+        }
+
+        Ok(hidden_states)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candle_core::Device;
+    use candle_nn::VarMap;
+
+    #[test]
+    fn test_forward_shape() {
+        let config: Config = serde_json::from_str(
+            r#"{"vocab_size": 32, "n_embd": 16, "n_head": 2, "n_layer": 2, "n_inner": 24, "rotary_emb_fraction": 0.5}"#,
+        )
+        .unwrap();
+
+        let device = Device::Cpu;
+        let varmap = VarMap::new();
+        let vb = VarBuilder::from_varmap(&varmap, DType::F32, &device);
+        let model = NomicBertModel::load(vb, &config).unwrap();
+
+        let input_ids = Tensor::new(&[[1u32, 5, 7, 2], [1, 9, 2, 0]], &device).unwrap();
+        let token_type_ids = input_ids.zeros_like().unwrap();
+        let attention_mask = Tensor::new(&[[1u32, 1, 1, 1], [1, 1, 1, 0]], &device).unwrap();
+
+        let output = model.forward(&input_ids, &token_type_ids, &attention_mask).unwrap();
+        assert_eq!(output.dims(), &[2, 4, 16]);
+    }
+}
@@ -0,0 +1,312 @@
+//! # Lexical Embedding Engine
+//!
+//! Embeddings without neural weights: text is split into word or character
+//! n-grams, each n-gram is hashed into one of `dimension` buckets, and the
+//! bucket counts form the vector.
+//!
+//! ## Modes
+//!
+//! - `hashing` - (optionally sublinear) term frequencies per bucket
+//! - `tfidf` - term frequencies scaled by an IDF table fitted from a local
+//!   corpus file with one document per line, using the smoothed IDF
+//!   `ln((1 + n) / (1 + df)) + 1`
+//!
+//! These vectors are a cheap fallback model and the keyword side of hybrid
+//! search, served through the same APIs as dense models.
+
+use crate::models::config::LexicalConfig;
+use crate::models::hashing::fnv1a_64;
+use crate::models::{pooling, EmbeddingError};
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+/// Feature extraction strategy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Analyzer {
+    /// Word n-grams
+    Word,
+    /// Character n-grams inside space-padded words
+    Char,
+}
+
+/// Lexical embedding engine
+#[derive(Debug)]
+pub struct LexicalEmbeddingEngine {
+    /// Number of hash buckets (embedding dimension)
+    dimension: usize,
+    analyzer: Analyzer,
+    min_n: usize,
+    max_n: usize,
+    lowercase: bool,
+    sublinear_tf: bool,
+    alternate_sign: bool,
+    normalize: bool,
+    /// Per-bucket IDF weights (tfidf mode only)
+    idf: Option<Vec<f32>>,
+}
+
+impl LexicalEmbeddingEngine {
+    /// Create a lexical engine, fitting the IDF table from `corpus_path` in tfidf mode
+    ///
+    /// # Arguments
+    /// * `config` - Lexical backend settings
+    /// * `dimension` - Number of hash buckets (the model's embedding dimension)
+    pub fn new(config: &LexicalConfig, dimension: usize) -> Result<Self, EmbeddingError> {
+        let mut engine = Self::unfitted(config, dimension)?;
+
+        match config.mode.to_lowercase().as_str() {
+            "hashing" => {}
+            "tfidf" => {
+                let corpus_path = config.corpus_path.as_ref().ok_or_else(|| EmbeddingError::ConfigError {
+                    message: "Lexical tfidf mode requires corpus_path".to_string(),
+                })?;
+                info!("Fitting TF-IDF weights from corpus: {}", corpus_path);
+                let corpus = std::fs::read_to_string(corpus_path)
+                    .map_err(|e| EmbeddingError::ModelLoadFailed {
+                        error: format!("Failed to read TF-IDF corpus {}: {}", corpus_path, e),
+                    })?;
+                engine.fit(corpus.lines().filter(|line| !line.trim().is_empty()));
+            }
+            other => {
+                return Err(EmbeddingError::ConfigError {
+                    message: format!("Unknown lexical mode '{}' (expected hashing or tfidf)", other),
+                });
+            }
+        }
+
+        info!("Lexical embedding engine initialized ({} buckets, {:?} {}-{} grams)",
+              engine.dimension, engine.analyzer, engine.min_n, engine.max_n);
+        Ok(engine)
+    }
+
+    /// Build an engine from settings without fitting any IDF weights
+    fn unfitted(config: &LexicalConfig, dimension: usize) -> Result<Self, EmbeddingError> {
+        let analyzer = match config.analyzer.to_lowercase().as_str() {
+            "word" => Analyzer::Word,
+            "char" | "char_wb" => Analyzer::Char,
+            other => {
+                return Err(EmbeddingError::ConfigError {
+                    message: format!("Unknown lexical analyzer '{}' (expected word or char)", other),
+                });
+            }
+        };
+
+        let [min_n, max_n] = config.ngram_range;
+        if min_n == 0 || min_n > max_n {
+            return Err(EmbeddingError::ConfigError {
+                message: format!("Invalid ngram_range [{}, {}]", min_n, max_n),
+            });
+        }
+
+        if dimension == 0 {
+            return Err(EmbeddingError::ConfigError {
+                message: "Lexical embedding_dimension must be greater than zero".to_string(),
+            });
+        }
+
+        let normalize = match config.normalization.to_lowercase().as_str() {
+            "l2" => true,
+            "none" => false,
+            other => {
+                return Err(EmbeddingError::ConfigError {
+                    message: format!("Unknown lexical normalization '{}' (expected l2 or none)", other),
+                });
+            }
+        };
+
+        Ok(Self {
+            dimension,
+            analyzer,
+            min_n,
+            max_n,
+            lowercase: config.lowercase,
+            sublinear_tf: config.sublinear_tf,
+            alternate_sign: config.alternate_sign,
+            normalize,
+            idf: None,
+        })
+    }
+
+    /// Fit per-bucket IDF weights from an iterator of documents
+    pub fn fit<'a>(&mut self, documents: impl Iterator<Item = &'a str>) {
+        let mut document_frequency = vec![0u32; self.dimension];
+        let mut documents_seen = 0u32;
+
+        for document in documents {
+            documents_seen += 1;
+            for bucket in self.term_counts(document).keys() {
+                document_frequency[*bucket] += 1;
+            }
+        }
+
+        let n = documents_seen as f32;
+        self.idf = Some(
+            document_frequency
+                .iter()
+                .map(|&df| ((1.0 + n) / (1.0 + df as f32)).ln() + 1.0)
+                .collect(),
+        );
+        debug!("Fitted IDF weights from {} documents", documents_seen);
+    }
+
+    /// Whether an IDF table has been fitted
+    pub fn is_fitted(&self) -> bool {
+        self.idf.is_some()
+    }
+
+    /// Extract n-grams from a text
+    fn ngrams(&self, text: &str) -> Vec<String> {
+        let text = if self.lowercase { text.to_lowercase() } else { text.to_string() };
+        let words: Vec<&str> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        let mut ngrams = Vec::new();
+        match self.analyzer {
+            Analyzer::Word => {
+                for n in self.min_n..=self.max_n {
+                    for window in words.windows(n) {
+                        ngrams.push(window.join(" "));
+                    }
+                }
+            }
+            Analyzer::Char => {
+                for word in &words {
+                    let padded: Vec<char> = format!(" {} ", word).chars().collect();
+                    for n in self.min_n..=self.max_n {
+                        for window in padded.windows(n) {
+                            ngrams.push(window.iter().collect());
+                        }
+                    }
+                }
+            }
+        }
+        ngrams
+    }
+
+    /// Count n-grams per bucket, with signs applied when `alternate_sign` is set
+    fn term_counts(&self, text: &str) -> HashMap<usize, f32> {
+        let mut counts: HashMap<usize, f32> = HashMap::new();
+        for ngram in self.ngrams(text) {
+            let hash = fnv1a_64(ngram.as_bytes());
+            let bucket = (hash % self.dimension as u64) as usize;
+            let sign = if self.alternate_sign && (hash >> 63) == 1 { -1.0 } else { 1.0 };
+            *counts.entry(bucket).or_insert(0.0) += sign;
+        }
+        counts
+    }
+
+    /// Embed a single text
+    ///
+    /// Text without usable signal is rejected as invalid input: either it has
+    /// no alphanumeric terms, or its signed terms cancel out to a zero vector.
+    pub fn embed_text(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        let counts = self.term_counts(text);
+        if counts.is_empty() {
+            return Err(EmbeddingError::InvalidInput {
+                message: "No lexical terms found in input text".to_string(),
+            });
+        }
+
+        let mut vector = vec![0.0f32; self.dimension];
+        for (bucket, count) in counts {
+            let tf = if self.sublinear_tf && count.abs() > 0.0 {
+                count.signum() * (1.0 + count.abs().ln())
+            } else {
+                count
+            };
+            let idf = self.idf.as_ref().map(|idf| idf[bucket]).unwrap_or(1.0);
+            vector[bucket] = tf * idf;
+        }
+
+        // Colliding terms of opposite sign can cancel out, leaving nothing to normalize or compare
+        if vector.iter().all(|&value| value == 0.0) {
+            return Err(EmbeddingError::InvalidInput {
+                message: "Lexical terms of input text cancel out to a zero vector".to_string(),
+            });
+        }
+
+        if self.normalize {
+            pooling::normalize_embedding(&vector)
+        } else {
+            Ok(vector)
+        }
+    }
+
+    /// Embed a batch of texts
+    pub fn embed_texts(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        if texts.is_empty() {
+            return Err(EmbeddingError::InvalidInput {
+                message: "Cannot embed empty text list".to_string(),
+            });
+        }
+
+        texts.iter().map(|text| self.embed_text(text)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(mode: &str) -> LexicalConfig {
+        LexicalConfig {
+            mode: mode.to_string(),
+            ngram_range: [1, 2],
+            ..Default::default()
+        }
+    }
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[test]
+    fn test_hashing_vectors() {
+        let engine = LexicalEmbeddingEngine::new(&config("hashing"), 1024).unwrap();
+        let a = engine.embed_text("The quick brown fox").unwrap();
+        let b = engine.embed_text("the QUICK brown fox!").unwrap();
+        let c = engine.embed_text("completely unrelated words").unwrap();
+
+        assert_eq!(a.len(), 1024);
+        assert_eq!(a, b);
+        assert!(cosine(&a, &c) < cosine(&a, &b));
+        assert!(matches!(engine.embed_text("  ...  "), Err(EmbeddingError::InvalidInput { .. })));
+    }
+
+    #[test]
+    fn test_tfidf_downweights_common_terms() {
+        let mut engine = LexicalEmbeddingEngine::unfitted(&config("tfidf"), 4096).unwrap();
+        engine.fit(["the cat", "the dog", "the bird", "a rare zebra"].into_iter());
+        assert!(engine.is_fitted());
+
+        let query = engine.embed_text("the zebra").unwrap();
+        let common = engine.embed_text("the").unwrap();
+        let rare = engine.embed_text("zebra").unwrap();
+        assert!(cosine(&query, &rare) > cosine(&query, &common));
+    }
+
+    #[test]
+    fn test_cancelled_terms_are_invalid_input() {
+        let signed = LexicalConfig { ngram_range: [1, 1], alternate_sign: true, ..Default::default() };
+        let engine = LexicalEmbeddingEngine::new(&signed, 1).unwrap();
+        // With one bucket, a positive and a negative term cancel out
+        let words = ["cat", "dog", "fox", "bird", "zebra", "tree", "sun"];
+        let sign = |word: &&str| engine.term_counts(word)[&0];
+        let positive = words.iter().find(|word| sign(word) > 0.0).unwrap();
+        let negative = words.iter().find(|word| sign(word) < 0.0).unwrap();
+        assert!(matches!(
+            engine.embed_text(&format!("{} {}", positive, negative)),
+            Err(EmbeddingError::InvalidInput { .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        assert!(LexicalEmbeddingEngine::new(&config("bm25"), 16).is_err());
+        assert!(LexicalEmbeddingEngine::new(&config("tfidf"), 16).is_err());
+        let bad_range = LexicalConfig { ngram_range: [2, 1], ..Default::default() };
+        assert!(LexicalEmbeddingEngine::new(&bad_range, 16).is_err());
+    }
+}
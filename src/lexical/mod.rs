@@ -0,0 +1,6 @@
+//! Lexical Embedding Module for Embedding Server
+//!
+//! This module provides feature-hashing and TF-IDF embedding functionality
+
+pub mod lexical_engine;
+pub use lexical_engine::{Analyzer, LexicalEmbeddingEngine};
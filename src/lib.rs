@@ -2,10 +2,15 @@
 //!
 //! Standalone TCP server for high-performance embedding generation
 
+#[cfg(feature = "candle")]
+pub mod candle;
+pub mod lexical;
 pub mod models;
+#[cfg(feature = "onnx")]
 pub mod onnx;
 pub mod protocol;
 pub mod server;
+pub mod static_embedding;
 
 // Re-exports
 pub use models::{EmbeddingModelsManager, EmbeddingError, Embedding};
@@ -3,14 +3,20 @@
 //! Entry point for the standalone embedding server
 //! Runs TCP (OVNT protocol) and ULTRA-FAST Hyper HTTP servers concurrently
 
-use embedding_server::{EmbeddingServer, ServerConfig, start_hyper_http_server};
+use embedding_server::models::projection::ProjectionFitRequest;
+use embedding_server::{EmbeddingModelsManager, EmbeddingServer, ServerConfig, start_hyper_http_server};
 use std::sync::Arc;
 use tracing_subscriber;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load configuration
-    let config = ServerConfig::from_file("config.toml")?;
+    // Self-contained builds run with defaults when no config.toml is deployed
+    let config = if cfg!(feature = "embedded-model") && !std::path::Path::new("config.toml").exists() {
+        ServerConfig::default()
+    } else {
+        ServerConfig::from_file("config.toml")?
+    };
     
     // Initialize tracing
     let log_level = std::env::var("RUST_LOG")
@@ -37,6 +43,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .without_time()
         .init();
 
+    // `fit-projection` runs the offline fitting tool instead of the server
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("fit-projection") {
+        return fit_projection(&config, &args[1..]).await;
+    }
+
     println!("🚀 Dual-Channel Embedding Server");
     println!("📊 Log Level: {}", config.monitoring.log_level);
     println!("===============================");
@@ -73,3 +85,49 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     tcp_result
 }
+
+/// Fit a projection for one model and write it to disk
+///
+/// Usage: `fit-projection --corpus <file> --output <file.safetensors>
+/// [--model <name>] [--dim <n>] [--whiten] [--max-texts <n>]`
+async fn fit_projection(config: &ServerConfig, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut request = ProjectionFitRequest {
+        model: None,
+        corpus_path: String::new(),
+        output_path: String::new(),
+        output_dimension: None,
+        whiten: false,
+        max_texts: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("{} needs a value", flag));
+        match flag.as_str() {
+            "--corpus" => request.corpus_path = value()?,
+            "--output" => request.output_path = value()?,
+            "--model" => request.model = Some(value()?),
+            "--dim" => request.output_dimension = Some(value()?.parse()?),
+            "--max-texts" => request.max_texts = Some(value()?.parse()?),
+            "--whiten" => request.whiten = true,
+            other => return Err(format!("Unknown fit-projection option '{}'", other).into()),
+        }
+    }
+    if request.corpus_path.is_empty() || request.output_path.is_empty() {
+        return Err("fit-projection needs --corpus and --output".into());
+    }
+
+    // Load only the model being fitted
+    let manager = EmbeddingModelsManager::from_config_file(&config.embedding.models_config)?;
+    let model_name = request.model.clone().unwrap_or_else(|| manager.config().global.default_model.clone());
+    manager.load_model(&model_name).await?;
+
+    let report = manager.fit_projection(&request).await?;
+    println!("✅ Fitted projection for {}", report.model);
+    println!("   {} -> {} dimensions from {} texts", report.input_dimension, report.output_dimension, report.samples);
+    println!("   Explained variance: {:.1}%", report.explained_variance * 100.0);
+    println!("   Written to {}", report.output_path);
+    println!("   Add it to embeddingmodels.toml under [models.<id>.projection] path = \"{}\"", report.output_path);
+    Ok(())
+}
+
@@ -3,6 +3,8 @@
 //! This module handles loading and parsing the embeddingmodels.toml configuration
 //! and provides structured access to model settings.
 
+use crate::models::identity::ModelNames;
+use crate::models::registry::LoadPolicy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
@@ -12,8 +14,12 @@ use std::path::Path;
 pub struct EmbeddingModelsConfig {
     /// Global settings
     pub global: GlobalConfig,
-    /// Model-specific configurations
+    /// Model-specific configurations, keyed by model id
+    #[serde(default)]
     pub models: HashMap<String, ModelConfig>,
+    /// Deprecated model names and the model each now refers to
+    #[serde(default)]
+    pub model_redirects: HashMap<String, String>,
     /// Model groups
     #[serde(default)]
     pub model_groups: ModelGroups,
@@ -26,6 +32,9 @@ pub struct EmbeddingModelsConfig {
     /// Development settings
     #[serde(default)]
     pub development: DevelopmentConfig,
+    /// Every accepted model name, built when the configuration is loaded
+    #[serde(skip)]
+    names: ModelNames,
 }
 
 impl Default for EmbeddingModelsConfig {
@@ -38,12 +47,16 @@ impl Default for EmbeddingModelsConfig {
                 cache_size_mb: 512,
                 init_timeout: 300,
                 inference_timeout: 60,
+                memory_budget_mb: None,
+                load_parallelism: default_load_parallelism(),
             },
             models: HashMap::new(),
+            model_redirects: HashMap::new(),
             model_groups: ModelGroups::default(),
             monitoring: MonitoringConfig::default(),
             error_handling: ErrorHandlingConfig::default(),
             development: DevelopmentConfig::default(),
+            names: ModelNames::default(),
         }
     }
 }
@@ -61,20 +74,40 @@ pub struct GlobalConfig {
     /// Timeout settings (seconds)
     pub init_timeout: u64,
     pub inference_timeout: u64,
+    /// Memory the loaded models may use together; least recently used
+    /// unpinned models are evicted to stay within it
+    #[serde(default)]
+    pub memory_budget_mb: Option<u64>,
+    /// Models loaded concurrently at startup
+    #[serde(default = "default_load_parallelism")]
+    pub load_parallelism: usize,
+}
+
+fn default_load_parallelism() -> usize {
+    4
 }
 
 /// Configuration for a specific model
 #[derive(Debug, Clone, Deserialize)]
 pub struct ModelConfig {
+    /// Canonical id: the model's key under `[models]`
+    #[serde(skip)]
+    pub id: String,
     /// Model metadata
     pub name: String,
+    /// Other names clients may use for the model, such as compatibility names
+    #[serde(default)]
+    pub aliases: Vec<String>,
     pub description: String,
     pub version: String,
     pub enabled: bool,
 
     /// File paths (relative to EmbeddingModels directory)
+    #[serde(default)]
     pub model_path: String,
+    #[serde(default)]
     pub tokenizer_path: String,
+    #[serde(default)]
     pub config_path: String,
 
     /// Model parameters
@@ -87,9 +120,331 @@ pub struct ModelConfig {
     pub use_gpu: bool,
     pub num_threads: usize,
 
-    /// Runtime settings
+    /// Runtime settings (only used by the ONNX backend)
+    #[serde(default)]
     pub onnx_runtime_path: String,
+    #[serde(default)]
     pub execution_provider: String,
+    /// Inference backend ("onnx", "candle", "static", "lexical", "mock" or "ensemble")
+    #[serde(default = "default_backend")]
+    pub backend: String,
+
+    /// Session settings for the ONNX backend
+    #[serde(default)]
+    pub onnx: OnnxSessionConfig,
+
+    /// Settings for the static lookup-table backend
+    #[serde(default)]
+    pub static_embedding: StaticEmbeddingConfig,
+
+    /// Settings for the lexical hashing / TF-IDF backend
+    #[serde(default)]
+    pub lexical: LexicalConfig,
+
+    /// Settings for the deterministic mock backend
+    #[serde(default)]
+    pub mock: MockConfig,
+
+    /// Text cleanup applied before tokenization, for every backend and protocol
+    #[serde(default)]
+    pub preprocessing: PreprocessingConfig,
+
+    /// Linear projection applied to pooled embeddings (PCA, whitening)
+    #[serde(default)]
+    pub projection: ProjectionConfig,
+
+    /// Member models and combination for the ensemble backend
+    #[serde(default)]
+    pub ensemble: EnsembleConfig,
+
+    /// Dump intermediate values for this model's text requests (see `development.intermediates`)
+    #[serde(default)]
+    pub save_intermediates: bool,
+
+    /// Requests run after loading, before the model is reported ready
+    #[serde(default)]
+    pub warmup: WarmupConfig,
+
+    /// Single-threaded, reproducible ONNX Runtime execution
+    #[serde(default)]
+    pub deterministic: bool,
+
+    /// When the model is loaded: "eager" (startup), "lazy" (first request) or
+    /// "on_demand" (first request, unloaded after `idle_timeout_secs` idle)
+    #[serde(default = "default_load_policy")]
+    pub load_policy: String,
+
+    /// Idle time after which an on-demand model is unloaded
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+
+    /// Never evict the model to stay within `global.memory_budget_mb`
+    #[serde(default)]
+    pub pinned: bool,
+
+    /// Memory the model needs, overriding the estimate measured at load
+    #[serde(default)]
+    pub memory_estimate_mb: Option<u64>,
+
+    /// Startup fails if this model cannot be loaded; other models that fail
+    /// are recorded and skipped. An eager default model is always required.
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl ModelConfig {
+    /// Canonical id, or the display name of a configuration not loaded from a file
+    pub fn id(&self) -> &str {
+        if self.id.is_empty() { &self.name } else { &self.id }
+    }
+}
+
+fn default_backend() -> String {
+    "onnx".to_string()
+}
+
+/// Cargo feature a backend needs, if this build was compiled without it
+fn missing_backend_feature(backend: &str) -> Option<&'static str> {
+    match backend {
+        "onnx" if !cfg!(feature = "onnx") => Some("onnx"),
+        "candle" if !cfg!(feature = "candle") => Some("candle"),
+        _ => None,
+    }
+}
+
+fn default_load_policy() -> String {
+    "eager".to_string()
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    900
+}
+
+/// ONNX session loading settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OnnxSessionConfig {
+    /// How model bytes reach ONNX Runtime: "file" (ORT reads the file),
+    /// "mmap" (memory-mapped, pages shared between sessions) or "memory"
+    pub load_mode: String,
+    /// External-data files holding the weights, relative to `model_path`.
+    /// Empty means `<model>.onnx_data` / `<model>.onnx.data` if present.
+    pub external_data: Vec<String>,
+    /// Directory for ORT-optimized graphs reused across restarts; unset disables the cache
+    pub optimized_cache_dir: Option<String>,
+}
+
+impl Default for OnnxSessionConfig {
+    fn default() -> Self {
+        Self {
+            load_mode: "file".to_string(),
+            external_data: Vec::new(),
+            optimized_cache_dir: None,
+        }
+    }
+}
+
+/// Static lookup-table backend settings (Model2Vec / GloVe style)
+///
+/// `model_path` points at the token-to-vector matrix (.safetensors or .npy),
+/// with one row per tokenizer id.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StaticEmbeddingConfig {
+    /// Tensor holding the matrix inside a safetensors file; by default the
+    /// file's only tensor, or "embeddings" when it has several
+    pub tensor_name: Option<String>,
+    /// Token weighting: "none", "sif" or "zipf"
+    pub weighting: String,
+    /// SIF smoothing coefficient `a` in `a / (a + p(w))`
+    pub sif_coefficient: f64,
+    /// L2-normalize the pooled vector
+    pub normalize: bool,
+}
+
+impl Default for StaticEmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            tensor_name: None,
+            weighting: "none".to_string(),
+            sif_coefficient: 1e-3,
+            normalize: true,
+        }
+    }
+}
+
+/// Lexical backend settings (feature hashing and TF-IDF)
+///
+/// The vector size is the model's `embedding_dimension`; n-grams are hashed
+/// into that many buckets.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LexicalConfig {
+    /// "hashing" (term frequencies) or "tfidf" (IDF fitted from `corpus_path`)
+    pub mode: String,
+    /// "word" n-grams or "char" n-grams within word boundaries
+    pub analyzer: String,
+    /// Inclusive n-gram range, e.g. [1, 2] for unigrams and bigrams
+    pub ngram_range: [usize; 2],
+    /// Lowercase text before extracting n-grams
+    pub lowercase: bool,
+    /// Use `1 + ln(tf)` instead of raw term counts
+    pub sublinear_tf: bool,
+    /// Flip the sign of half the buckets to reduce collision bias
+    pub alternate_sign: bool,
+    /// Vector normalization: "l2" or "none"
+    pub normalization: String,
+    /// Corpus for fitting IDF weights, one document per line (tfidf mode)
+    pub corpus_path: Option<String>,
+}
+
+impl Default for LexicalConfig {
+    fn default() -> Self {
+        Self {
+            mode: "hashing".to_string(),
+            analyzer: "word".to_string(),
+            ngram_range: [1, 1],
+            lowercase: true,
+            sublinear_tf: false,
+            alternate_sign: true,
+            normalization: "l2".to_string(),
+            corpus_path: None,
+        }
+    }
+}
+
+/// Mock backend settings
+///
+/// The mock backend returns deterministic, hash-derived unit vectors and needs
+/// no model files, so the server can be exercised in CI and by client teams.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct MockConfig {
+    /// Artificial latency added to every request (milliseconds)
+    pub latency_ms: u64,
+    /// Additional pseudo-random latency of up to this many milliseconds
+    pub latency_jitter_ms: u64,
+    /// Fraction of requests that fail, between 0.0 and 1.0
+    pub failure_rate: f64,
+    /// Seed mixed into the vectors and the failure/jitter sequence
+    pub seed: u64,
+    /// Time `initialize` blocks its thread, like a backend reading its model files (milliseconds)
+    pub init_delay_ms: u64,
+}
+
+/// Text preprocessing settings
+///
+/// Steps run in a fixed order: HTML stripping, Markdown stripping, Unicode
+/// normalization, control-character removal, lowercasing, whitespace
+/// collapsing and finally the character cap. Everything is off by default.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct PreprocessingConfig {
+    /// Unicode normalization form: "nfc" or "nfkc" (unset leaves text as is)
+    pub unicode_normalization: Option<String>,
+    /// Lowercase the text
+    pub lowercase: bool,
+    /// Remove HTML tags, script/style content and decode entities
+    pub strip_html: bool,
+    /// Remove Markdown markup (headings, emphasis, links, code fences)
+    pub strip_markdown: bool,
+    /// Remove control characters other than whitespace
+    pub remove_control_chars: bool,
+    /// Collapse runs of whitespace into single spaces and trim the ends
+    pub collapse_whitespace: bool,
+    /// Keep at most this many characters
+    pub max_chars: Option<usize>,
+}
+
+/// Post-pooling projection settings
+///
+/// `path` points at a `[embedding_dimension, output_dimension]` matrix. A
+/// `.safetensors` file may also carry the mean vector subtracted before
+/// projecting; with a `.npy` matrix the mean lives in its own file. The
+/// model then reports `output_dimension` as its dimension.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProjectionConfig {
+    /// Projection matrix file (.safetensors or .npy); unset disables projection
+    pub path: Option<String>,
+    /// Tensor holding the matrix inside a safetensors file
+    pub matrix_tensor: String,
+    /// Tensor holding the mean vector inside a safetensors file, if present
+    pub mean_tensor: String,
+    /// Mean vector file (.npy) for a `.npy` matrix
+    pub mean_path: Option<String>,
+    /// L2-normalize the projected vector
+    pub normalize: bool,
+}
+
+impl Default for ProjectionConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            matrix_tensor: "projection".to_string(),
+            mean_tensor: "mean".to_string(),
+            mean_path: None,
+            normalize: true,
+        }
+    }
+}
+
+/// Ensemble backend settings
+///
+/// An ensemble runs other configured models and combines their embeddings.
+/// Its dimension is computed from the members: the sum of their dimensions
+/// for "concat", their shared dimension for "average".
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EnsembleConfig {
+    /// Names of the member models
+    pub members: Vec<String>,
+    /// Weight of each member (equal weights when empty)
+    pub weights: Vec<f32>,
+    /// "concat" joins the member vectors, "average" takes their weighted mean
+    pub combine: String,
+    /// L2-normalize the combined vector
+    pub normalize: bool,
+}
+
+impl Default for EnsembleConfig {
+    fn default() -> Self {
+        Self {
+            members: Vec::new(),
+            weights: Vec::new(),
+            combine: "concat".to_string(),
+            normalize: true,
+        }
+    }
+}
+
+/// Warmup settings
+///
+/// The first inference pays the runtime's lazy allocations and kernel
+/// selection. Warmup runs one request per combination of sequence length and
+/// batch size before the model is made available, so real requests do not.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WarmupConfig {
+    /// Run warmup after loading
+    pub enabled: bool,
+    /// Approximate token counts of the synthetic warmup texts (capped at `max_sequence_length`)
+    pub sequence_lengths: Vec<usize>,
+    /// Batch sizes to warm up
+    pub batch_sizes: Vec<usize>,
+    /// File with one representative text per line, used instead of synthetic texts
+    pub sample_file: Option<String>,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sequence_lengths: vec![16, 128],
+            batch_sizes: vec![1, 8],
+            sample_file: None,
+        }
+    }
 }
 
 /// Model groups for different use cases
@@ -124,27 +479,158 @@ pub struct ErrorHandlingConfig {
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct DevelopmentConfig {
     pub debug_logging: bool,
+    /// Check every produced embedding (see `output_validation`)
     pub validate_outputs: bool,
+    /// Dump intermediate values for every model (see `intermediates`)
     pub save_intermediates: bool,
+    /// Thresholds for `validate_outputs`
+    #[serde(default)]
+    pub output_validation: OutputValidationConfig,
+    /// Where and how often intermediate values are dumped
+    #[serde(default)]
+    pub intermediates: IntermediatesConfig,
+}
+
+/// Intermediate dump settings
+///
+/// Dumps are taken for text requests when `save_intermediates` is set
+/// globally or on the model, or when a request carries the
+/// `X-Save-Intermediates` header and `allow_request_header` is on. Each dump
+/// is a directory holding `input.json` and `.npy` arrays.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IntermediatesConfig {
+    /// Directory receiving the dumps, one subdirectory per model
+    pub dump_dir: String,
+    /// Fraction of configured requests to dump, between 0.0 and 1.0 (header requests are always dumped)
+    pub sample_rate: f64,
+    /// Stop dumping after this many dumps
+    pub max_dumps: u64,
+    /// Stop dumping once the dumps reach this size (megabytes)
+    pub max_total_mb: u64,
+    /// Honor the `X-Save-Intermediates` request header
+    pub allow_request_header: bool,
+}
+
+impl Default for IntermediatesConfig {
+    fn default() -> Self {
+        Self {
+            dump_dir: "debug_dumps".to_string(),
+            sample_rate: 1.0,
+            max_dumps: 100,
+            max_total_mb: 256,
+            allow_request_header: false,
+        }
+    }
+}
+
+/// Output validation thresholds
+///
+/// Embeddings are checked for NaN/Inf values, a dimension other than the
+/// model's, an L2 norm outside `[min_norm, max_norm]`, and collapse: two
+/// distinct inputs in one batch whose vectors have a cosine similarity of at
+/// least `collapse_similarity`. Models without normalization need a wider
+/// norm range.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OutputValidationConfig {
+    /// Smallest accepted L2 norm
+    pub min_norm: f32,
+    /// Largest accepted L2 norm
+    pub max_norm: f32,
+    /// Cosine similarity at which vectors of distinct inputs count as collapsed
+    pub collapse_similarity: f32,
+    /// Stop serving a model after its first violation, until it is reloaded
+    pub quarantine: bool,
+}
+
+impl Default for OutputValidationConfig {
+    fn default() -> Self {
+        Self {
+            min_norm: 0.999,
+            max_norm: 1.001,
+            collapse_similarity: 0.9999,
+            quarantine: false,
+        }
+    }
 }
 
 impl EmbeddingModelsConfig {
     /// Load configuration from TOML file
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, crate::models::EmbeddingError> {
         let content = std::fs::read_to_string(path)?;
-        let config: EmbeddingModelsConfig = toml::from_str(&content)?;
-        Ok(config)
+        Self::from_str(&content)
     }
 
     /// Load configuration from string
     pub fn from_str(content: &str) -> Result<Self, crate::models::EmbeddingError> {
-        let config: EmbeddingModelsConfig = toml::from_str(content)?;
+        let mut config: EmbeddingModelsConfig = toml::from_str(content)?;
+        config.index_models();
+        Ok(config)
+    }
+
+    /// Configuration serving only the model compiled into the binary
+    #[cfg(feature = "embedded-model")]
+    pub fn embedded() -> Result<Self, crate::models::EmbeddingError> {
+        let model = crate::onnx::embedded::model_config()?;
+        let mut config = Self::default();
+        config.global.default_model = crate::onnx::embedded::MODEL_ID.to_string();
+        config.models.insert(crate::onnx::embedded::MODEL_ID.to_string(), model);
+        config.index_models();
         Ok(config)
     }
 
+    /// Register the model compiled into the binary unless the file defines the same id
+    #[cfg(feature = "embedded-model")]
+    pub fn add_embedded_model(&mut self) -> Result<(), crate::models::EmbeddingError> {
+        if !self.models.contains_key(crate::onnx::embedded::MODEL_ID) {
+            let model = crate::onnx::embedded::model_config()?;
+            self.models.insert(crate::onnx::embedded::MODEL_ID.to_string(), model);
+            self.index_models();
+        }
+        Ok(())
+    }
+
+    /// Set model ids from their keys and rewrite model references to ids
+    ///
+    /// `default_model`, `model_groups` and ensemble members may use any
+    /// accepted name; unknown names are left for `validate` to report.
+    fn index_models(&mut self) {
+        for (id, model) in &mut self.models {
+            model.id = id.clone();
+        }
+        self.names = ModelNames::new(&self.models, &self.model_redirects, &self.global.default_model);
+
+        let names = &self.names;
+        let canonical = |name: &mut String| {
+            if let Some(id) = names.resolve(name) {
+                *name = id.to_string();
+            }
+        };
+        canonical(&mut self.global.default_model);
+        for group in [&mut self.model_groups.general, &mut self.model_groups.multilingual,
+                      &mut self.model_groups.high_dim, &mut self.model_groups.gpu_models] {
+            group.iter_mut().for_each(canonical);
+        }
+        for model in self.models.values_mut() {
+            model.ensemble.members.iter_mut().for_each(canonical);
+        }
+    }
+
+    /// Canonical id of a model given its id, display name, alias or a deprecated name
+    ///
+    /// Names match case-insensitively, and "default" names the default model
+    /// unless a model claims it.
+    pub fn resolve_model(&self, name: &str) -> Option<&str> {
+        match self.models.get_key_value(name) {
+            Some((id, _)) => Some(id),
+            None => self.names.resolve(name),
+        }
+    }
+
     /// Get the default model configuration
     pub fn get_default_model(&self) -> Option<&ModelConfig> {
-        self.models.values().find(|model| model.name == self.global.default_model)
+        self.get_model(&self.global.default_model)
     }
 
     /// Get all enabled models
@@ -155,16 +641,22 @@ impl EmbeddingModelsConfig {
             .collect()
     }
 
-    /// Get model by name
+    /// Get model by id or any other accepted name (see `resolve_model`)
     pub fn get_model(&self, name: &str) -> Option<&ModelConfig> {
-        self.models.values().find(|model| model.name == name)
+        self.models.get(self.resolve_model(name)?)
     }
 
     /// Validate the configuration
     pub fn validate(&self) -> Result<(), crate::models::EmbeddingError> {
+        // Check that every model name refers to exactly one model
+        if let Some(conflict) = self.names.conflicts().first() {
+            return Err(crate::models::EmbeddingError::ConfigError {
+                message: format!("Invalid model name: {}", conflict),
+            });
+        }
+
         // Check that default model exists
-        let default_model_exists = self.models.values().any(|model| model.name == self.global.default_model);
-        if !default_model_exists {
+        if self.get_default_model().is_none() {
             return Err(crate::models::EmbeddingError::ConfigError {
                 message: format!("Default model '{}' not found in models", self.global.default_model),
             });
@@ -179,11 +671,38 @@ impl EmbeddingModelsConfig {
             }
         }
 
+        for model in self.models.values().filter(|model| model.enabled) {
+            if let Some(feature) = missing_backend_feature(&model.backend) {
+                return Err(crate::models::EmbeddingError::ConfigError {
+                    message: format!(
+                        "Model '{}' uses the {} backend{}, but this build does not include it; rebuild with `--features {}`",
+                        model.id(),
+                        model.backend,
+                        if model.backend == default_backend() { " (the default when `backend` is not set)" } else { "" },
+                        feature
+                    ),
+                });
+            }
+        }
+
+        for model in self.models.values() {
+            let policy = LoadPolicy::from_config(&model.load_policy)?;
+            if model.required && policy != LoadPolicy::Eager {
+                return Err(crate::models::EmbeddingError::ConfigError {
+                    message: format!("Required model '{}' must use the eager load policy", model.id()),
+                });
+            }
+        }
+
+        for model in self.models.values().filter(|model| model.backend == "ensemble") {
+            self.validate_ensemble(model)?;
+        }
+
         // Validate model groups reference existing models
         for group in [&self.model_groups.general, &self.model_groups.multilingual,
                      &self.model_groups.high_dim, &self.model_groups.gpu_models].iter() {
             for model_name in *group {
-                if !self.models.contains_key(model_name) {
+                if self.get_model(model_name).is_none() {
                     return Err(crate::models::EmbeddingError::ConfigError {
                         message: format!("Model '{}' in group not found in models", model_name),
                     });
@@ -193,6 +712,48 @@ impl EmbeddingModelsConfig {
 
         Ok(())
     }
+
+    /// Check that an ensemble references enabled, non-ensemble models
+    fn validate_ensemble(&self, model: &ModelConfig) -> Result<(), crate::models::EmbeddingError> {
+        let invalid = |message: String| crate::models::EmbeddingError::ConfigError {
+            message: format!("Ensemble '{}': {}", model.id(), message),
+        };
+        let ensemble = &model.ensemble;
+
+        if ensemble.members.is_empty() {
+            return Err(invalid("no members configured".to_string()));
+        }
+        if !ensemble.weights.is_empty() && ensemble.weights.len() != ensemble.members.len() {
+            return Err(invalid(format!(
+                "{} weights for {} members",
+                ensemble.weights.len(), ensemble.members.len()
+            )));
+        }
+        if ensemble.weights.iter().any(|weight| !weight.is_finite() || *weight < 0.0) {
+            return Err(invalid("weights must be finite and non-negative".to_string()));
+        }
+        if let Err(e) = crate::models::model::ensemble::CombineMode::from_config(&ensemble.combine) {
+            return Err(invalid(e.to_string()));
+        }
+
+        for member in &ensemble.members {
+            match self.get_model(member) {
+                None => return Err(invalid(format!("member '{}' not found in models", member))),
+                Some(config) if !config.enabled => {
+                    return Err(invalid(format!("member '{}' is not enabled", member)));
+                }
+                Some(config) if config.backend == "ensemble" => {
+                    return Err(invalid(format!("member '{}' is itself an ensemble", member)));
+                }
+                Some(config) if LoadPolicy::from_config(&config.load_policy).ok() == Some(LoadPolicy::OnDemand) => {
+                    return Err(invalid(format!("member '{}' is on_demand, but ensembles keep their members loaded", member)));
+                }
+                Some(_) => {}
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -229,7 +790,8 @@ mod tests {
         "#;
 
         let config = EmbeddingModelsConfig::from_str(config_str).unwrap();
-        assert!(config.validate().is_ok());
+        // The model uses the default onnx backend
+        assert_eq!(config.validate().is_ok(), cfg!(feature = "onnx"));
     }
 
     #[test]
@@ -247,4 +809,61 @@ mod tests {
         let config = EmbeddingModelsConfig::from_str(config_str).unwrap();
         assert!(config.validate().is_err());
     }
+
+    #[test]
+    fn test_model_references_resolve_to_ids() {
+        let config_with = |aliases: &str| EmbeddingModelsConfig::from_str(&format!(r#"
+            [global]
+            default_model = "Test Model"
+            max_batch_size = 32
+            cache_enabled = true
+            cache_size_mb = 512
+            init_timeout = 300
+            inference_timeout = 60
+
+            [models.test-model]
+            name = "Test Model"
+            description = "A test model"
+            version = "1.0.0"
+            enabled = true
+            max_sequence_length = 256
+            embedding_dimension = 384
+            pooling_mode = "mean"
+            batch_size = 16
+            use_gpu = false
+            num_threads = 4
+            backend = "mock"
+            aliases = [{}]
+
+            [models.other-model]
+            name = "Other Model"
+            description = "Another test model"
+            version = "1.0.0"
+            enabled = true
+            max_sequence_length = 256
+            embedding_dimension = 384
+            pooling_mode = "mean"
+            batch_size = 16
+            use_gpu = false
+            num_threads = 4
+            backend = "mock"
+
+            [model_groups]
+            general = ["TEST MODEL", "other-model"]
+            multilingual = []
+            high_dim = []
+            gpu_models = []
+        "#, aliases)).unwrap();
+
+        let config = config_with(r#""text-embedding-3-small""#);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.global.default_model, "test-model");
+        assert_eq!(config.model_groups.general, vec!["test-model", "other-model"]);
+        assert_eq!(config.get_model("Text-Embedding-3-Small").map(ModelConfig::id), Some("test-model"));
+        assert_eq!(config.resolve_model("default"), Some("test-model"));
+        assert_eq!(config.get_default_model().unwrap().name, "Test Model");
+
+        // An alias may not take another model's name
+        assert!(config_with(r#""other model""#).validate().is_err());
+    }
 }
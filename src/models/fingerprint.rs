@@ -0,0 +1,65 @@
+//! Output fingerprints
+//!
+//! A fingerprint is a hash of the embeddings a model produces for a fixed set
+//! of canary texts, computed when the model loads. Two deployments with the
+//! same fingerprint produce compatible vectors, so operators can check hosts
+//! and versions before mixing their vectors in one index.
+//!
+//! Values are rounded to four decimals before hashing, which absorbs the
+//! last-bit differences of SIMD paths and thread scheduling. A value that
+//! lands on a rounding boundary can still flip the hash; models with
+//! `deterministic = true` avoid that on a given host and build.
+
+use crate::models::hashing;
+use crate::models::model::EmbeddingModel;
+use crate::models::EmbeddingResult;
+
+/// Canary texts embedded to compute the fingerprint; never change these
+pub const CANARY_TEXTS: [&str; 5] = [
+    "The quick brown fox jumps over the lazy dog.",
+    "Embeddings map text to vectors.",
+    "HelixDB stores graphs and vectors together.",
+    "Ein kurzer deutscher Satz.",
+    "1234567890",
+];
+
+/// Scale applied before rounding values to integers
+const QUANTIZATION: f32 = 10_000.0;
+
+/// Embed the canary texts one by one and hash the results
+pub async fn compute(model: &dyn EmbeddingModel) -> EmbeddingResult<String> {
+    let mut embeddings = Vec::with_capacity(CANARY_TEXTS.len());
+    for text in CANARY_TEXTS {
+        embeddings.push(model.embed_text(text).await?);
+    }
+    Ok(fingerprint(&embeddings))
+}
+
+/// Hash of quantized embeddings, as 16 hex digits
+pub fn fingerprint(embeddings: &[Vec<f32>]) -> String {
+    let hash = embeddings.iter().fold(hashing::FNV1A_64_OFFSET_BASIS, |hash, embedding| {
+        let hash = hashing::fnv1a_64_extend(hash, &(embedding.len() as u64).to_le_bytes());
+        embedding.iter().fold(hash, |hash, value| {
+            let quantized = (value * QUANTIZATION).round() as i32;
+            hashing::fnv1a_64_extend(hash, &quantized.to_le_bytes())
+        })
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_tolerates_rounding_noise() {
+        let embeddings = vec![vec![0.6, 0.8], vec![1.0, 0.0]];
+        let noisy = vec![vec![0.6 + 1e-7, 0.8 - 1e-7], vec![1.0, 0.0]];
+        let different = vec![vec![0.8, 0.6], vec![1.0, 0.0]];
+
+        assert_eq!(fingerprint(&embeddings).len(), 16);
+        assert_eq!(fingerprint(&embeddings), fingerprint(&noisy));
+        assert_ne!(fingerprint(&embeddings), fingerprint(&different));
+        assert_ne!(fingerprint(&embeddings), fingerprint(&embeddings[..1]));
+    }
+}
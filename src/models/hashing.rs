@@ -0,0 +1,48 @@
+//! Stable hashing helpers
+//!
+//! `std::hash` makes no stability guarantees across Rust versions, but
+//! feature hashing and the mock backend need values that never change
+//! between builds or hosts, so they use these instead.
+
+/// FNV-1a initial state, the hash of an empty input
+pub const FNV1A_64_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// 64-bit FNV-1a hash of a byte string
+pub fn fnv1a_64(bytes: &[u8]) -> u64 {
+    fnv1a_64_extend(FNV1A_64_OFFSET_BASIS, bytes)
+}
+
+/// Continue an FNV-1a hash with more bytes, for inputs hashed in chunks
+pub fn fnv1a_64_extend(hash: u64, bytes: &[u8]) -> u64 {
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
+
+/// One step of the SplitMix64 generator; returns the next output and advances `state`
+pub fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_reference_values() {
+        assert_eq!(fnv1a_64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a_64_extend(fnv1a_64(b"ab"), b"c"), fnv1a_64(b"abc"));
+    }
+
+    #[test]
+    fn test_splitmix_is_deterministic() {
+        let (mut a, mut b) = (42, 42);
+        assert_eq!(splitmix64(&mut a), splitmix64(&mut b));
+        assert_ne!(splitmix64(&mut a), splitmix64(&mut 43));
+    }
+}
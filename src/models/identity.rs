@@ -0,0 +1,135 @@
+//! Model identity
+//!
+//! A model's canonical id is its key under `[models]`, and the registry,
+//! metrics and APIs report models by that id. Clients, `default_model`,
+//! `model_groups` and ensemble members may also name a model by its display
+//! `name`, one of its `aliases`, or a deprecated name redirected to it under
+//! `[model_redirects]`. Names match case-insensitively, and `default` names
+//! the default model unless a model claims it.
+
+use crate::models::config::ModelConfig;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Name used for the default model
+pub const DEFAULT_ALIAS: &str = "default";
+
+/// Lookup from every accepted model name to the canonical id
+#[derive(Debug, Clone, Default)]
+pub struct ModelNames {
+    /// Lowercased id, display name or alias to id
+    names: HashMap<String, String>,
+    /// Lowercased deprecated name to id
+    redirects: HashMap<String, String>,
+    /// Names claimed by more than one model, reported by validation
+    conflicts: Vec<String>,
+    /// Deprecated names already warned about
+    warned: Arc<Mutex<HashSet<String>>>,
+}
+
+impl ModelNames {
+    /// Index the models by id, display name and aliases
+    ///
+    /// Ids are indexed first, so a display name or alias equal to another
+    /// model's id is reported as a conflict instead of shadowing it.
+    pub fn new(
+        models: &HashMap<String, ModelConfig>,
+        redirects: &HashMap<String, String>,
+        default_model: &str,
+    ) -> Self {
+        let mut index = Self::default();
+        for id in models.keys() {
+            index.claim(id, id);
+        }
+        for (id, model) in models {
+            for name in std::iter::once(&model.name).chain(&model.aliases) {
+                index.claim(name, id);
+            }
+        }
+
+        if let Some(default_id) = index.lookup(default_model).map(str::to_string) {
+            index.names.entry(DEFAULT_ALIAS.to_string()).or_insert(default_id);
+        }
+        for (old, target) in redirects {
+            match index.lookup(target).map(str::to_string) {
+                Some(id) if !index.names.contains_key(&old.to_lowercase()) => {
+                    index.redirects.insert(old.to_lowercase(), id);
+                }
+                Some(_) => index.conflicts.push(format!("deprecated name '{}' is still in use by a model", old)),
+                None => index.conflicts.push(format!("deprecated name '{}' redirects to unknown model '{}'", old, target)),
+            }
+        }
+        index
+    }
+
+    /// Canonical id for a name, following deprecated redirects with a warning
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        if let Some(id) = self.lookup(name) {
+            return Some(id);
+        }
+        let id = self.redirects.get(&name.to_lowercase())?;
+        if self.warned.lock().unwrap().insert(name.to_lowercase()) {
+            tracing::warn!("⚠️ Model name '{}' is deprecated; use '{}'", name, id);
+        }
+        Some(id)
+    }
+
+    /// Names claimed by more than one model or redirect
+    pub fn conflicts(&self) -> &[String] {
+        &self.conflicts
+    }
+
+    fn lookup(&self, name: &str) -> Option<&str> {
+        self.names.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    fn claim(&mut self, name: &str, id: &str) {
+        match self.names.get(&name.to_lowercase()) {
+            Some(existing) if existing != id => {
+                self.conflicts.push(format!("'{}' names both '{}' and '{}'", name, existing, id));
+            }
+            Some(_) => {}
+            None => {
+                self.names.insert(name.to_lowercase(), id.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(name: &str, aliases: &[&str]) -> ModelConfig {
+        let mut config = crate::models::model::mock::mock_model_config(name, "");
+        config.aliases = aliases.iter().map(|alias| alias.to_string()).collect();
+        config
+    }
+
+    #[test]
+    fn test_resolves_ids_names_aliases_and_redirects() {
+        let models = HashMap::from([
+            ("all-MiniLM-L6-v2".to_string(), model("All MiniLM L6 v2", &["text-embedding-3-small"])),
+            ("bge-small".to_string(), model("BGE Small", &[])),
+        ]);
+        let redirects = HashMap::from([("minilm-v1".to_string(), "All MiniLM L6 v2".to_string())]);
+        let names = ModelNames::new(&models, &redirects, "all minilm l6 v2");
+
+        for name in ["all-MiniLM-L6-v2", "ALL-minilm-l6-v2", "All MiniLM L6 v2", "Text-Embedding-3-Small", "default", "minilm-v1"] {
+            assert_eq!(names.resolve(name), Some("all-MiniLM-L6-v2"), "{}", name);
+        }
+        assert_eq!(names.resolve("bge small"), Some("bge-small"));
+        assert_eq!(names.resolve("unknown"), None);
+        assert!(names.conflicts().is_empty());
+
+        // Names claimed twice, and redirects to nowhere, are reported
+        let models = HashMap::from([
+            ("a".to_string(), model("Shared", &[])),
+            ("b".to_string(), model("shared", &["a"])),
+        ]);
+        let redirects = HashMap::from([("old".to_string(), "missing".to_string())]);
+        let names = ModelNames::new(&models, &redirects, "a");
+        assert_eq!(names.resolve("a"), Some("a"));
+        assert_eq!(names.conflicts().len(), 3);
+    }
+}
@@ -0,0 +1,225 @@
+//! Intermediate value dumps for debugging
+//!
+//! When scores look off, the quickest check is to diff each stage against
+//! the Python sentence-transformers reference: tokenization, the raw
+//! `last_hidden_state`, the pooled vector and the final embedding. Models
+//! capture these through `EmbeddingModel::embed_text_traced`, and the
+//! dumper writes them as `input.json` plus `.npy` arrays that load directly
+//! with `np.load`.
+
+use crate::models::config::IntermediatesConfig;
+use crate::models::weights::{self, Matrix};
+use crate::models::{Embedding, EmbeddingError, EmbeddingResult};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Values captured while embedding one text
+///
+/// Backends fill in what they can observe; a lookup-table model has no
+/// hidden state, for example.
+#[derive(Debug, Clone, Default)]
+pub struct Intermediates {
+    /// Text as received
+    pub text: String,
+    /// Text after the model's preprocessing chain, when one is configured
+    pub preprocessed_text: Option<String>,
+    /// Token ids fed to the model
+    pub token_ids: Option<Vec<u32>>,
+    /// Attention mask fed to the model
+    pub attention_mask: Option<Vec<u32>>,
+    /// Raw `last_hidden_state`, one row per token
+    pub hidden_state: Option<Matrix>,
+    /// Pooled vector before normalization
+    pub pooled: Option<Embedding>,
+    /// Embedding returned to the client
+    pub embedding: Embedding,
+}
+
+/// Contents of `input.json`
+#[derive(Serialize)]
+struct DumpManifest<'a> {
+    model: &'a str,
+    text: &'a str,
+    preprocessed_text: Option<&'a str>,
+    token_ids: Option<&'a [u32]>,
+    attention_mask: Option<&'a [u32]>,
+    hidden_state_shape: Option<[usize; 2]>,
+    pooled_dimension: Option<usize>,
+    embedding_dimension: usize,
+}
+
+/// Writes intermediate dumps within the configured sampling rate and caps
+#[derive(Debug)]
+pub struct IntermediateDumper {
+    config: IntermediatesConfig,
+    /// Requests offered for sampling
+    offered: AtomicU64,
+    /// Dumps written
+    dumps: AtomicU64,
+    /// Bytes written
+    bytes: AtomicU64,
+}
+
+impl IntermediateDumper {
+    /// Create a dumper
+    pub fn new(config: IntermediatesConfig) -> Self {
+        Self {
+            config,
+            offered: AtomicU64::new(0),
+            dumps: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether per-request dumps may be asked for with a header
+    pub fn allows_request_header(&self) -> bool {
+        self.config.allow_request_header
+    }
+
+    /// Decide whether a request eligible through configuration is dumped
+    ///
+    /// Sampling is deterministic: with a rate of 0.25 every fourth request is
+    /// dumped, which keeps dumps spread evenly over a run.
+    pub fn sample(&self) -> bool {
+        let rate = self.config.sample_rate.clamp(0.0, 1.0);
+        let n = self.offered.fetch_add(1, Ordering::Relaxed) as f64;
+        ((n + 1.0) * rate).floor() > (n * rate).floor()
+    }
+
+    /// Write one dump, returning its directory
+    ///
+    /// Returns `None` once `max_dumps` or `max_total_mb` would be exceeded.
+    pub fn write(&self, model_name: &str, intermediates: &Intermediates) -> EmbeddingResult<Option<PathBuf>> {
+        let mut files: Vec<(&str, Vec<u8>)> = Vec::new();
+        if let Some(hidden_state) = &intermediates.hidden_state {
+            files.push(("hidden_state.npy", weights::to_npy(&[hidden_state.rows, hidden_state.cols], &hidden_state.data)));
+        }
+        if let Some(pooled) = &intermediates.pooled {
+            files.push(("pooled.npy", weights::to_npy(&[pooled.len()], pooled)));
+        }
+        files.push(("embedding.npy", weights::to_npy(&[intermediates.embedding.len()], &intermediates.embedding)));
+
+        let manifest = DumpManifest {
+            model: model_name,
+            text: &intermediates.text,
+            preprocessed_text: intermediates.preprocessed_text.as_deref(),
+            token_ids: intermediates.token_ids.as_deref(),
+            attention_mask: intermediates.attention_mask.as_deref(),
+            hidden_state_shape: intermediates.hidden_state.as_ref().map(|hidden| [hidden.rows, hidden.cols]),
+            pooled_dimension: intermediates.pooled.as_ref().map(Vec::len),
+            embedding_dimension: intermediates.embedding.len(),
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest).map_err(|e| EmbeddingError::EmbeddingFailed {
+            error: format!("Failed to serialize dump manifest: {}", e),
+        })?;
+        files.push(("input.json", manifest));
+
+        let size: u64 = files.iter().map(|(_, bytes)| bytes.len() as u64).sum();
+        let Some(sequence) = self.reserve(size) else {
+            return Ok(None);
+        };
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis())
+            .unwrap_or_default();
+        let dir = Path::new(&self.config.dump_dir)
+            .join(sanitize(model_name))
+            .join(format!("{}-{:05}", timestamp, sequence));
+
+        std::fs::create_dir_all(&dir)?;
+        for (name, bytes) in files {
+            std::fs::write(dir.join(name), bytes)?;
+        }
+        Ok(Some(dir))
+    }
+
+    /// Claim room for one dump of `size` bytes, returning its sequence number
+    fn reserve(&self, size: u64) -> Option<u64> {
+        let max_bytes = self.config.max_total_mb.saturating_mul(1024 * 1024);
+        self.bytes
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bytes| {
+                (bytes + size <= max_bytes).then_some(bytes + size)
+            })
+            .ok()?;
+
+        let max_dumps = self.config.max_dumps;
+        let sequence = self.dumps.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |dumps| {
+            (dumps < max_dumps).then_some(dumps + 1)
+        });
+        if sequence.is_err() {
+            self.bytes.fetch_sub(size, Ordering::Relaxed);
+        }
+        sequence.ok()
+    }
+}
+
+/// Model names become directory names
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dumper(dir: &Path, extra: impl FnOnce(&mut IntermediatesConfig)) -> IntermediateDumper {
+        let mut config = IntermediatesConfig {
+            dump_dir: dir.to_str().unwrap().to_string(),
+            ..Default::default()
+        };
+        extra(&mut config);
+        IntermediateDumper::new(config)
+    }
+
+    #[test]
+    fn test_writes_dump_files() {
+        let dir = std::env::temp_dir().join(format!("intermediates-{}", std::process::id()));
+        let intermediates = Intermediates {
+            text: "hello".to_string(),
+            token_ids: Some(vec![101, 7592, 102]),
+            attention_mask: Some(vec![1, 1, 1]),
+            hidden_state: Some(Matrix::new(3, 2, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap()),
+            pooled: Some(vec![3.0, 4.0]),
+            embedding: vec![0.6, 0.8],
+            ..Default::default()
+        };
+
+        let dump = dumper(&dir, |_| {}).write("My Model", &intermediates).unwrap().unwrap();
+        assert!(dump.starts_with(dir.join("My_Model")));
+        let hidden = weights::load_matrix(dump.join("hidden_state.npy"), None).unwrap();
+        assert_eq!((hidden.rows, hidden.cols), (3, 2));
+        assert_eq!(weights::load_matrix(dump.join("embedding.npy"), None).unwrap().data, vec![0.6, 0.8]);
+        let manifest: serde_json::Value = serde_json::from_slice(&std::fs::read(dump.join("input.json")).unwrap()).unwrap();
+        assert_eq!(manifest["token_ids"], serde_json::json!([101, 7592, 102]));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_caps_and_sampling() {
+        let dir = std::env::temp_dir().join(format!("intermediates-caps-{}", std::process::id()));
+        let intermediates = Intermediates {
+            text: "hello".to_string(),
+            embedding: vec![1.0],
+            ..Default::default()
+        };
+
+        let capped = dumper(&dir, |config| config.max_dumps = 2);
+        assert!(capped.write("m", &intermediates).unwrap().is_some());
+        assert!(capped.write("m", &intermediates).unwrap().is_some());
+        assert!(capped.write("m", &intermediates).unwrap().is_none());
+
+        let tiny = dumper(&dir, |config| config.max_total_mb = 0);
+        assert!(tiny.write("m", &intermediates).unwrap().is_none());
+
+        let quarter = dumper(&dir, |config| config.sample_rate = 0.25);
+        let sampled = (0..8).filter(|_| quarter.sample()).count();
+        assert_eq!(sampled, 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
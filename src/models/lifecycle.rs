@@ -0,0 +1,271 @@
+//! Model lifecycle states
+//!
+//! The registry tracks every configured model through a small state machine.
+//! A load moves a model from Configured through Loading and WarmingUp to
+//! Ready, or to Failed. Output validation can degrade a ready model, and
+//! unloading drains it before it becomes Unloaded. Failed, Degraded,
+//! Unloaded and Ready models can be loaded again.
+//!
+//! Each transition is timestamped and the most recent ones are kept, so
+//! operators can see why a model is not serving and since when. Requests for
+//! a model that is not ready get an error naming its state.
+
+use crate::models::EmbeddingError;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Transitions kept per model
+const HISTORY_LIMIT: usize = 32;
+
+/// Retry hint for loads without an earlier load time to go by, and for draining models
+const DEFAULT_RETRY: Duration = Duration::from_secs(1);
+
+/// Where a model is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelState {
+    /// Enabled in the configuration, not loaded yet
+    Configured,
+    /// Files are being read and the runtime session created
+    Loading,
+    /// Running warmup requests and the output fingerprint
+    WarmingUp,
+    /// Serving requests
+    Ready,
+    /// Loaded, but taken out of service by output validation until reloaded
+    Degraded,
+    /// Unloading; waiting for requests in flight to finish
+    Draining,
+    /// The last load failed
+    Failed,
+    /// Unloaded explicitly, for idleness or to stay within the memory budget
+    Unloaded,
+}
+
+impl ModelState {
+    /// Name used in the HTTP and OVNT APIs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelState::Configured => "configured",
+            ModelState::Loading => "loading",
+            ModelState::WarmingUp => "warming_up",
+            ModelState::Ready => "ready",
+            ModelState::Degraded => "degraded",
+            ModelState::Draining => "draining",
+            ModelState::Failed => "failed",
+            ModelState::Unloaded => "unloaded",
+        }
+    }
+
+    /// Whether the state machine allows moving from this state to `next`
+    pub fn can_become(self, next: ModelState) -> bool {
+        use ModelState::*;
+        matches!(
+            (self, next),
+            (Configured | Failed | Unloaded | Ready | Degraded, Loading)
+                | (Loading, WarmingUp | Ready | Failed)
+                | (WarmingUp, Ready | Failed)
+                | (Ready, Degraded | Draining)
+                | (Degraded, Draining)
+                | (Draining, Unloaded)
+        )
+    }
+}
+
+impl fmt::Display for ModelState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.as_str().replace('_', " "))
+    }
+}
+
+/// One state change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateTransition {
+    pub state: ModelState,
+    /// Why the model failed or was degraded
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Milliseconds since the Unix epoch
+    pub at_unix_ms: u64,
+}
+
+/// Current state and recent transitions of a model, as reported by the APIs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelStatus {
+    pub name: String,
+    pub state: ModelState,
+    /// Why the model failed or was degraded
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// When the model entered its current state, in milliseconds since the Unix epoch
+    pub since_unix_ms: u64,
+    /// Recent transitions, oldest first
+    #[serde(default)]
+    pub history: Vec<StateTransition>,
+    /// Output fingerprint of the loaded model, when it could be computed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+}
+
+/// State machine of one model
+#[derive(Debug, Clone)]
+pub struct Lifecycle {
+    state: ModelState,
+    reason: Option<String>,
+    since: Instant,
+    /// Time from Loading to Ready of the last successful load
+    last_load: Option<Duration>,
+    history: VecDeque<StateTransition>,
+}
+
+impl Lifecycle {
+    /// A model that is configured but not loaded
+    pub fn new() -> Self {
+        let mut lifecycle = Self {
+            state: ModelState::Configured,
+            reason: None,
+            since: Instant::now(),
+            last_load: None,
+            history: VecDeque::new(),
+        };
+        lifecycle.record();
+        lifecycle
+    }
+
+    /// Current state
+    pub fn state(&self) -> ModelState {
+        self.state
+    }
+
+    /// Move to `next`, returning false if the state machine does not allow it
+    pub fn transition(&mut self, next: ModelState, reason: Option<String>) -> bool {
+        if !self.state.can_become(next) {
+            return false;
+        }
+        if next == ModelState::Ready && matches!(self.state, ModelState::Loading | ModelState::WarmingUp) {
+            // The load started when the model entered Loading
+            let loading_since = self.history
+                .iter()
+                .rev()
+                .find(|transition| transition.state == ModelState::Loading)
+                .map(|transition| transition.at_unix_ms);
+            self.last_load = loading_since.map(|started| Duration::from_millis(unix_ms().saturating_sub(started)));
+        }
+
+        self.state = next;
+        self.reason = reason;
+        self.since = Instant::now();
+        self.record();
+        true
+    }
+
+    /// How long a client should wait before retrying, for states that resolve on their own
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.state {
+            ModelState::Configured | ModelState::Loading | ModelState::WarmingUp => Some(
+                self.last_load
+                    .map(|load| load.saturating_sub(self.since.elapsed()))
+                    .unwrap_or(DEFAULT_RETRY)
+                    .max(DEFAULT_RETRY),
+            ),
+            ModelState::Draining => Some(DEFAULT_RETRY),
+            _ => None,
+        }
+    }
+
+    /// Error for a request to this model while it is not serving
+    pub fn unavailable(&self, name: &str) -> EmbeddingError {
+        let retry_after_secs = self.retry_after().map(|retry| retry.as_secs_f64().ceil() as u64);
+        let mut detail = match &self.reason {
+            Some(reason) => format!("is {}: {}", self.state, reason),
+            None => format!("is {}", self.state),
+        };
+        if let Some(secs) = retry_after_secs {
+            detail.push_str(&format!(", retry after {} s", secs));
+        }
+        EmbeddingError::ModelUnavailable {
+            model_name: name.to_string(),
+            state: self.state.as_str().to_string(),
+            detail,
+            retry_after_secs,
+        }
+    }
+
+    /// Snapshot for the APIs
+    pub fn status(&self, name: &str) -> ModelStatus {
+        ModelStatus {
+            name: name.to_string(),
+            state: self.state,
+            reason: self.reason.clone(),
+            since_unix_ms: self.history.back().map(|transition| transition.at_unix_ms).unwrap_or_default(),
+            history: self.history.iter().cloned().collect(),
+            fingerprint: None,
+        }
+    }
+
+    fn record(&mut self) {
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(StateTransition {
+            state: self.state,
+            reason: self.reason.clone(),
+            at_unix_ms: unix_ms(),
+        });
+    }
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_transitions() {
+        let mut lifecycle = Lifecycle::new();
+        assert!(!lifecycle.transition(ModelState::Ready, None));
+        assert!(lifecycle.transition(ModelState::Loading, None));
+        assert_eq!(lifecycle.retry_after(), Some(DEFAULT_RETRY));
+        let error = lifecycle.unavailable("m").to_string();
+        assert!(error.contains("'m' is loading, retry after 1 s"), "{}", error);
+
+        assert!(lifecycle.transition(ModelState::WarmingUp, None));
+        assert!(lifecycle.transition(ModelState::Ready, None));
+        assert!(lifecycle.last_load.is_some());
+        assert!(lifecycle.transition(ModelState::Degraded, Some("norm".to_string())));
+        assert!(lifecycle.transition(ModelState::Draining, None));
+        assert!(!lifecycle.transition(ModelState::Loading, None));
+        assert!(lifecycle.transition(ModelState::Unloaded, None));
+        assert_eq!(lifecycle.retry_after(), None);
+
+        let status = lifecycle.status("m");
+        let states: Vec<_> = status.history.iter().map(|transition| transition.state).collect();
+        assert_eq!(states, vec![
+            ModelState::Configured, ModelState::Loading, ModelState::WarmingUp, ModelState::Ready,
+            ModelState::Degraded, ModelState::Draining, ModelState::Unloaded,
+        ]);
+        assert_eq!(status.history[4].reason.as_deref(), Some("norm"));
+
+        for _ in 0..HISTORY_LIMIT {
+            lifecycle.transition(ModelState::Loading, None);
+            lifecycle.transition(ModelState::Failed, Some("missing file".to_string()));
+        }
+        let status = lifecycle.status("m");
+        assert_eq!(status.history.len(), HISTORY_LIMIT);
+        assert_eq!((status.state, status.reason.as_deref()), (ModelState::Failed, Some("missing file")));
+    }
+}
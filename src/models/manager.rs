@@ -12,7 +12,11 @@ pub struct EmbeddingModelsManager {
     /// Configuration
     config: crate::models::config::EmbeddingModelsConfig,
     /// Model registry
-    registry: crate::models::registry::ModelRegistry,
+    registry: std::sync::Arc<crate::models::registry::ModelRegistry>,
+    /// Output checks, when `development.validate_outputs` is enabled
+    validator: Option<crate::models::validation::OutputValidator>,
+    /// Intermediate value dumps (see `development.intermediates`)
+    dumper: crate::models::intermediates::IntermediateDumper,
 }
 
 impl EmbeddingModelsManager {
@@ -20,18 +24,35 @@ impl EmbeddingModelsManager {
     pub fn new() -> Self {
         Self {
             config: crate::models::config::EmbeddingModelsConfig::default(),
-            registry: crate::models::registry::ModelRegistry::new(),
+            registry: std::sync::Arc::new(crate::models::registry::ModelRegistry::new()),
+            validator: None,
+            dumper: crate::models::intermediates::IntermediateDumper::new(Default::default()),
         }
     }
 
     /// Create a manager from configuration file
+    ///
+    /// Binaries built with the `embedded-model` feature always register the
+    /// embedded model, and fall back to it alone when the file is missing.
     pub fn from_config_file<P: AsRef<Path>>(config_path: P) -> EmbeddingResult<Self> {
+        #[cfg(feature = "embedded-model")]
+        let config = if config_path.as_ref().exists() {
+            let mut config = crate::models::config::EmbeddingModelsConfig::from_file(config_path)?;
+            config.add_embedded_model()?;
+            config
+        } else {
+            tracing::info!("{} not found, serving the embedded model only", config_path.as_ref().display());
+            crate::models::config::EmbeddingModelsConfig::embedded()?
+        };
+        #[cfg(not(feature = "embedded-model"))]
         let config = crate::models::config::EmbeddingModelsConfig::from_file(config_path)?;
         config.validate()?;
 
         Ok(Self {
+            validator: Self::output_validator(&config),
+            dumper: crate::models::intermediates::IntermediateDumper::new(config.development.intermediates.clone()),
+            registry: std::sync::Arc::new(crate::models::registry::ModelRegistry::new()),
             config,
-            registry: crate::models::registry::ModelRegistry::new(),
         })
     }
 
@@ -40,8 +61,19 @@ impl EmbeddingModelsManager {
         config.validate()?;
 
         Ok(Self {
+            validator: Self::output_validator(&config),
+            dumper: crate::models::intermediates::IntermediateDumper::new(config.development.intermediates.clone()),
+            registry: std::sync::Arc::new(crate::models::registry::ModelRegistry::new()),
             config,
-            registry: crate::models::registry::ModelRegistry::new(),
+        })
+    }
+
+    /// Build the output validator if `development.validate_outputs` is enabled
+    fn output_validator(
+        config: &crate::models::config::EmbeddingModelsConfig,
+    ) -> Option<crate::models::validation::OutputValidator> {
+        config.development.validate_outputs.then(|| {
+            crate::models::validation::OutputValidator::new(config.development.output_validation.clone())
         })
     }
 
@@ -62,12 +94,7 @@ impl EmbeddingModelsManager {
 
     /// Embed text using the default model
     pub async fn embed_text(&self, text: &str) -> EmbeddingResult<Embedding> {
-        let model = self.registry.get_default_model(&self.config).await
-            .ok_or_else(|| crate::EmbeddingError::ModelNotFound {
-                model_name: self.config.global.default_model.clone(),
-            })?;
-
-        model.embed_text(text).await
+        self.embed_one(text, None, false).await
     }
 
     /// Embed text using a specific model
@@ -76,22 +103,27 @@ impl EmbeddingModelsManager {
         text: &str,
         model_name: &str,
     ) -> EmbeddingResult<Embedding> {
-        let model = self.registry.get_model(model_name).await
-            .ok_or_else(|| crate::EmbeddingError::ModelNotFound {
-                model_name: model_name.to_string(),
-            })?;
+        self.embed_one(text, Some(model_name), false).await
+    }
 
-        model.embed_text(text).await
+    /// Embed text (default model if None), dumping intermediate values
+    ///
+    /// Used for requests carrying the `X-Save-Intermediates` header; ignored
+    /// unless `development.intermediates.allow_request_header` is enabled.
+    pub async fn embed_text_saving_intermediates(
+        &self,
+        text: &str,
+        model_name: Option<&str>,
+    ) -> EmbeddingResult<Embedding> {
+        self.embed_one(text, model_name, true).await
     }
 
     /// Embed a batch of texts using the default model
     pub async fn embed_batch(&self, texts: &[String]) -> EmbeddingResult<Vec<Embedding>> {
-        let model = self.registry.get_default_model(&self.config).await
-            .ok_or_else(|| crate::EmbeddingError::ModelNotFound {
-                model_name: self.config.global.default_model.clone(),
-            })?;
-
-        model.embed_batch(texts).await
+        let model = self.serving_model(None).await?;
+        let embeddings = model.embed_batch(texts).await?;
+        self.check_outputs(model.as_ref(), Some(texts), &embeddings).await?;
+        Ok(embeddings)
     }
 
     /// Embed a batch of texts using a specific model
@@ -100,12 +132,290 @@ impl EmbeddingModelsManager {
         texts: &[String],
         model_name: &str,
     ) -> EmbeddingResult<Vec<Embedding>> {
-        let model = self.registry.get_model(model_name).await
-            .ok_or_else(|| crate::EmbeddingError::ModelNotFound {
-                model_name: model_name.to_string(),
-            })?;
+        let model = self.serving_model(Some(model_name)).await?;
+        let embeddings = model.embed_batch(texts).await?;
+        self.check_outputs(model.as_ref(), Some(texts), &embeddings).await?;
+        Ok(embeddings)
+    }
+
+    /// Embed pre-tokenized input using the default model
+    pub async fn embed_token_ids(&self, input_ids: &[u32]) -> EmbeddingResult<Embedding> {
+        let model = self.serving_model(None).await?;
+        Self::validate_token_ids(model.info(), input_ids)?;
+        let embedding = model.embed_token_ids(input_ids).await?;
+        self.check_outputs(model.as_ref(), None, std::slice::from_ref(&embedding)).await?;
+        Ok(embedding)
+    }
+
+    /// Embed pre-tokenized input using a specific model
+    pub async fn embed_token_ids_with_model(
+        &self,
+        input_ids: &[u32],
+        model_name: &str,
+    ) -> EmbeddingResult<Embedding> {
+        let model = self.serving_model(Some(model_name)).await?;
+        Self::validate_token_ids(model.info(), input_ids)?;
+        let embedding = model.embed_token_ids(input_ids).await?;
+        self.check_outputs(model.as_ref(), None, std::slice::from_ref(&embedding)).await?;
+        Ok(embedding)
+    }
+
+    /// Embed one text, dumping intermediate values when requested or configured
+    async fn embed_one(
+        &self,
+        text: &str,
+        model_name: Option<&str>,
+        request_dump: bool,
+    ) -> EmbeddingResult<Embedding> {
+        let model = self.serving_model(model_name).await?;
+        let embedding = if self.should_dump(&model.info().name, request_dump) {
+            let intermediates = model.embed_text_traced(text).await?;
+            match self.dumper.write(&model.info().name, &intermediates) {
+                Ok(Some(dir)) => tracing::debug!("Saved intermediates to {}", dir.display()),
+                Ok(None) => tracing::debug!("Intermediate dump limits reached, skipping dump"),
+                Err(e) => tracing::warn!("⚠️ Failed to save intermediates for '{}': {}", model.info().name, e),
+            }
+            intermediates.embedding
+        } else {
+            model.embed_text(text).await?
+        };
+        self.check_outputs(model.as_ref(), None, std::slice::from_ref(&embedding)).await?;
+        Ok(embedding)
+    }
+
+    /// Whether a text request for the named model is dumped
+    fn should_dump(&self, model_name: &str, request_dump: bool) -> bool {
+        if request_dump && self.dumper.allows_request_header() {
+            return true;
+        }
+        let configured = self.config.development.save_intermediates
+            || self.config.get_model(model_name).is_some_and(|model| model.save_intermediates);
+        configured && self.dumper.sample()
+    }
+
+    /// Look up a model for embedding (default model if None), refusing quarantined models
+    async fn serving_model(
+        &self,
+        model_name: Option<&str>,
+    ) -> EmbeddingResult<std::sync::Arc<dyn crate::models::EmbeddingModel>> {
+        let model = self.model_or_default(model_name).await?;
+        if let Some(reason) = self.registry.quarantine_reason(&model.info().name).await {
+            return Err(crate::EmbeddingError::ModelQuarantined {
+                model_name: model.info().name.clone(),
+                reason,
+            });
+        }
+        Ok(model)
+    }
+
+    /// Validate produced embeddings when `development.validate_outputs` is enabled
+    ///
+    /// A violation fails the request and, if configured, quarantines the model.
+    async fn check_outputs(
+        &self,
+        model: &dyn crate::models::EmbeddingModel,
+        inputs: Option<&[String]>,
+        embeddings: &[Embedding],
+    ) -> EmbeddingResult<()> {
+        let Some(validator) = &self.validator else {
+            return Ok(());
+        };
+
+        let info = model.info();
+        let inputs: Option<Vec<_>> = inputs.map(|texts| texts.iter().map(|text| Self::model_input(model, text)).collect());
+        match validator.check(info.dimension, inputs.as_deref(), embeddings) {
+            Ok(()) => Ok(()),
+            Err(violation) => {
+                tracing::error!(
+                    "❌ Output validation failed for '{}' ({}): {}",
+                    info.name, violation.check.as_str(), violation.message
+                );
+                if validator.quarantines() {
+                    tracing::error!("🚫 Quarantining model '{}' until it is reloaded", info.name);
+                    self.registry.quarantine(&info.name, format!("{}: {}", violation.check.as_str(), violation.message)).await;
+                    validator.record_quarantine();
+                }
+                Err(violation.into_error(&info.name))
+            }
+        }
+    }
+
+    /// An input as the model tells it apart: its token ids after preprocessing
+    /// where the model has a tokenizer, so variants that preprocessing or an
+    /// uncased tokenizer merge count as one input
+    fn model_input(model: &dyn crate::models::EmbeddingModel, text: &str) -> crate::models::validation::ModelInput {
+        match model.tokenize(text, true) {
+            Ok(tokenized) => crate::models::validation::ModelInput::Tokens(tokenized.ids),
+            Err(_) => crate::models::validation::ModelInput::Text(text.to_string()),
+        }
+    }
+
+    /// Check pre-tokenized input against the model's sequence length and vocabulary
+    fn validate_token_ids(info: &crate::models::model::ModelInfo, input_ids: &[u32]) -> EmbeddingResult<()> {
+        if input_ids.is_empty() {
+            return Err(crate::EmbeddingError::InvalidInput {
+                message: "input_ids must not be empty".to_string(),
+            });
+        }
+
+        if input_ids.len() > info.max_sequence_length {
+            return Err(crate::EmbeddingError::InvalidInput {
+                message: format!(
+                    "input_ids has {} tokens, but model '{}' accepts at most {}",
+                    input_ids.len(), info.name, info.max_sequence_length
+                ),
+            });
+        }
+
+        if let Some(vocab_size) = info.vocab_size {
+            if let Some((position, id)) = input_ids.iter().enumerate().find(|(_, &id)| id as usize >= vocab_size) {
+                return Err(crate::EmbeddingError::InvalidInput {
+                    message: format!(
+                        "input_ids[{}] = {} is outside the vocabulary of model '{}' (size {})",
+                        position, id, info.name, vocab_size
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tokenize a text with the tokenizer of a model (default model if None)
+    pub async fn tokenize(
+        &self,
+        text: &str,
+        add_special_tokens: bool,
+        model_name: Option<&str>,
+    ) -> EmbeddingResult<crate::models::TokenizedText> {
+        self.model_or_default(model_name).await?.tokenize(text, add_special_tokens)
+    }
+
+    /// Decode token ids with the tokenizer of a model (default model if None)
+    pub async fn decode(
+        &self,
+        ids: &[u32],
+        skip_special_tokens: bool,
+        model_name: Option<&str>,
+    ) -> EmbeddingResult<String> {
+        self.model_or_default(model_name).await?.decode(ids, skip_special_tokens)
+    }
+
+    /// Count the tokens of a text for a model (default model if None)
+    pub async fn count_tokens(
+        &self,
+        text: &str,
+        add_special_tokens: bool,
+        model_name: Option<&str>,
+    ) -> EmbeddingResult<usize> {
+        self.model_or_default(model_name).await?.count_tokens(text, add_special_tokens)
+    }
+
+    /// Fit a projection from a corpus embedded by a loaded model
+    ///
+    /// The model must not already have a projection, since the fit needs its
+    /// native embeddings. The result is written to `request.output_path`.
+    pub async fn fit_projection(
+        &self,
+        request: &crate::models::projection::ProjectionFitRequest,
+    ) -> EmbeddingResult<crate::models::projection::ProjectionFitReport> {
+        let model = self.model_or_default(request.model.as_deref()).await?;
+        let info = model.info().clone();
+        if info.projection.is_some() {
+            return Err(crate::EmbeddingError::InvalidInput {
+                message: format!(
+                    "Model '{}' already applies a projection; fit against a configuration without one",
+                    info.name
+                ),
+            });
+        }
+
+        let texts = crate::models::projection::read_corpus(&request.corpus_path, request.max_texts)?;
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for chunk in texts.chunks(self.config.global.max_batch_size.max(1)) {
+            embeddings.extend(model.embed_batch(chunk).await?);
+        }
+
+        let output_dimension = request.output_dimension;
+        let whiten = request.whiten;
+        let (projection, explained_variance) = tokio::task::spawn_blocking(move || {
+            crate::models::projection::fit(&embeddings, output_dimension, whiten)
+        })
+        .await
+        .map_err(|e| crate::EmbeddingError::EmbeddingFailed {
+            error: format!("Projection fitting task failed: {}", e),
+        })??;
+        projection.save(&request.output_path)?;
+
+        tracing::info!(
+            "Fitted {}x{} projection for '{}' from {} texts ({:.1}% variance kept)",
+            projection.input_dimension(), projection.output_dimension(), info.name,
+            texts.len(), explained_variance * 100.0
+        );
+
+        Ok(crate::models::projection::ProjectionFitReport {
+            model: info.name,
+            samples: texts.len(),
+            input_dimension: projection.input_dimension(),
+            output_dimension: projection.output_dimension(),
+            explained_variance,
+            whiten,
+            output_path: request.output_path.clone(),
+        })
+    }
+
+    /// Look up a model by name, falling back to the default model
+    ///
+    /// Lazy and on-demand models are loaded here on first use.
+    async fn model_or_default(
+        &self,
+        model_name: Option<&str>,
+    ) -> EmbeddingResult<std::sync::Arc<dyn crate::models::EmbeddingModel>> {
+        let name = self.model_id(model_name);
+        let model = self.registry.get_or_load(name).await?;
+
+        model.ok_or_else(|| crate::EmbeddingError::ModelNotFound {
+            model_name: name.to_string(),
+        })
+    }
 
-        model.embed_batch(texts).await
+    /// Canonical id of a model given any accepted name (default model if None)
+    ///
+    /// Unknown names are returned unchanged, for the registry to report.
+    fn model_id<'a>(&'a self, model_name: Option<&'a str>) -> &'a str {
+        let name = model_name.unwrap_or(&self.config.global.default_model);
+        self.config.resolve_model(name).unwrap_or(name)
+    }
+
+    /// Information on the default model, if it is loaded, warmed up and serving
+    ///
+    /// Models are registered only after their warmup, so readiness checks
+    /// need no inference of their own.
+    pub async fn ready_default_model(&self) -> EmbeddingResult<crate::models::model::ModelInfo> {
+        let model = self.serving_model(None).await?;
+        let name = model.info().name.clone();
+        if !model.is_ready().await {
+            return Err(crate::EmbeddingError::ModelLoadError {
+                model_name: name,
+                error: "Model is not ready".to_string(),
+            });
+        }
+        self.registry.get_model_info(&name).await.ok_or(crate::EmbeddingError::ModelNotFound { model_name: name })
+    }
+
+    /// Output fingerprint of a model (default model if None)
+    pub async fn model_fingerprint(&self, model_name: Option<&str>) -> Option<String> {
+        self.registry.fingerprint(self.model_id(model_name)).await
+    }
+
+    /// Lifecycle state and history of every configured or loaded model
+    pub async fn model_statuses(&self) -> Vec<crate::models::lifecycle::ModelStatus> {
+        self.registry.model_statuses().await
+    }
+
+    /// Lifecycle state and history of a model (default model if None)
+    pub async fn model_status(&self, model_name: Option<&str>) -> Option<crate::models::lifecycle::ModelStatus> {
+        self.registry.model_status(self.model_id(model_name)).await
     }
 
     /// Get information about all loaded models
@@ -115,12 +425,12 @@ impl EmbeddingModelsManager {
 
     /// Get information about a specific model
     pub async fn get_model_info(&self, name: &str) -> Option<crate::models::model::ModelInfo> {
-        self.registry.get_model_info(name).await
+        self.registry.get_model_info(self.model_id(Some(name))).await
     }
 
     /// Check if a model is loaded
     pub async fn is_model_loaded(&self, name: &str) -> bool {
-        self.registry.is_model_loaded(name).await
+        self.registry.is_model_loaded(self.model_id(Some(name))).await
     }
 
     /// Load a specific model
@@ -134,9 +444,38 @@ impl EmbeddingModelsManager {
         }
     }
 
+    /// Unload on-demand models idle for longer than their `idle_timeout_secs`
+    pub async fn unload_idle_models(&self) -> EmbeddingResult<Vec<String>> {
+        self.registry.unload_idle().await
+    }
+
+    /// Start a background task that unloads idle on-demand models
+    ///
+    /// Returns `None` when no model is on-demand. The task checks at a quarter
+    /// of the shortest idle timeout and ends once the manager is dropped.
+    pub async fn spawn_idle_unloader(manager: &std::sync::Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let timeout = manager.registry.shortest_idle_timeout().await?;
+        let period = (timeout / 4).clamp(std::time::Duration::from_secs(1), std::time::Duration::from_secs(60));
+        let manager = std::sync::Arc::downgrade(manager);
+
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                if let Err(e) = manager.unload_idle_models().await {
+                    tracing::warn!("⚠️ Unloading idle models failed: {}", e);
+                }
+            }
+        }))
+    }
+
     /// Unload a specific model
     pub async fn unload_model(&self, model_name: &str) -> EmbeddingResult<()> {
-        self.registry.unload_model(model_name).await
+        self.registry.unload_model(self.model_id(Some(model_name))).await
     }
 
     /// Get models by group
@@ -168,6 +507,8 @@ impl EmbeddingModelsManager {
         self.registry.shutdown_all().await?;
 
         // Update configuration
+        self.validator = Self::output_validator(&new_config);
+        self.dumper = crate::models::intermediates::IntermediateDumper::new(new_config.development.intermediates.clone());
         self.config = new_config;
 
         // Reload models
@@ -182,6 +523,9 @@ impl EmbeddingModelsManager {
                 total_requests: 0,
                 average_latency_ms: 0.0,
                 models_loaded: self.registry.list_models().await.len(),
+                output_validation: self.validator.as_ref().map(|validator| validator.metrics()),
+                residency: self.registry.residency_metrics().await,
+                load_failures: self.registry.load_failures().await,
             })
         } else {
             None
@@ -195,11 +539,17 @@ impl EmbeddingModelsManager {
 }
 
 /// Performance metrics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct Metrics {
     pub total_requests: u64,
     pub average_latency_ms: f64,
     pub models_loaded: usize,
+    /// Output validation counters, when `development.validate_outputs` is enabled
+    pub output_validation: Option<crate::models::validation::ValidationMetrics>,
+    /// Memory budget, resident estimate, evictions and reloads
+    pub residency: crate::models::residency::ResidencyMetrics,
+    /// Models that failed to load at startup, with the error
+    pub load_failures: std::collections::HashMap<String, String>,
 }
 
 impl Default for EmbeddingModelsManager {
@@ -211,6 +561,7 @@ impl Default for EmbeddingModelsManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::model::mock::{mock_config, mock_model_toml};
 
     #[tokio::test]
     async fn test_manager_creation() {
@@ -251,6 +602,261 @@ mod tests {
 
         let config = crate::models::config::EmbeddingModelsConfig::from_str(config_str).unwrap();
         let manager = EmbeddingModelsManager::from_config(config);
-        assert!(manager.is_ok());
+        // The model uses the default onnx backend
+        assert_eq!(manager.is_ok(), cfg!(feature = "onnx"));
+    }
+
+    #[tokio::test]
+    async fn test_manager_embeds_with_mock_model() {
+        let config = mock_config("mock-model", &[mock_model_toml("mock-model", "max_sequence_length = 256\nembedding_dimension = 8")]);
+        let mut manager = EmbeddingModelsManager::from_config(config).unwrap();
+        manager.initialize().await.unwrap();
+
+        let single = manager.embed_text("hello").await.unwrap();
+        let batch = manager.embed_batch_with_model(&["hello".to_string()], "mock-model").await.unwrap();
+        assert_eq!(single, batch[0]);
+        assert!(manager.embed_text_with_model("hello", "missing").await.is_err());
+
+        let ids = manager.embed_token_ids(&[101, 7592, 102]).await.unwrap();
+        assert_eq!(ids, manager.embed_token_ids_with_model(&[101, 7592, 102], "mock-model").await.unwrap());
+        assert!(manager.embed_token_ids(&[]).await.is_err());
+        assert!(manager.embed_token_ids(&vec![1; 257]).await.is_err());
+    }
+
+    #[test]
+    fn test_token_ids_outside_vocabulary() {
+        let info = crate::models::model::ModelInfo {
+            name: "test-model".to_string(),
+            display_name: "Test Model".to_string(),
+            description: String::new(),
+            version: "1.0.0".to_string(),
+            dimension: 8,
+            max_sequence_length: 8,
+            pooling_mode: "mean".to_string(),
+            uses_gpu: false,
+            model_path: String::new(),
+            tokenizer_path: String::new(),
+            backend: "onnx".to_string(),
+            precision: "f32".to_string(),
+            load_report: None,
+            vocab_size: Some(100),
+            preprocessing: Vec::new(),
+            projection: None,
+            warmup: None,
+            deterministic: false,
+            fingerprint: None,
+            memory_bytes: None,
+            lifecycle: None,
+        };
+        assert!(EmbeddingModelsManager::validate_token_ids(&info, &[1, 99]).is_ok());
+        assert!(matches!(
+            EmbeddingModelsManager::validate_token_ids(&info, &[1, 100]),
+            Err(crate::EmbeddingError::InvalidInput { .. })
+        ));
+    }
+
+    async fn mock_manager(model_settings: &str) -> EmbeddingModelsManager {
+        let mut config = mock_config("mock-model", &[mock_model_toml(
+            "mock-model",
+            &format!("max_sequence_length = 256\nembedding_dimension = 8\n{}", model_settings),
+        )]);
+        config.global.max_batch_size = 16;
+        let mut manager = EmbeddingModelsManager::from_config(config).unwrap();
+        manager.initialize().await.unwrap();
+        manager
+    }
+
+    #[tokio::test]
+    async fn test_fit_and_apply_projection() {
+        let dir = std::env::temp_dir().join(format!("manager-projection-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let corpus_path = dir.join("corpus.txt");
+        let output_path = dir.join("projection.safetensors");
+        let corpus: Vec<String> = (0..40).map(|i| format!("document number {}", i)).collect();
+        std::fs::write(&corpus_path, corpus.join("\n")).unwrap();
+
+        let request = crate::models::projection::ProjectionFitRequest {
+            model: None,
+            corpus_path: corpus_path.to_str().unwrap().to_string(),
+            output_path: output_path.to_str().unwrap().to_string(),
+            output_dimension: Some(4),
+            whiten: true,
+            max_texts: Some(30),
+        };
+        let report = mock_manager("").await.fit_projection(&request).await.unwrap();
+        assert_eq!((report.samples, report.input_dimension, report.output_dimension), (30, 8, 4));
+        assert!(report.explained_variance > 0.0 && report.explained_variance <= 1.0);
+
+        let projected = mock_manager(&format!("[models.mock-model.projection]\npath = {:?}", request.output_path)).await;
+        let info = projected.get_model_info("mock-model").await.unwrap();
+        assert_eq!(info.dimension, 4);
+        assert_eq!(info.projection.as_ref().map(|p| p.input_dimension), Some(8));
+        assert_eq!(projected.embed_text("hello").await.unwrap().len(), 4);
+
+        // Fitting needs the native embeddings, not already projected ones
+        assert!(projected.fit_projection(&request).await.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_output_validation_quarantines_model() {
+        let manager = mock_manager(r#"
+            [development]
+            debug_logging = false
+            validate_outputs = true
+            save_intermediates = false
+            [development.output_validation]
+            min_norm = 2.0
+            max_norm = 3.0
+            quarantine = true
+
+            [monitoring]
+            metrics_enabled = true
+            log_inference_times = false
+            track_usage = false
+            metrics_interval = 60
+        "#).await;
+
+        assert!(matches!(
+            manager.embed_text("hello").await,
+            Err(crate::EmbeddingError::InvalidOutput { ref check, .. }) if check == "norm"
+        ));
+        assert!(matches!(
+            manager.embed_text("hello").await,
+            Err(crate::EmbeddingError::ModelQuarantined { .. })
+        ));
+        let metrics = manager.get_metrics().await.unwrap().output_validation.unwrap();
+        assert_eq!((metrics.norm, metrics.quarantined), (1, 1));
+
+        // Reloading the model lifts the quarantine
+        manager.load_model("mock-model").await.unwrap();
+        assert!(matches!(manager.embed_text("hello").await, Err(crate::EmbeddingError::InvalidOutput { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_case_variants_are_not_collapsed_outputs() {
+        let tokenizer_path = std::env::temp_dir().join(format!("manager-tokenizer-{}.json", std::process::id()));
+        crate::models::tokenization::tests::test_tokenizer().save(&tokenizer_path, false).unwrap();
+        let manager = mock_manager(&format!(r#"
+            tokenizer_path = {:?}
+            [models.mock-model.preprocessing]
+            lowercase = true
+            collapse_whitespace = true
+
+            [development]
+            debug_logging = false
+            validate_outputs = true
+            save_intermediates = false
+            [development.output_validation]
+            quarantine = true
+        "#, tokenizer_path.to_str().unwrap())).await;
+
+        // Preprocessing turns the variants into one text, and so one vector
+        let texts = ["Hello world", "hello world", "HELLO   World", "world"].map(String::from);
+        let embeddings = manager.embed_batch(&texts).await.unwrap();
+        assert!(embeddings[0] == embeddings[1] && embeddings[1] == embeddings[2]);
+        assert!(manager.embed_text("hello").await.is_ok());
+
+        std::fs::remove_file(tokenizer_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_lexical_input_without_terms_is_not_an_output_violation() {
+        let mut config = mock_config("lexical-model", &[mock_model_toml("lexical-model", r#"
+            embedding_dimension = 1
+            backend = "lexical"
+
+            [development]
+            debug_logging = false
+            validate_outputs = true
+            save_intermediates = false
+            [development.output_validation]
+            quarantine = true
+
+            [monitoring]
+            metrics_enabled = true
+            log_inference_times = false
+            track_usage = false
+            metrics_interval = 60
+        "#)]);
+        config.global.max_batch_size = 16;
+        let mut manager = EmbeddingModelsManager::from_config(config).unwrap();
+        manager.initialize().await.unwrap();
+
+        // With a single bucket, one positive and one negative term cancel out
+        let words = ["cat", "dog", "fox", "bird", "zebra", "tree", "sun"];
+        let mut positive = None;
+        let mut negative = None;
+        for word in words {
+            let embedding = manager.embed_text(word).await.unwrap();
+            if embedding[0] > 0.0 { positive.get_or_insert(word); } else { negative.get_or_insert(word); }
+        }
+        let cancelled = format!("{} {}", positive.unwrap(), negative.unwrap());
+
+        for texts in [vec!["  ...  ".to_string()], vec!["cat".to_string(), cancelled]] {
+            assert!(matches!(manager.embed_batch(&texts).await, Err(crate::EmbeddingError::InvalidInput { .. })));
+        }
+        assert!(manager.embed_text("cat").await.is_ok());
+        let metrics = manager.get_metrics().await.unwrap().output_validation.unwrap();
+        assert_eq!((metrics.norm, metrics.quarantined), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_save_intermediates_for_model() {
+        let dir = std::env::temp_dir().join(format!("manager-intermediates-{}", std::process::id()));
+        let manager = mock_manager(&format!(r#"
+            save_intermediates = true
+
+            [development]
+            debug_logging = false
+            validate_outputs = false
+            save_intermediates = false
+            [development.intermediates]
+            dump_dir = "{}"
+            max_dumps = 1
+        "#, dir.display())).await;
+
+        let embedding = manager.embed_text("hello").await.unwrap();
+        manager.embed_text("world").await.unwrap();
+
+        let dumps: Vec<_> = std::fs::read_dir(dir.join("mock-model")).unwrap().map(|entry| entry.unwrap().path()).collect();
+        assert_eq!(dumps.len(), 1);
+        let saved = crate::models::weights::load_matrix(dumps[0].join("embedding.npy"), None).unwrap();
+        assert_eq!(saved.data, embedding);
+        let manifest: serde_json::Value = serde_json::from_slice(&std::fs::read(dumps[0].join("input.json")).unwrap()).unwrap();
+        assert_eq!(manifest["text"], "hello");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_models_resolve_by_alias_and_redirect() {
+        let manager = mock_manager(r#"
+            aliases = ["text-embedding-3-small"]
+
+            [model_redirects]
+            "old-mock" = "mock-model"
+
+            [model_groups]
+            general = ["Text-Embedding-3-Small"]
+            multilingual = []
+            high_dim = []
+            gpu_models = []
+        "#).await;
+
+        let expected = manager.embed_text("hello").await.unwrap();
+        for name in ["text-embedding-3-small", "MOCK-MODEL", "default", "old-mock"] {
+            assert_eq!(manager.embed_text_with_model("hello", name).await.unwrap(), expected, "{}", name);
+        }
+        assert!(matches!(
+            manager.embed_text_with_model("hello", "unknown").await,
+            Err(crate::EmbeddingError::ModelNotFound { .. })
+        ));
+
+        assert!(manager.is_model_loaded("Text-Embedding-3-Small").await);
+        assert_eq!(manager.get_model_info("default").await.unwrap().name, "mock-model");
+        assert_eq!(manager.get_models_by_group("general").await, vec!["mock-model"]);
+        assert_eq!(manager.registry().get_models_by_group("general", manager.config()).await.len(), 1);
     }
 }
@@ -2,15 +2,32 @@
 // These will be adapted for the standalone server
 
 pub mod config;
+pub mod fingerprint;
+pub mod hashing;
+pub mod identity;
+pub mod intermediates;
+pub mod lifecycle;
 pub mod manager;
+pub mod memory;
 pub mod model;
+pub mod pooling;
+pub mod precision;
+pub mod preprocessing;
+pub mod projection;
 pub mod registry;
+pub mod residency;
+pub mod tokenization;
+pub mod tokenizer_loader;
+pub mod validation;
+pub mod warmup;
+pub mod weights;
 
 // Re-exports
 pub use config::{EmbeddingModelsConfig, ModelConfig};
 pub use manager::EmbeddingModelsManager;
 pub use model::{EmbeddingModel, ModelInfo};
 pub use registry::ModelRegistry;
+pub use tokenization::TokenizedText;
 
 /// Embedding vector type
 pub type Embedding = Vec<f32>;
@@ -47,6 +64,15 @@ pub enum EmbeddingError {
 
     #[error("TOML parsing error: {error}")]
     TomlError { error: toml::de::Error },
+
+    #[error("Invalid output from {model_name}: {check} - {message}")]
+    InvalidOutput { model_name: String, check: String, message: String },
+
+    #[error("Model quarantined: {model_name} - {reason}")]
+    ModelQuarantined { model_name: String, reason: String },
+
+    #[error("Model '{model_name}' {detail}")]
+    ModelUnavailable { model_name: String, state: String, detail: String, retry_after_secs: Option<u64> },
 }
 
 impl From<std::io::Error> for EmbeddingError {
@@ -61,6 +87,7 @@ impl From<toml::de::Error> for EmbeddingError {
     }
 }
 
+#[cfg(feature = "onnx")]
 impl From<ort::Error> for EmbeddingError {
     fn from(error: ort::Error) -> Self {
         EmbeddingError::ModelLoadFailed { error: error.to_string() }
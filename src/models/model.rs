@@ -3,6 +3,7 @@
 //! This module defines the core traits and structures for embedding models,
 //! providing a unified interface for different model implementations.
 
+use crate::models::tokenization;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -10,8 +11,11 @@ use std::sync::Arc;
 /// Information about a model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelInfo {
-    /// Model name
+    /// Canonical model id, the model's key under `[models]`
     pub name: String,
+    /// Human-readable name from the configuration
+    #[serde(default)]
+    pub display_name: String,
     /// Model description
     pub description: String,
     /// Model version
@@ -28,6 +32,135 @@ pub struct ModelInfo {
     pub model_path: String,
     /// Tokenizer path
     pub tokenizer_path: String,
+    /// Inference backend serving the model
+    pub backend: String,
+    /// Numeric precision of the model's outputs ("f32", "f16" or "bf16")
+    #[serde(default = "default_precision")]
+    pub precision: String,
+    /// What loading the model cost, once it is loaded
+    #[serde(default)]
+    pub load_report: Option<LoadReport>,
+    /// Tokenizer vocabulary size, once known; pre-tokenized input ids must be below it
+    #[serde(default)]
+    pub vocab_size: Option<usize>,
+    /// Text preprocessing steps applied before tokenization, in order
+    #[serde(default)]
+    pub preprocessing: Vec<String>,
+    /// Projection applied after pooling, once loaded; `dimension` is its output size
+    #[serde(default)]
+    pub projection: Option<ProjectionInfo>,
+    /// Warmup run before the model was reported ready
+    #[serde(default)]
+    pub warmup: Option<WarmupReport>,
+    /// Whether the model runs with deterministic execution settings
+    #[serde(default)]
+    pub deterministic: bool,
+    /// Hash of the model's embeddings of fixed canary texts, computed at load
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    /// Estimated memory the model occupies, recorded at load
+    #[serde(default)]
+    pub memory_bytes: Option<u64>,
+    /// Lifecycle state and recent transitions, filled in by the registry
+    #[serde(default)]
+    pub lifecycle: Option<crate::models::lifecycle::ModelStatus>,
+}
+
+fn default_precision() -> String {
+    crate::models::precision::Precision::F32.as_str().to_string()
+}
+
+/// Files read and memory used while loading a model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadReport {
+    /// How the model bytes were handed to the runtime
+    pub load_mode: String,
+    /// Model files with their sizes
+    pub files: Vec<LoadedFile>,
+    /// Total size of all model files in bytes
+    pub total_bytes: u64,
+    /// Resident memory of the process before loading, where available
+    pub rss_before_bytes: Option<u64>,
+    /// Resident memory of the process after loading, where available
+    pub rss_after_bytes: Option<u64>,
+    /// Wall-clock loading time
+    pub load_time_ms: u64,
+    /// Optimized graph cache outcome ("hit", "miss" or "rebuilt"), when a cache is configured
+    #[serde(default)]
+    pub optimized_graph_cache: Option<String>,
+}
+
+/// A model file and its size
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadedFile {
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// Requests run to warm a model up, and how long they took
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupReport {
+    /// One entry per warmup request
+    pub passes: Vec<WarmupPass>,
+    /// Wall-clock time of the whole warmup
+    pub total_ms: u64,
+}
+
+/// A single warmup request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupPass {
+    /// Number of texts in the request
+    pub batch_size: usize,
+    /// Approximate tokens per text, for synthetic texts
+    pub sequence_length: Option<usize>,
+    /// Time the request took
+    pub duration_ms: u64,
+}
+
+/// A loaded post-pooling projection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectionInfo {
+    /// Projection matrix file
+    pub path: String,
+    /// Dimension of the backend's pooled embeddings
+    pub input_dimension: usize,
+    /// Dimension of the projected embeddings
+    pub output_dimension: usize,
+    /// Whether a mean is subtracted before projecting
+    pub mean_centered: bool,
+    /// Whether projected vectors are L2-normalized
+    pub normalize: bool,
+}
+
+impl ModelInfo {
+    /// Build model information from its configuration
+    pub fn from_config(config: &crate::models::config::ModelConfig) -> Self {
+        Self {
+            name: config.id().to_string(),
+            display_name: config.name.clone(),
+            description: config.description.clone(),
+            version: config.version.clone(),
+            dimension: config.embedding_dimension,
+            max_sequence_length: config.max_sequence_length,
+            pooling_mode: config.pooling_mode.clone(),
+            uses_gpu: config.use_gpu,
+            model_path: config.model_path.clone(),
+            tokenizer_path: config.tokenizer_path.clone(),
+            backend: config.backend.clone(),
+            precision: default_precision(),
+            load_report: None,
+            vocab_size: None,
+            preprocessing: crate::models::preprocessing::TextPreprocessor::from_config(&config.preprocessing)
+                .map(|preprocessor| preprocessor.steps())
+                .unwrap_or_default(),
+            projection: None,
+            warmup: None,
+            deterministic: config.deterministic,
+            fingerprint: None,
+            memory_bytes: None,
+            lifecycle: None,
+        }
+    }
 }
 
 /// Core embedding model trait
@@ -48,6 +181,49 @@ pub trait EmbeddingModel: Send + Sync {
     /// Generate embeddings for a batch of texts
     async fn embed_batch(&self, texts: &[String]) -> crate::models::EmbeddingResult<Vec<crate::models::Embedding>>;
 
+    /// Generate embeddings for already tokenized input, bypassing the tokenizer
+    async fn embed_token_ids(&self, input_ids: &[u32]) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+        let _ = input_ids;
+        Err(crate::EmbeddingError::InvalidInput {
+            message: format!("Model '{}' does not accept pre-tokenized input", self.info().name),
+        })
+    }
+
+    /// Generate an embedding for a single text, capturing intermediate values
+    ///
+    /// Backends that cannot observe their internals return only the embedding.
+    async fn embed_text_traced(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::intermediates::Intermediates> {
+        Ok(crate::models::intermediates::Intermediates {
+            text: text.to_string(),
+            embedding: self.embed_text(text).await?,
+            ..Default::default()
+        })
+    }
+
+    /// Tokenizer used by the model, with padding and truncation removed (None for
+    /// models without one, or before initialization)
+    fn tokenizer(&self) -> Option<&tokenizers::Tokenizer> {
+        None
+    }
+
+    /// Tokenize a text exactly as the model would, without truncation
+    fn tokenize(&self, text: &str, add_special_tokens: bool) -> crate::models::EmbeddingResult<crate::models::TokenizedText> {
+        let tokenizer = self.tokenizer().ok_or_else(|| tokenization::missing_tokenizer(&self.info().name))?;
+        tokenization::tokenize(tokenizer, text, add_special_tokens)
+    }
+
+    /// Decode token ids back to text
+    fn decode(&self, ids: &[u32], skip_special_tokens: bool) -> crate::models::EmbeddingResult<String> {
+        let tokenizer = self.tokenizer().ok_or_else(|| tokenization::missing_tokenizer(&self.info().name))?;
+        tokenization::decode(tokenizer, ids, skip_special_tokens)
+    }
+
+    /// Count the tokens of a text, without truncation
+    fn count_tokens(&self, text: &str, add_special_tokens: bool) -> crate::models::EmbeddingResult<usize> {
+        let tokenizer = self.tokenizer().ok_or_else(|| tokenization::missing_tokenizer(&self.info().name))?;
+        tokenization::count_tokens(tokenizer, text, add_special_tokens)
+    }
+
     /// Get the embedding dimension
     fn dimension(&self) -> usize {
         self.info().dimension
@@ -62,40 +238,74 @@ pub trait EmbeddingModel: Send + Sync {
     async fn shutdown(&mut self) -> crate::models::EmbeddingResult<()>;
 }
 
+/// Wrap a backend error as an inference error, keeping invalid input as is so it maps to a 400
+fn inference_error(model_name: &str, error: crate::EmbeddingError) -> crate::EmbeddingError {
+    match error {
+        error @ crate::EmbeddingError::InvalidInput { .. } => error,
+        error => crate::EmbeddingError::InferenceError {
+            model_name: model_name.to_string(),
+            error: error.to_string(),
+        },
+    }
+}
+
 /// ONNX-based embedding model implementation
+#[cfg(feature = "onnx")]
 pub mod onnx {
     use super::*;
-    use crate::onnx;
-    use std::path::Path;
 
     /// ONNX embedding model
     pub struct OnnxEmbeddingModel {
         info: ModelInfo,
         engine: Option<std::sync::Arc<tokio::sync::RwLock<crate::onnx::OnnxEmbeddingEngine>>>,
+        /// Tokenizer for inspection, set once the engine is loaded
+        tokenizer: Option<tokenizers::Tokenizer>,
         config: crate::models::config::ModelConfig,
     }
 
     impl OnnxEmbeddingModel {
         /// Create a new ONNX embedding model
         pub fn new(config: crate::models::config::ModelConfig) -> Self {
-            let info = ModelInfo {
-                name: config.name.clone(),
-                description: config.description.clone(),
-                version: config.version.clone(),
-                dimension: config.embedding_dimension,
-                max_sequence_length: config.max_sequence_length,
-                pooling_mode: config.pooling_mode.clone(),
-                uses_gpu: config.use_gpu,
-                model_path: config.model_path.clone(),
-                tokenizer_path: config.tokenizer_path.clone(),
-            };
+            let info = ModelInfo::from_config(&config);
 
             Self {
                 info,
                 engine: None,
+                tokenizer: None,
                 config,
             }
         }
+
+        /// Load the engine from assets compiled into the binary
+        #[cfg(feature = "embedded-model")]
+        fn load_embedded(&self) -> crate::models::EmbeddingResult<crate::onnx::OnnxEmbeddingEngine> {
+            use crate::onnx::embedded;
+
+            // The runtime library ships next to the executable, wherever it is started from
+            let mut onnx_config = crate::onnx::OnnxConfig::with_runtime_path_in(
+                &embedded::executable_dir(),
+                &self.config.onnx_runtime_path,
+            );
+            onnx_config.deterministic = self.config.deterministic;
+            crate::onnx::OnnxEmbeddingEngine::from_memory(
+                embedded::asset(&self.config.model_path)?,
+                embedded::asset(&self.config.tokenizer_path)?,
+                &onnx_config,
+                "cpu",
+                self.config.batch_size,
+                self.config.max_sequence_length,
+            )
+        }
+
+        #[cfg(not(feature = "embedded-model"))]
+        fn load_embedded(&self) -> crate::models::EmbeddingResult<crate::onnx::OnnxEmbeddingEngine> {
+            Err(crate::EmbeddingError::ConfigError {
+                message: format!(
+                    "Model '{}' uses embedded assets but this binary was built without the embedded-model feature",
+                    self.info.name
+                ),
+            })
+        }
     }
 
     #[async_trait]
@@ -106,13 +316,25 @@ pub mod onnx {
 
         async fn initialize(&mut self) -> crate::models::EmbeddingResult<()> {
             // Initialize the ONNX engine
-            let onnx_config = crate::onnx::OnnxConfig::with_runtime_path(&self.config.onnx_runtime_path);
-            let engine = crate::onnx::OnnxEmbeddingEngine::new(
-                &self.config.model_path,
-                &self.config.tokenizer_path,
-                &onnx_config
-            )?;
+            let engine = if self.config.model_path.starts_with(crate::onnx::EMBEDDED_PREFIX) {
+                self.load_embedded()?
+            } else {
+                let mut onnx_config = crate::onnx::OnnxConfig::with_runtime_path(&self.config.onnx_runtime_path);
+                onnx_config.load_mode = crate::onnx::ModelLoadMode::from_config(&self.config.onnx.load_mode)?;
+                onnx_config.external_data = self.config.onnx.external_data.clone();
+                onnx_config.optimized_cache_dir = self.config.onnx.optimized_cache_dir.clone();
+                onnx_config.deterministic = self.config.deterministic;
+                crate::onnx::OnnxEmbeddingEngine::new(
+                    &self.config.model_path,
+                    &self.config.tokenizer_path,
+                    &onnx_config
+                )?
+            };
+            self.info.load_report = Some(engine.load_report().clone());
+            self.info.precision = engine.precision().as_str().to_string();
+            self.info.vocab_size = Some(engine.vocab_size());
 
+            self.tokenizer = Some(tokenization::for_inspection(engine.tokenizer()));
             self.engine = Some(std::sync::Arc::new(tokio::sync::RwLock::new(engine)));
             Ok(())
         }
@@ -121,6 +343,10 @@ pub mod onnx {
             self.engine.is_some()
         }
 
+        fn tokenizer(&self) -> Option<&tokenizers::Tokenizer> {
+            self.tokenizer.as_ref()
+        }
+
         async fn embed_text(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::Embedding> {
             if let Some(engine) = &self.engine {
                 let mut engine = engine.write().await;
@@ -154,7 +380,37 @@ pub mod onnx {
                         error: e.to_string(),
                     })?;
                 
-                Ok(embeddings.into_iter().map(|e| e).collect())
+                Ok(embeddings)
+            } else {
+                Err(crate::EmbeddingError::ModelNotFound {
+                    model_name: self.info.name.clone(),
+                })
+            }
+        }
+
+        async fn embed_token_ids(&self, input_ids: &[u32]) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            if let Some(engine) = &self.engine {
+                let mut engine = engine.write().await;
+                engine.embed_token_ids(input_ids.to_vec()).await
+                    .map_err(|e| crate::EmbeddingError::InferenceError {
+                        model_name: self.info.name.clone(),
+                        error: e.to_string(),
+                    })
+            } else {
+                Err(crate::EmbeddingError::ModelNotFound {
+                    model_name: self.info.name.clone(),
+                })
+            }
+        }
+
+        async fn embed_text_traced(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::intermediates::Intermediates> {
+            if let Some(engine) = &self.engine {
+                let mut engine = engine.write().await;
+                engine.trace_text(text).await
+                    .map_err(|e| crate::EmbeddingError::InferenceError {
+                        model_name: self.info.name.clone(),
+                        error: e.to_string(),
+                    })
             } else {
                 Err(crate::EmbeddingError::ModelNotFound {
                     model_name: self.info.name.clone(),
@@ -163,48 +419,1135 @@ pub mod onnx {
         }
 
         async fn shutdown(&mut self) -> crate::models::EmbeddingResult<()> {
-            if let Some(engine) = self.engine.take() {
-                // The ONNX engine handles its own cleanup
-                Ok(())
+            // The ONNX engine handles its own cleanup when dropped
+            self.engine = None;
+            self.tokenizer = None;
+            Ok(())
+        }
+    }
+}
+
+/// Candle-based embedding model implementation
+#[cfg(feature = "candle")]
+pub mod candle {
+    use super::*;
+
+    /// Candle embedding model for safetensors checkpoints
+    pub struct CandleEmbeddingModel {
+        info: ModelInfo,
+        engine: Option<Arc<tokio::sync::RwLock<crate::candle::CandleEmbeddingEngine>>>,
+        /// Tokenizer for inspection, set once the engine is loaded
+        tokenizer: Option<tokenizers::Tokenizer>,
+        config: crate::models::config::ModelConfig,
+    }
+
+    impl CandleEmbeddingModel {
+        /// Create a new candle embedding model
+        pub fn new(config: crate::models::config::ModelConfig) -> Self {
+            let info = ModelInfo::from_config(&config);
+
+            Self {
+                info,
+                engine: None,
+                tokenizer: None,
+                config,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingModel for CandleEmbeddingModel {
+        fn info(&self) -> &ModelInfo {
+            &self.info
+        }
+
+        async fn initialize(&mut self) -> crate::models::EmbeddingResult<()> {
+            let engine = crate::candle::CandleEmbeddingEngine::new(
+                &self.config.model_path,
+                &self.config.tokenizer_path,
+                &self.config.config_path,
+                &self.config.pooling_mode,
+                self.config.max_sequence_length,
+                self.config.use_gpu,
+            )?;
+            self.info.vocab_size = Some(engine.vocab_size());
+
+            self.tokenizer = Some(tokenization::for_inspection(engine.tokenizer()));
+            self.engine = Some(Arc::new(tokio::sync::RwLock::new(engine)));
+            Ok(())
+        }
+
+        async fn is_ready(&self) -> bool {
+            self.engine.is_some()
+        }
+
+        fn tokenizer(&self) -> Option<&tokenizers::Tokenizer> {
+            self.tokenizer.as_ref()
+        }
+
+        async fn embed_text(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            let mut embeddings = self.embed_batch(&[text.to_string()]).await?;
+            embeddings.pop().ok_or_else(|| crate::EmbeddingError::InferenceError {
+                model_name: self.info.name.clone(),
+                error: "No embedding returned".to_string(),
+            })
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> crate::models::EmbeddingResult<Vec<crate::models::Embedding>> {
+            if let Some(engine) = &self.engine {
+                let mut engine = engine.write().await;
+                engine.embed_texts(texts.to_vec()).await
+                    .map_err(|e| crate::EmbeddingError::InferenceError {
+                        model_name: self.info.name.clone(),
+                        error: e.to_string(),
+                    })
+            } else {
+                Err(crate::EmbeddingError::ModelNotFound {
+                    model_name: self.info.name.clone(),
+                })
+            }
+        }
+
+        async fn embed_token_ids(&self, input_ids: &[u32]) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            if let Some(engine) = &self.engine {
+                let mut engine = engine.write().await;
+                engine.embed_token_ids(input_ids.to_vec()).await
+                    .map_err(|e| crate::EmbeddingError::InferenceError {
+                        model_name: self.info.name.clone(),
+                        error: e.to_string(),
+                    })
+            } else {
+                Err(crate::EmbeddingError::ModelNotFound {
+                    model_name: self.info.name.clone(),
+                })
+            }
+        }
+
+        async fn embed_text_traced(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::intermediates::Intermediates> {
+            if let Some(engine) = &self.engine {
+                let mut engine = engine.write().await;
+                engine.trace_text(text).await
+                    .map_err(|e| crate::EmbeddingError::InferenceError {
+                        model_name: self.info.name.clone(),
+                        error: e.to_string(),
+                    })
             } else {
-                Ok(())
+                Err(crate::EmbeddingError::ModelNotFound {
+                    model_name: self.info.name.clone(),
+                })
             }
         }
+
+        async fn shutdown(&mut self) -> crate::models::EmbeddingResult<()> {
+            self.engine = None;
+            self.tokenizer = None;
+            Ok(())
+        }
     }
 }
 
-/// Factory for creating embedding models
-pub struct ModelFactory;
+/// Static lookup-table embedding model implementation
+pub mod static_embedding {
+    use super::*;
 
-impl ModelFactory {
-    /// Create a model from configuration
-    pub fn create_model(config: &crate::models::config::ModelConfig) -> Box<dyn EmbeddingModel> {
-        // For now, we only support ONNX models
-        // In the future, this could support different model types
-        Box::new(onnx::OnnxEmbeddingModel::new(config.clone()))
+    /// Lookup-table embedding model (Model2Vec / GloVe style)
+    pub struct StaticEmbeddingModel {
+        info: ModelInfo,
+        engine: Option<Arc<crate::static_embedding::StaticEmbeddingEngine>>,
+        /// Tokenizer for inspection, set once the engine is loaded
+        tokenizer: Option<tokenizers::Tokenizer>,
+        config: crate::models::config::ModelConfig,
+    }
+
+    impl StaticEmbeddingModel {
+        /// Create a new static embedding model
+        pub fn new(config: crate::models::config::ModelConfig) -> Self {
+            let info = ModelInfo::from_config(&config);
+
+            Self {
+                info,
+                engine: None,
+                tokenizer: None,
+                config,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingModel for StaticEmbeddingModel {
+        fn info(&self) -> &ModelInfo {
+            &self.info
+        }
+
+        async fn initialize(&mut self) -> crate::models::EmbeddingResult<()> {
+            let engine = crate::static_embedding::StaticEmbeddingEngine::new(
+                &self.config.model_path,
+                &self.config.tokenizer_path,
+                &self.config.static_embedding,
+                self.config.max_sequence_length,
+            )?;
+
+            if engine.dimension() != self.config.embedding_dimension {
+                return Err(crate::EmbeddingError::ModelLoadError {
+                    model_name: self.info.name.clone(),
+                    error: format!(
+                        "Embedding matrix has dimension {}, but embedding_dimension is {}",
+                        engine.dimension(), self.config.embedding_dimension
+                    ),
+                });
+            }
+
+            self.info.vocab_size = Some(engine.vocab_size());
+            self.tokenizer = Some(tokenization::for_inspection(engine.tokenizer()));
+            self.engine = Some(Arc::new(engine));
+            Ok(())
+        }
+
+        async fn is_ready(&self) -> bool {
+            self.engine.is_some()
+        }
+
+        fn tokenizer(&self) -> Option<&tokenizers::Tokenizer> {
+            self.tokenizer.as_ref()
+        }
+
+        async fn embed_text(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            if let Some(engine) = &self.engine {
+                engine.embed_text(text)
+                    .map_err(|e| inference_error(&self.info.name, e))
+            } else {
+                Err(crate::EmbeddingError::ModelNotFound {
+                    model_name: self.info.name.clone(),
+                })
+            }
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> crate::models::EmbeddingResult<Vec<crate::models::Embedding>> {
+            if let Some(engine) = &self.engine {
+                engine.embed_texts(texts)
+                    .map_err(|e| inference_error(&self.info.name, e))
+            } else {
+                Err(crate::EmbeddingError::ModelNotFound {
+                    model_name: self.info.name.clone(),
+                })
+            }
+        }
+
+        async fn embed_token_ids(&self, input_ids: &[u32]) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            if let Some(engine) = &self.engine {
+                engine.embed_token_ids(input_ids)
+                    .map_err(|e| inference_error(&self.info.name, e))
+            } else {
+                Err(crate::EmbeddingError::ModelNotFound {
+                    model_name: self.info.name.clone(),
+                })
+            }
+        }
+
+        async fn shutdown(&mut self) -> crate::models::EmbeddingResult<()> {
+            self.engine = None;
+            self.tokenizer = None;
+            Ok(())
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
+/// Lexical hashing / TF-IDF embedding model implementation
+pub mod lexical {
     use super::*;
 
-    #[tokio::test]
-    async fn test_model_info() {
-        let info = ModelInfo {
-            name: "test-model".to_string(),
-            description: "Test model".to_string(),
-            version: "1.0.0".to_string(),
-            dimension: 384,
-            max_sequence_length: 256,
-            pooling_mode: "mean".to_string(),
-            uses_gpu: false,
-            model_path: "test/model.onnx".to_string(),
-            tokenizer_path: "test/tokenizer.json".to_string(),
-        };
+    /// Lexical embedding model (no neural weights)
+    pub struct LexicalEmbeddingModel {
+        info: ModelInfo,
+        engine: Option<Arc<crate::lexical::LexicalEmbeddingEngine>>,
+        config: crate::models::config::ModelConfig,
+    }
 
-        assert_eq!(info.name, "test-model");
-        assert_eq!(info.dimension, 384);
-        assert!(!info.uses_gpu);
+    impl LexicalEmbeddingModel {
+        /// Create a new lexical embedding model
+        pub fn new(config: crate::models::config::ModelConfig) -> Self {
+            let info = ModelInfo::from_config(&config);
+
+            Self {
+                info,
+                engine: None,
+                config,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingModel for LexicalEmbeddingModel {
+        fn info(&self) -> &ModelInfo {
+            &self.info
+        }
+
+        async fn initialize(&mut self) -> crate::models::EmbeddingResult<()> {
+            let engine = crate::lexical::LexicalEmbeddingEngine::new(
+                &self.config.lexical,
+                self.config.embedding_dimension,
+            )?;
+
+            self.engine = Some(Arc::new(engine));
+            Ok(())
+        }
+
+        async fn is_ready(&self) -> bool {
+            self.engine.is_some()
+        }
+
+        async fn embed_text(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            if let Some(engine) = &self.engine {
+                engine.embed_text(text)
+                    .map_err(|e| inference_error(&self.info.name, e))
+            } else {
+                Err(crate::EmbeddingError::ModelNotFound {
+                    model_name: self.info.name.clone(),
+                })
+            }
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> crate::models::EmbeddingResult<Vec<crate::models::Embedding>> {
+            if let Some(engine) = &self.engine {
+                engine.embed_texts(texts)
+                    .map_err(|e| inference_error(&self.info.name, e))
+            } else {
+                Err(crate::EmbeddingError::ModelNotFound {
+                    model_name: self.info.name.clone(),
+                })
+            }
+        }
+
+        async fn shutdown(&mut self) -> crate::models::EmbeddingResult<()> {
+            self.engine = None;
+            Ok(())
+        }
+    }
+}
+
+/// Deterministic mock embedding model implementation
+pub mod mock {
+    use super::*;
+    use crate::models::hashing::{fnv1a_64, splitmix64};
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+    /// Mock embedding model for tests
+    ///
+    /// Each text maps to a unit vector derived from a stable hash of the text,
+    /// so the same input always produces the same embedding on every host.
+    /// Latency and failures can be injected through `[models.<id>.mock]`.
+    /// A `tokenizer_path`, when set, is loaded so the tokenize APIs can be tested.
+    pub struct MockEmbeddingModel {
+        info: ModelInfo,
+        config: crate::models::config::MockConfig,
+        tokenizer: Option<tokenizers::Tokenizer>,
+        ready: AtomicBool,
+        /// Request counter driving the failure and jitter sequence
+        requests: AtomicU64,
+    }
+
+    impl MockEmbeddingModel {
+        /// Create a new mock embedding model
+        pub fn new(config: crate::models::config::ModelConfig) -> Self {
+            Self {
+                info: ModelInfo::from_config(&config),
+                config: config.mock,
+                tokenizer: None,
+                ready: AtomicBool::new(false),
+                requests: AtomicU64::new(0),
+            }
+        }
+
+        /// Deterministic unit vector for a text
+        pub fn vector_for(&self, text: &str) -> crate::models::Embedding {
+            self.vector_for_hash(fnv1a_64(text.as_bytes()))
+        }
+
+        /// Deterministic unit vector for a token id sequence
+        pub fn vector_for_token_ids(&self, input_ids: &[u32]) -> crate::models::Embedding {
+            let bytes: Vec<u8> = input_ids.iter().flat_map(|id| id.to_le_bytes()).collect();
+            self.vector_for_hash(fnv1a_64(&bytes))
+        }
+
+        fn vector_for_hash(&self, hash: u64) -> crate::models::Embedding {
+            let mut state = hash ^ self.config.seed;
+            let values: Vec<f32> = (0..self.info.dimension)
+                .map(|_| (splitmix64(&mut state) >> 40) as f32 / (1u64 << 23) as f32 - 1.0)
+                .collect();
+
+            crate::models::pooling::normalize_embedding(&values)
+                .unwrap_or_else(|_| {
+                    let mut unit = vec![0.0; self.info.dimension];
+                    if let Some(first) = unit.first_mut() {
+                        *first = 1.0;
+                    }
+                    unit
+                })
+        }
+
+        /// Apply configured latency and decide whether this request fails
+        async fn simulate_request(&self) -> crate::models::EmbeddingResult<()> {
+            if !self.ready.load(Ordering::Acquire) {
+                return Err(crate::EmbeddingError::ModelNotFound {
+                    model_name: self.info.name.clone(),
+                });
+            }
+
+            let mut state = self.config.seed ^ self.requests.fetch_add(1, Ordering::Relaxed);
+            let roll = (splitmix64(&mut state) >> 11) as f64 / (1u64 << 53) as f64;
+            let jitter = if self.config.latency_jitter_ms > 0 {
+                splitmix64(&mut state) % (self.config.latency_jitter_ms + 1)
+            } else {
+                0
+            };
+
+            let latency = self.config.latency_ms + jitter;
+            if latency > 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(latency)).await;
+            }
+
+            if roll < self.config.failure_rate {
+                return Err(crate::EmbeddingError::InferenceError {
+                    model_name: self.info.name.clone(),
+                    error: "Injected mock failure".to_string(),
+                });
+            }
+
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingModel for MockEmbeddingModel {
+        fn info(&self) -> &ModelInfo {
+            &self.info
+        }
+
+        async fn initialize(&mut self) -> crate::models::EmbeddingResult<()> {
+            if !(0.0..=1.0).contains(&self.config.failure_rate) {
+                return Err(crate::EmbeddingError::ConfigError {
+                    message: format!(
+                        "Mock failure_rate for '{}' must be between 0.0 and 1.0 (got {})",
+                        self.info.name, self.config.failure_rate
+                    ),
+                });
+            }
+
+            if !self.info.tokenizer_path.is_empty() {
+                let tokenizer = crate::models::tokenizer_loader::load_tokenizer(&self.info.tokenizer_path)?;
+                self.info.vocab_size = Some(tokenizer.get_vocab_size(true));
+                self.tokenizer = Some(tokenization::for_inspection(&tokenizer));
+            }
+            if self.config.init_delay_ms > 0 {
+                std::thread::sleep(std::time::Duration::from_millis(self.config.init_delay_ms));
+            }
+
+            self.ready.store(true, Ordering::Release);
+            Ok(())
+        }
+
+        async fn is_ready(&self) -> bool {
+            self.ready.load(Ordering::Acquire)
+        }
+
+        fn tokenizer(&self) -> Option<&tokenizers::Tokenizer> {
+            self.tokenizer.as_ref()
+        }
+
+        async fn embed_text(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            self.simulate_request().await?;
+            Ok(self.vector_for(text))
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> crate::models::EmbeddingResult<Vec<crate::models::Embedding>> {
+            if texts.is_empty() {
+                return Err(crate::EmbeddingError::InvalidInput {
+                    message: "Cannot embed empty text list".to_string(),
+                });
+            }
+
+            self.simulate_request().await?;
+            Ok(texts.iter().map(|text| self.vector_for(text)).collect())
+        }
+
+        async fn embed_token_ids(&self, input_ids: &[u32]) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            self.simulate_request().await?;
+            Ok(self.vector_for_token_ids(input_ids))
+        }
+
+        async fn shutdown(&mut self) -> crate::models::EmbeddingResult<()> {
+            self.ready.store(false, Ordering::Release);
+            Ok(())
+        }
+    }
+
+    /// Table of a mock model for tests
+    ///
+    /// Keys at the top of `extra` replace the defaults; tables after them
+    /// extend the model or, like `[development]`, the whole configuration.
+    #[cfg(test)]
+    pub fn mock_model_toml(name: &str, extra: &str) -> String {
+        let overridden: Vec<&str> = extra
+            .lines()
+            .map(str::trim)
+            .take_while(|line| !line.starts_with('['))
+            .filter_map(|line| line.split_once('=').map(|(key, _)| key.trim()))
+            .collect();
+        let defaults = [
+            ("description", "\"Mock model\""),
+            ("version", "\"1.0.0\""),
+            ("enabled", "true"),
+            ("max_sequence_length", "128"),
+            ("embedding_dimension", "4"),
+            ("pooling_mode", "\"mean\""),
+            ("batch_size", "16"),
+            ("use_gpu", "false"),
+            ("num_threads", "1"),
+            ("backend", "\"mock\""),
+        ];
+        let mut toml = format!("[models.\"{name}\"]\nname = \"{name}\"\n");
+        for (key, value) in defaults.into_iter().filter(|(key, _)| !overridden.contains(key)) {
+            toml.push_str(&format!("{} = {}\n", key, value));
+        }
+        toml.push_str(extra);
+        toml.push('\n');
+        toml
+    }
+
+    /// Configuration of `models` (see `mock_model_toml`) with `default` as the default model
+    #[cfg(test)]
+    pub fn mock_config(default: &str, models: &[String]) -> crate::models::config::EmbeddingModelsConfig {
+        crate::models::config::EmbeddingModelsConfig::from_str(&format!(r#"
+            [global]
+            default_model = "{}"
+            max_batch_size = 32
+            cache_enabled = false
+            cache_size_mb = 0
+            init_timeout = 10
+            inference_timeout = 10
+
+            {}
+        "#, default, models.join("\n"))).unwrap()
+    }
+
+    /// Configuration of a single mock model (see `mock_model_toml`)
+    #[cfg(test)]
+    pub fn mock_model_config(name: &str, extra: &str) -> crate::models::config::ModelConfig {
+        mock_config(name, &[mock_model_toml(name, extra)]).get_model(name).unwrap().clone()
+    }
+}
+
+/// Ensemble model combining other models
+pub mod ensemble {
+    use super::*;
+
+    /// Virtual model combining the embeddings of other loaded models
+    ///
+    /// Members are shared with the registry and run concurrently. Each member
+    /// vector is L2-normalized and scaled by its weight before combining, so
+    /// weights express relative influence regardless of member norms.
+    pub struct EnsembleModel {
+        info: ModelInfo,
+        members: Vec<Arc<dyn EmbeddingModel>>,
+        weights: Vec<f32>,
+        combine: CombineMode,
+        normalize: bool,
+        ready: bool,
+    }
+
+    /// How member embeddings are combined
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CombineMode {
+        /// Join the member vectors end to end
+        Concat,
+        /// Weighted mean of equally sized member vectors
+        Average,
+    }
+
+    impl CombineMode {
+        /// Parse the `combine` value from embeddingmodels.toml
+        pub fn from_config(combine: &str) -> crate::models::EmbeddingResult<Self> {
+            match combine.to_lowercase().as_str() {
+                "concat" => Ok(CombineMode::Concat),
+                "average" => Ok(CombineMode::Average),
+                other => Err(crate::EmbeddingError::ConfigError {
+                    message: format!("Unknown combine mode '{}' (expected concat or average)", other),
+                }),
+            }
+        }
+    }
+
+    impl EnsembleModel {
+        /// Create an ensemble over already loaded member models
+        pub fn new(
+            config: crate::models::config::ModelConfig,
+            members: Vec<Arc<dyn EmbeddingModel>>,
+        ) -> crate::models::EmbeddingResult<Self> {
+            let combine = CombineMode::from_config(&config.ensemble.combine)?;
+            let mut info = ModelInfo::from_config(&config);
+            info.dimension = match combine {
+                CombineMode::Average => members.first().map(|member| member.dimension()).unwrap_or(0),
+                CombineMode::Concat => members.iter().map(|member| member.dimension()).sum(),
+            };
+            if let Some(max_sequence_length) = members.iter().map(|member| member.info().max_sequence_length).min() {
+                info.max_sequence_length = max_sequence_length;
+            }
+
+            let weights = if config.ensemble.weights.is_empty() {
+                vec![1.0; members.len()]
+            } else {
+                config.ensemble.weights.clone()
+            };
+
+            Ok(Self {
+                info,
+                members,
+                weights,
+                combine,
+                normalize: config.ensemble.normalize,
+                ready: false,
+            })
+        }
+
+        /// Combine one embedding per member into the ensemble embedding
+        fn combine(&self, embeddings: Vec<crate::models::Embedding>) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            let mut combined = Vec::with_capacity(self.info.dimension);
+            if self.combine == CombineMode::Average {
+                combined.resize(self.info.dimension, 0.0);
+            }
+
+            let total_weight: f32 = self.weights.iter().sum();
+            for (embedding, &weight) in embeddings.iter().zip(&self.weights) {
+                let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+                let scale = if norm > 0.0 { weight / norm } else { 0.0 };
+
+                match self.combine {
+                    CombineMode::Average => {
+                        for (sum, &value) in combined.iter_mut().zip(embedding) {
+                            *sum += value * scale / total_weight;
+                        }
+                    }
+                    CombineMode::Concat => combined.extend(embedding.iter().map(|&value| value * scale)),
+                }
+            }
+
+            if self.normalize {
+                crate::models::pooling::normalize_embedding(&combined)
+            } else {
+                Ok(combined)
+            }
+        }
+
+        fn not_ready(&self) -> crate::EmbeddingError {
+            crate::EmbeddingError::InferenceError {
+                model_name: self.info.name.clone(),
+                error: "Ensemble not initialized".to_string(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingModel for EnsembleModel {
+        fn info(&self) -> &ModelInfo {
+            &self.info
+        }
+
+        async fn initialize(&mut self) -> crate::models::EmbeddingResult<()> {
+            let load_error = |error: String| crate::EmbeddingError::ModelLoadError {
+                model_name: self.info.name.clone(),
+                error,
+            };
+
+            if self.members.is_empty() {
+                return Err(load_error("Ensemble has no members".to_string()));
+            }
+            if self.weights.len() != self.members.len() {
+                return Err(load_error(format!(
+                    "{} weights for {} members", self.weights.len(), self.members.len()
+                )));
+            }
+            if self.weights.iter().sum::<f32>() <= 0.0 {
+                return Err(load_error("Ensemble weights sum to zero".to_string()));
+            }
+            if self.combine == CombineMode::Average {
+                if let Some(member) = self.members.iter().find(|member| member.dimension() != self.info.dimension) {
+                    return Err(load_error(format!(
+                        "Averaging needs equal dimensions, but '{}' has {} and '{}' has {}",
+                        member.info().name, member.dimension(), self.members[0].info().name, self.info.dimension
+                    )));
+                }
+            }
+            for member in &self.members {
+                if !member.is_ready().await {
+                    return Err(load_error(format!("Member '{}' is not ready", member.info().name)));
+                }
+            }
+
+            self.ready = true;
+            Ok(())
+        }
+
+        async fn is_ready(&self) -> bool {
+            self.ready
+        }
+
+        async fn embed_text(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            let mut embeddings = self.embed_batch(&[text.to_string()]).await?;
+            embeddings.pop().ok_or_else(|| self.not_ready())
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> crate::models::EmbeddingResult<Vec<crate::models::Embedding>> {
+            if !self.ready {
+                return Err(self.not_ready());
+            }
+
+            // Run every member on its own task so slow members overlap
+            let texts: Arc<[String]> = texts.into();
+            let handles: Vec<_> = self.members
+                .iter()
+                .map(|member| {
+                    let member = Arc::clone(member);
+                    let texts = Arc::clone(&texts);
+                    tokio::spawn(async move { member.embed_batch(&texts).await })
+                })
+                .collect();
+
+            let mut per_member = Vec::with_capacity(handles.len());
+            for handle in handles {
+                let embeddings = handle.await.map_err(|e| crate::EmbeddingError::InferenceError {
+                    model_name: self.info.name.clone(),
+                    error: format!("Member task failed: {}", e),
+                })??;
+                per_member.push(embeddings.into_iter());
+            }
+
+            (0..texts.len())
+                .map(|_| {
+                    let embeddings = per_member
+                        .iter_mut()
+                        .map(|embeddings| embeddings.next().ok_or_else(|| self.not_ready()))
+                        .collect::<crate::models::EmbeddingResult<Vec<_>>>()?;
+                    self.combine(embeddings)
+                })
+                .collect()
+        }
+
+        async fn shutdown(&mut self) -> crate::models::EmbeddingResult<()> {
+            // Members belong to the registry and stay loaded; keeping the
+            // references lets the ensemble be initialized again in place
+            self.ready = false;
+            Ok(())
+        }
+    }
+}
+
+/// Text preprocessing applied around any backend
+pub mod preprocessed {
+    use super::*;
+    use crate::models::preprocessing::TextPreprocessor;
+
+    /// Model wrapper that runs the configured preprocessing chain before the
+    /// inner model sees a text, for embedding and tokenizer calls alike
+    pub struct PreprocessedModel {
+        inner: Box<dyn EmbeddingModel>,
+        preprocessor: TextPreprocessor,
+    }
+
+    impl PreprocessedModel {
+        /// Wrap a model with a preprocessing chain
+        pub fn new(inner: Box<dyn EmbeddingModel>, preprocessor: TextPreprocessor) -> Self {
+            Self { inner, preprocessor }
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingModel for PreprocessedModel {
+        fn info(&self) -> &ModelInfo {
+            self.inner.info()
+        }
+
+        async fn initialize(&mut self) -> crate::models::EmbeddingResult<()> {
+            self.inner.initialize().await
+        }
+
+        async fn is_ready(&self) -> bool {
+            self.inner.is_ready().await
+        }
+
+        async fn embed_text(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            self.inner.embed_text(&self.preprocessor.apply(text)).await
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> crate::models::EmbeddingResult<Vec<crate::models::Embedding>> {
+            let texts: Vec<String> = texts.iter().map(|text| self.preprocessor.apply(text)).collect();
+            self.inner.embed_batch(&texts).await
+        }
+
+        async fn embed_token_ids(&self, input_ids: &[u32]) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            self.inner.embed_token_ids(input_ids).await
+        }
+
+        async fn embed_text_traced(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::intermediates::Intermediates> {
+            let preprocessed = self.preprocessor.apply(text);
+            let mut intermediates = self.inner.embed_text_traced(&preprocessed).await?;
+            intermediates.text = text.to_string();
+            intermediates.preprocessed_text = Some(preprocessed);
+            Ok(intermediates)
+        }
+
+        fn tokenizer(&self) -> Option<&tokenizers::Tokenizer> {
+            self.inner.tokenizer()
+        }
+
+        fn tokenize(&self, text: &str, add_special_tokens: bool) -> crate::models::EmbeddingResult<crate::models::TokenizedText> {
+            self.inner.tokenize(&self.preprocessor.apply(text), add_special_tokens)
+        }
+
+        fn decode(&self, ids: &[u32], skip_special_tokens: bool) -> crate::models::EmbeddingResult<String> {
+            self.inner.decode(ids, skip_special_tokens)
+        }
+
+        fn count_tokens(&self, text: &str, add_special_tokens: bool) -> crate::models::EmbeddingResult<usize> {
+            self.inner.count_tokens(&self.preprocessor.apply(text), add_special_tokens)
+        }
+
+        async fn shutdown(&mut self) -> crate::models::EmbeddingResult<()> {
+            self.inner.shutdown().await
+        }
+    }
+}
+
+/// Post-pooling projection applied around any backend
+pub mod projected {
+    use super::*;
+    use crate::models::projection::Projection;
+
+    /// Model wrapper that projects every embedding of the inner model
+    ///
+    /// The projection is loaded during `initialize`, after the inner model,
+    /// and its input dimension must match the backend's `embedding_dimension`.
+    pub struct ProjectedModel {
+        inner: Box<dyn EmbeddingModel>,
+        info: ModelInfo,
+        projection: Option<Projection>,
+        config: crate::models::config::ProjectionConfig,
+    }
+
+    impl ProjectedModel {
+        /// Wrap a model with the projection described by `config`
+        pub fn new(inner: Box<dyn EmbeddingModel>, config: crate::models::config::ProjectionConfig) -> Self {
+            let info = inner.info().clone();
+            Self {
+                inner,
+                info,
+                projection: None,
+                config,
+            }
+        }
+
+        fn project(&self, embedding: crate::models::Embedding) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            match &self.projection {
+                Some(projection) => projection.apply(&embedding),
+                None => Err(crate::EmbeddingError::InferenceError {
+                    model_name: self.info.name.clone(),
+                    error: "Projection not loaded".to_string(),
+                }),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingModel for ProjectedModel {
+        fn info(&self) -> &ModelInfo {
+            &self.info
+        }
+
+        async fn initialize(&mut self) -> crate::models::EmbeddingResult<()> {
+            self.inner.initialize().await?;
+
+            let model_name = self.inner.info().name.clone();
+            let projection = Projection::load(&self.config)
+                .map_err(|e| crate::EmbeddingError::ModelLoadError {
+                    model_name: model_name.clone(),
+                    error: format!("Failed to load projection: {}", e),
+                })?
+                .ok_or_else(|| crate::EmbeddingError::ConfigError {
+                    message: format!("Model '{}' has no projection path", model_name),
+                })?;
+
+            let native_dimension = self.inner.info().dimension;
+            if projection.input_dimension() != native_dimension {
+                return Err(crate::EmbeddingError::ModelLoadError {
+                    model_name,
+                    error: format!(
+                        "Projection expects {}-dimensional input, but embedding_dimension is {}",
+                        projection.input_dimension(), native_dimension
+                    ),
+                });
+            }
+
+            let mut info = self.inner.info().clone();
+            info.dimension = projection.output_dimension();
+            info.projection = Some(ProjectionInfo {
+                path: self.config.path.clone().unwrap_or_default(),
+                input_dimension: native_dimension,
+                output_dimension: projection.output_dimension(),
+                mean_centered: projection.is_mean_centered(),
+                normalize: projection.normalizes(),
+            });
+            self.info = info;
+            self.projection = Some(projection);
+            Ok(())
+        }
+
+        async fn is_ready(&self) -> bool {
+            self.projection.is_some() && self.inner.is_ready().await
+        }
+
+        async fn embed_text(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            self.project(self.inner.embed_text(text).await?)
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> crate::models::EmbeddingResult<Vec<crate::models::Embedding>> {
+            self.inner.embed_batch(texts).await?
+                .into_iter()
+                .map(|embedding| self.project(embedding))
+                .collect()
+        }
+
+        async fn embed_token_ids(&self, input_ids: &[u32]) -> crate::models::EmbeddingResult<crate::models::Embedding> {
+            self.project(self.inner.embed_token_ids(input_ids).await?)
+        }
+
+        async fn embed_text_traced(&self, text: &str) -> crate::models::EmbeddingResult<crate::models::intermediates::Intermediates> {
+            let mut intermediates = self.inner.embed_text_traced(text).await?;
+            intermediates.embedding = self.project(std::mem::take(&mut intermediates.embedding))?;
+            Ok(intermediates)
+        }
+
+        fn tokenizer(&self) -> Option<&tokenizers::Tokenizer> {
+            self.inner.tokenizer()
+        }
+
+        fn tokenize(&self, text: &str, add_special_tokens: bool) -> crate::models::EmbeddingResult<crate::models::TokenizedText> {
+            self.inner.tokenize(text, add_special_tokens)
+        }
+
+        fn decode(&self, ids: &[u32], skip_special_tokens: bool) -> crate::models::EmbeddingResult<String> {
+            self.inner.decode(ids, skip_special_tokens)
+        }
+
+        fn count_tokens(&self, text: &str, add_special_tokens: bool) -> crate::models::EmbeddingResult<usize> {
+            self.inner.count_tokens(text, add_special_tokens)
+        }
+
+        async fn shutdown(&mut self) -> crate::models::EmbeddingResult<()> {
+            self.projection = None;
+            self.info = self.inner.info().clone();
+            self.inner.shutdown().await
+        }
+    }
+}
+
+/// Factory for creating embedding models
+pub struct ModelFactory;
+
+impl ModelFactory {
+    /// Create a model from configuration
+    ///
+    /// The implementation is selected by the model's `backend` setting. It is
+    /// wrapped in the model's projection and preprocessing chain when those
+    /// are configured.
+    pub fn create_model(config: &crate::models::config::ModelConfig) -> crate::models::EmbeddingResult<Box<dyn EmbeddingModel>> {
+        let model = Self::create_backend(config)?;
+        Self::wrap(config, model)
+    }
+
+    /// Create an ensemble model over loaded member models
+    ///
+    /// Members are passed in by the registry, in the order of the
+    /// configuration's `members` list.
+    pub fn create_ensemble(
+        config: &crate::models::config::ModelConfig,
+        members: Vec<Arc<dyn EmbeddingModel>>,
+    ) -> crate::models::EmbeddingResult<Box<dyn EmbeddingModel>> {
+        let model = Box::new(ensemble::EnsembleModel::new(config.clone(), members)?);
+        Self::wrap(config, model)
+    }
+
+    /// Wrap a backend in the model's projection and preprocessing chain
+    fn wrap(
+        config: &crate::models::config::ModelConfig,
+        mut model: Box<dyn EmbeddingModel>,
+    ) -> crate::models::EmbeddingResult<Box<dyn EmbeddingModel>> {
+        let preprocessor = crate::models::preprocessing::TextPreprocessor::from_config(&config.preprocessing)?;
+
+        if config.projection.path.is_some() {
+            model = Box::new(projected::ProjectedModel::new(model, config.projection.clone()));
+        }
+
+        if preprocessor.is_identity() {
+            Ok(model)
+        } else {
+            Ok(Box::new(preprocessed::PreprocessedModel::new(model, preprocessor)))
+        }
+    }
+
+    /// Create the backend implementation for a model
+    fn create_backend(config: &crate::models::config::ModelConfig) -> crate::models::EmbeddingResult<Box<dyn EmbeddingModel>> {
+        match config.backend.as_str() {
+            #[cfg(feature = "onnx")]
+            "onnx" => Ok(Box::new(onnx::OnnxEmbeddingModel::new(config.clone()))),
+            #[cfg(feature = "candle")]
+            "candle" => Ok(Box::new(candle::CandleEmbeddingModel::new(config.clone()))),
+            "static" => Ok(Box::new(static_embedding::StaticEmbeddingModel::new(config.clone()))),
+            "lexical" => Ok(Box::new(lexical::LexicalEmbeddingModel::new(config.clone()))),
+            "mock" => Ok(Box::new(mock::MockEmbeddingModel::new(config.clone()))),
+            "ensemble" => Err(crate::models::EmbeddingError::ConfigError {
+                message: format!("Ensemble '{}' must be loaded through the model registry", config.id()),
+            }),
+            other => Err(crate::models::EmbeddingError::ConfigError {
+                message: format!(
+                    "Backend '{}' for model '{}' is unknown or not enabled in this build",
+                    other, config.id()
+                ),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_model_info() {
+        let info = ModelInfo {
+            name: "test-model".to_string(),
+            display_name: "Test Model".to_string(),
+            description: "Test model".to_string(),
+            version: "1.0.0".to_string(),
+            dimension: 384,
+            max_sequence_length: 256,
+            pooling_mode: "mean".to_string(),
+            uses_gpu: false,
+            model_path: "test/model.onnx".to_string(),
+            tokenizer_path: "test/tokenizer.json".to_string(),
+            backend: "onnx".to_string(),
+            precision: "f32".to_string(),
+            load_report: None,
+            vocab_size: None,
+            preprocessing: Vec::new(),
+            projection: None,
+            warmup: None,
+            deterministic: false,
+            fingerprint: None,
+            memory_bytes: None,
+            lifecycle: None,
+        };
+
+        assert_eq!(info.name, "test-model");
+        assert_eq!(info.dimension, 384);
+        assert!(!info.uses_gpu);
+    }
+
+    #[test]
+    fn test_factory_rejects_unknown_backend() {
+        let config: crate::models::config::ModelConfig = toml::from_str(r#"
+            name = "Test Model"
+            description = "A test model"
+            version = "1.0.0"
+            enabled = true
+            model_path = "test/model.safetensors"
+            tokenizer_path = "test/tokenizer.json"
+            config_path = "test/config.json"
+            max_sequence_length = 256
+            embedding_dimension = 384
+            pooling_mode = "mean"
+            batch_size = 16
+            use_gpu = false
+            num_threads = 4
+            onnx_runtime_path = "runtime"
+            execution_provider = "CPU"
+            backend = "tensorflow"
+        "#).unwrap();
+
+        assert!(ModelFactory::create_model(&config).is_err());
+
+        // Backends compiled out of this build are rejected the same way
+        #[cfg(not(feature = "onnx"))]
+        {
+            let mut onnx_config = config.clone();
+            onnx_config.backend = "onnx".to_string();
+            assert!(ModelFactory::create_model(&onnx_config).is_err());
+        }
+    }
+
+    #[cfg(all(feature = "onnx", not(feature = "embedded-model")))]
+    #[tokio::test]
+    async fn test_embedded_assets_require_feature() {
+        let config: crate::models::config::ModelConfig = toml::from_str(r#"
+            name = "embedded"
+            description = "Embedded model"
+            version = "1.0.0"
+            enabled = true
+            model_path = "embedded://model.onnx"
+            tokenizer_path = "embedded://tokenizer.json"
+            max_sequence_length = 256
+            embedding_dimension = 384
+            pooling_mode = "mean"
+            batch_size = 16
+            use_gpu = false
+            num_threads = 4
+        "#).unwrap();
+
+        let mut model = ModelFactory::create_model(&config).unwrap();
+        assert!(matches!(
+            model.initialize().await,
+            Err(crate::EmbeddingError::ConfigError { .. })
+        ));
+    }
+
+    fn mock_config(extra: &str) -> crate::models::config::ModelConfig {
+        mock::mock_model_config("mock-model", &format!("max_sequence_length = 256\nembedding_dimension = 32\n{}", extra))
+    }
+
+    #[tokio::test]
+    async fn test_mock_model_is_deterministic() {
+        let mut model = ModelFactory::create_model(&mock_config("")).unwrap();
+        assert!(model.embed_text("hello").await.is_err());
+        model.initialize().await.unwrap();
+
+        let a = model.embed_text("hello").await.unwrap();
+        let b = model.embed_batch(&["hello".to_string(), "world".to_string()]).await.unwrap();
+        assert_eq!(a.len(), 32);
+        assert_eq!(a, b[0]);
+        assert_ne!(b[0], b[1]);
+
+        let norm: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn test_mock_model_failure_injection() {
+        let mut model = ModelFactory::create_model(&mock_config("[models.mock-model.mock]\nfailure_rate = 1.0")).unwrap();
+        model.initialize().await.unwrap();
+        assert!(model.embed_text("hello").await.is_err());
+
+        let mut invalid = ModelFactory::create_model(&mock_config("[models.mock-model.mock]\nfailure_rate = 1.5")).unwrap();
+        assert!(invalid.initialize().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_preprocessing_wraps_backend() {
+        let config = mock_config("[models.mock-model.preprocessing]\nlowercase = true\ncollapse_whitespace = true");
+        let mut model = ModelFactory::create_model(&config).unwrap();
+        model.initialize().await.unwrap();
+
+        assert_eq!(model.info().preprocessing, ["lowercase", "collapse_whitespace"]);
+        assert_eq!(model.embed_text("  Hello   WORLD ").await.unwrap(), model.embed_text("hello world").await.unwrap());
+
+        let invalid = mock_config("[models.mock-model.preprocessing]\nunicode_normalization = \"nfd\"");
+        assert!(ModelFactory::create_model(&invalid).is_err());
     }
 }
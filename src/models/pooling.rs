@@ -0,0 +1,298 @@
+//! Pooling and normalization shared by all inference backends
+//!
+//! Backends produce a `last_hidden_state` tensor of shape
+//! `[batch_size, seq_len, hidden_size]`; the functions here reduce each
+//! sequence of it to a single embedding vector. They work on the contiguous
+//! output buffer directly, so backends can pass the runtime's slice without
+//! copying it into an ndarray first (`pool_batch`). Microbenchmarks against
+//! the element-indexing implementation live in `benches/pooling.rs`.
+
+use crate::models::{EmbeddingError, EmbeddingResult};
+use ndarray::ArrayViewD;
+
+/// Pooling strategy applied over the sequence dimension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolingMode {
+    /// Average of all non-padding token embeddings
+    Mean,
+    /// Embedding of the first ([CLS]) token
+    Cls,
+    /// Element-wise maximum over non-padding token embeddings
+    Max,
+}
+
+impl PoolingMode {
+    /// Parse the `pooling_mode` value from embeddingmodels.toml
+    pub fn from_config(pooling_mode: &str) -> EmbeddingResult<Self> {
+        match pooling_mode.to_lowercase().as_str() {
+            "mean" => Ok(PoolingMode::Mean),
+            "cls" => Ok(PoolingMode::Cls),
+            "max" => Ok(PoolingMode::Max),
+            other => Err(EmbeddingError::ConfigError {
+                message: format!("Unknown pooling mode '{}' (expected mean, cls or max)", other),
+            }),
+        }
+    }
+}
+
+/// Pool a single sequence with the given strategy
+///
+/// # Arguments
+/// * `mode` - Pooling strategy
+/// * `output_tensor` - Output tensor from the model [1, seq_len, hidden_size]
+/// * `attention_mask` - Attention mask indicating which tokens are real (1) vs padding (0)
+pub fn pool(mode: PoolingMode, output_tensor: &ArrayViewD<f32>, attention_mask: &[u32]) -> Result<Vec<f32>, EmbeddingError> {
+    let (seq_len, hidden_size) = check_shape(output_tensor, attention_mask)?;
+    let contiguous = output_tensor.as_standard_layout();
+    let data = contiguous.as_slice().ok_or_else(|| EmbeddingError::EmbeddingFailed {
+        error: "Output tensor is not contiguous".to_string(),
+    })?;
+    pool_sequence(mode, &data[..seq_len * hidden_size], hidden_size, attention_mask)
+}
+
+/// Pool every sequence of a contiguous `[batch_size, seq_len, hidden_size]` buffer
+///
+/// This is the fast path: backends hand over the runtime's output slice as-is
+/// and `attention_mask` holds the `batch_size * seq_len` mask values row by row.
+pub fn pool_batch(
+    mode: PoolingMode,
+    hidden: &[f32],
+    dims: &[usize],
+    attention_mask: &[u32],
+) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+    let &[batch_size, seq_len, hidden_size] = dims else {
+        return Err(EmbeddingError::EmbeddingFailed {
+            error: format!("Expected 3D output tensor, got {}D", dims.len()),
+        });
+    };
+    if hidden.len() != batch_size * seq_len * hidden_size || attention_mask.len() != batch_size * seq_len {
+        return Err(EmbeddingError::EmbeddingFailed {
+            error: format!(
+                "Output of {} values and attention mask of {} values don't match shape {:?}",
+                hidden.len(), attention_mask.len(), dims
+            ),
+        });
+    }
+    if seq_len == 0 || hidden_size == 0 {
+        return Err(EmbeddingError::EmbeddingFailed {
+            error: format!("Cannot pool an output tensor of shape {:?}", dims),
+        });
+    }
+
+    hidden
+        .chunks_exact(seq_len * hidden_size)
+        .zip(attention_mask.chunks_exact(seq_len))
+        .map(|(sequence, mask)| pool_sequence(mode, sequence, hidden_size, mask))
+        .collect()
+}
+
+/// Pool one contiguous `[seq_len, hidden_size]` sequence, with `seq_len = attention_mask.len()`
+pub fn pool_sequence(
+    mode: PoolingMode,
+    hidden: &[f32],
+    hidden_size: usize,
+    attention_mask: &[u32],
+) -> Result<Vec<f32>, EmbeddingError> {
+    if hidden_size == 0 || hidden.len() != attention_mask.len() * hidden_size {
+        return Err(EmbeddingError::EmbeddingFailed {
+            error: format!(
+                "Attention mask length {} doesn't match sequence length {}",
+                attention_mask.len(),
+                hidden.len().checked_div(hidden_size).unwrap_or(0)
+            ),
+        });
+    }
+
+    match mode {
+        PoolingMode::Mean => mean_rows(hidden, hidden_size, attention_mask),
+        PoolingMode::Cls => hidden.chunks_exact(hidden_size).next().map(<[f32]>::to_vec).ok_or_else(|| {
+            EmbeddingError::EmbeddingFailed {
+                error: "Cannot apply CLS pooling to an empty sequence".to_string(),
+            }
+        }),
+        PoolingMode::Max => max_rows(hidden, hidden_size, attention_mask),
+    }
+}
+
+/// Check the tensor rank and mask length, returning `(seq_len, hidden_size)`
+fn check_shape(output_tensor: &ArrayViewD<f32>, attention_mask: &[u32]) -> Result<(usize, usize), EmbeddingError> {
+    let shape = output_tensor.shape();
+    if shape.len() != 3 {
+        return Err(EmbeddingError::EmbeddingFailed {
+            error: format!("Expected 3D output tensor, got {}D", shape.len()),
+        });
+    }
+
+    let seq_len = shape[1];
+    let hidden_size = shape[2];
+
+    if attention_mask.len() != seq_len {
+        return Err(EmbeddingError::EmbeddingFailed {
+            error: format!("Attention mask length {} doesn't match sequence length {}", attention_mask.len(), seq_len),
+        });
+    }
+
+    Ok((seq_len, hidden_size))
+}
+
+/// Apply mean pooling to the token embeddings
+///
+/// # Arguments
+/// * `output_tensor` - Output tensor from the model [batch_size, seq_len, hidden_size]
+/// * `attention_mask` - Attention mask indicating which tokens are real (1) vs padding (0)
+///
+/// # Returns
+/// Mean-pooled embedding vector
+pub fn mean_pooling(output_tensor: &ArrayViewD<f32>, attention_mask: &[u32]) -> Result<Vec<f32>, EmbeddingError> {
+    pool(PoolingMode::Mean, output_tensor, attention_mask)
+}
+
+/// Use the first token's embedding as the sequence embedding
+pub fn cls_pooling(output_tensor: &ArrayViewD<f32>, attention_mask: &[u32]) -> Result<Vec<f32>, EmbeddingError> {
+    pool(PoolingMode::Cls, output_tensor, attention_mask)
+}
+
+/// Take the element-wise maximum over valid tokens
+pub fn max_pooling(output_tensor: &ArrayViewD<f32>, attention_mask: &[u32]) -> Result<Vec<f32>, EmbeddingError> {
+    pool(PoolingMode::Max, output_tensor, attention_mask)
+}
+
+/// Average the rows whose mask value is 1
+///
+/// Rows are added whole with zipped iterators, which the compiler turns into
+/// SIMD adds; indexing element by element through ndarray does not vectorize.
+fn mean_rows(hidden: &[f32], hidden_size: usize, attention_mask: &[u32]) -> Result<Vec<f32>, EmbeddingError> {
+    let mut pooled = vec![0.0f32; hidden_size];
+    let mut valid_tokens = 0;
+
+    for (row, _) in hidden.chunks_exact(hidden_size).zip(attention_mask).filter(|(_, &mask)| mask == 1) {
+        for (sum, value) in pooled.iter_mut().zip(row) {
+            *sum += value;
+        }
+        valid_tokens += 1;
+    }
+
+    if valid_tokens == 0 {
+        return Err(EmbeddingError::EmbeddingFailed {
+            error: "No valid tokens found in attention mask".to_string(),
+        });
+    }
+
+    let count = valid_tokens as f32;
+    for value in &mut pooled {
+        *value /= count;
+    }
+    Ok(pooled)
+}
+
+/// Element-wise maximum of the rows whose mask value is 1
+fn max_rows(hidden: &[f32], hidden_size: usize, attention_mask: &[u32]) -> Result<Vec<f32>, EmbeddingError> {
+    let mut rows = hidden.chunks_exact(hidden_size).zip(attention_mask).filter(|(_, &mask)| mask == 1);
+    let Some((first, _)) = rows.next() else {
+        return Err(EmbeddingError::EmbeddingFailed {
+            error: "No valid tokens found in attention mask".to_string(),
+        });
+    };
+
+    let mut pooled = first.to_vec();
+    for (row, _) in rows {
+        for (max, value) in pooled.iter_mut().zip(row) {
+            *max = max.max(*value);
+        }
+    }
+    Ok(pooled)
+}
+
+/// Sum of squares, accumulated in independent lanes so the loop vectorizes
+pub fn squared_norm(values: &[f32]) -> f32 {
+    const LANES: usize = 8;
+    let mut lanes = [0.0f32; LANES];
+    let chunks = values.chunks_exact(LANES);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        for (lane, value) in lanes.iter_mut().zip(chunk) {
+            *lane += value * value;
+        }
+    }
+    lanes.iter().sum::<f32>() + tail.iter().map(|x| x * x).sum::<f32>()
+}
+
+/// Normalize embedding using L2 normalization
+///
+/// # Arguments
+/// * `embedding` - Input embedding vector
+///
+/// # Returns
+/// L2-normalized embedding vector
+pub fn normalize_embedding(embedding: &[f32]) -> Result<Vec<f32>, EmbeddingError> {
+    let mut normalized = embedding.to_vec();
+    normalize_in_place(&mut normalized)?;
+    Ok(normalized)
+}
+
+/// L2-normalize an embedding without allocating
+pub fn normalize_in_place(embedding: &mut [f32]) -> Result<(), EmbeddingError> {
+    let norm = squared_norm(embedding).sqrt();
+
+    if norm == 0.0 {
+        return Err(EmbeddingError::EmbeddingFailed {
+            error: "Cannot normalize zero vector".to_string(),
+        });
+    }
+
+    for value in embedding.iter_mut() {
+        *value /= norm;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_pooling_modes() {
+        // Two real tokens and one padding token, hidden size 2
+        let hidden = Array3::from_shape_vec((1, 3, 2), vec![1.0, 4.0, 3.0, 2.0, 100.0, 100.0]).unwrap();
+        let view = hidden.view().into_dyn();
+        let mask = [1, 1, 0];
+
+        assert_eq!(pool(PoolingMode::Mean, &view, &mask).unwrap(), vec![2.0, 3.0]);
+        assert_eq!(pool(PoolingMode::Cls, &view, &mask).unwrap(), vec![1.0, 4.0]);
+        assert_eq!(pool(PoolingMode::Max, &view, &mask).unwrap(), vec![3.0, 4.0]);
+        assert!(pool(PoolingMode::Mean, &view, &[1, 1]).is_err());
+    }
+
+    #[test]
+    fn test_normalize_embedding() {
+        let normalized = normalize_embedding(&[3.0, 4.0]).unwrap();
+        assert_eq!(normalized, vec![0.6, 0.8]);
+        assert!(normalize_embedding(&[0.0, 0.0]).is_err());
+        assert!(PoolingMode::from_config("weighted").is_err());
+    }
+
+    #[test]
+    fn test_pool_batch_matches_ndarray_path() {
+        // Two sequences of three tokens, hidden size 2, the second with one padding token
+        let data = vec![1.0, 4.0, 3.0, 2.0, 5.0, 0.0, -1.0, 2.0, 7.0, -3.0, 100.0, 100.0];
+        let mask = [1, 1, 1, 1, 1, 0];
+        let hidden = Array3::from_shape_vec((2, 3, 2), data.clone()).unwrap();
+
+        for mode in [PoolingMode::Mean, PoolingMode::Cls, PoolingMode::Max] {
+            let batched = pool_batch(mode, &data, &[2, 3, 2], &mask).unwrap();
+            for (idx, pooled) in batched.iter().enumerate() {
+                let sequence = hidden.slice(ndarray::s![idx..idx + 1, .., ..]).into_dyn();
+                assert_eq!(pooled, &pool(mode, &sequence, &mask[idx * 3..idx * 3 + 3]).unwrap());
+            }
+        }
+        assert!(pool_batch(PoolingMode::Mean, &data, &[2, 3], &mask).is_err());
+        assert!(pool_batch(PoolingMode::Mean, &data, &[2, 3, 2], &mask[..5]).is_err());
+    }
+
+    #[test]
+    fn test_squared_norm() {
+        let values: Vec<f32> = (1..=19).map(|x| x as f32).collect();
+        assert_eq!(squared_norm(&values), values.iter().map(|x| x * x).sum::<f32>());
+    }
+}
@@ -0,0 +1,100 @@
+//! Numeric precision of model outputs and of embeddings on the wire
+//!
+//! Half-precision (fp16/bf16) exports halve a model's memory footprint. The
+//! engines widen their outputs to f32 for pooling, and clients may ask for
+//! half-precision embeddings to halve response sizes.
+
+use crate::models::EmbeddingError;
+use half::{bf16, f16};
+use serde::{Deserialize, Serialize};
+
+/// Floating-point precision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Precision {
+    F32,
+    F16,
+    Bf16,
+}
+
+impl Precision {
+    /// Parse a precision name ("f32", "f16", "bf16" and common aliases)
+    pub fn from_config(precision: &str) -> Result<Self, EmbeddingError> {
+        match precision.to_lowercase().as_str() {
+            "f32" | "fp32" | "float32" => Ok(Precision::F32),
+            "f16" | "fp16" | "float16" => Ok(Precision::F16),
+            "bf16" | "bfloat16" => Ok(Precision::Bf16),
+            other => Err(EmbeddingError::InvalidInput {
+                message: format!("Unknown precision '{}' (expected f32, f16 or bf16)", other),
+            }),
+        }
+    }
+
+    /// Canonical name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Precision::F32 => "f32",
+            Precision::F16 => "f16",
+            Precision::Bf16 => "bf16",
+        }
+    }
+
+    /// Bytes per value
+    pub fn size_of(&self) -> usize {
+        match self {
+            Precision::F32 => 4,
+            Precision::F16 | Precision::Bf16 => 2,
+        }
+    }
+}
+
+/// Encode an embedding as little-endian values of the given precision
+pub fn encode(embedding: &[f32], precision: Precision) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(embedding.len() * precision.size_of());
+    for &value in embedding {
+        match precision {
+            Precision::F32 => bytes.extend_from_slice(&value.to_le_bytes()),
+            Precision::F16 => bytes.extend_from_slice(&f16::from_f32(value).to_le_bytes()),
+            Precision::Bf16 => bytes.extend_from_slice(&bf16::from_f32(value).to_le_bytes()),
+        }
+    }
+    bytes
+}
+
+/// Decode little-endian values of the given precision back to f32
+pub fn decode(bytes: &[u8], precision: Precision) -> Result<Vec<f32>, EmbeddingError> {
+    if !bytes.len().is_multiple_of(precision.size_of()) {
+        return Err(EmbeddingError::InvalidInput {
+            message: format!("{} bytes is not a whole number of {} values", bytes.len(), precision.as_str()),
+        });
+    }
+
+    Ok(bytes
+        .chunks_exact(precision.size_of())
+        .map(|chunk| match precision {
+            Precision::F32 => f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+            Precision::F16 => f16::from_le_bytes([chunk[0], chunk[1]]).to_f32(),
+            Precision::Bf16 => bf16::from_le_bytes([chunk[0], chunk[1]]).to_f32(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_half_precision_round_trip() {
+        let embedding = vec![0.5f32, -0.25, 0.1234];
+        for precision in [Precision::F32, Precision::F16, Precision::Bf16] {
+            let bytes = encode(&embedding, precision);
+            assert_eq!(bytes.len(), embedding.len() * precision.size_of());
+            let decoded = decode(&bytes, precision).unwrap();
+            for (a, b) in embedding.iter().zip(&decoded) {
+                assert!((a - b).abs() < 1e-2);
+            }
+        }
+        assert!(decode(&[0, 0, 0], Precision::F16).is_err());
+        assert_eq!(Precision::from_config("FP16").unwrap(), Precision::F16);
+    }
+}
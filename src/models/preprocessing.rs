@@ -0,0 +1,386 @@
+//! Text preprocessing
+//!
+//! Each model can clean up its input before tokenization, configured under
+//! `[models.<id>.preprocessing]`. The chain runs inside the model itself, so
+//! HTTP and OVNT requests (and the tokenizer APIs) see exactly the same text,
+//! and clients no longer need their own cleanup.
+
+use crate::models::config::PreprocessingConfig;
+use crate::models::EmbeddingError;
+use tokenizers::NormalizedString;
+
+/// Unicode normalization form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodeForm {
+    Nfc,
+    Nfkc,
+}
+
+/// Per-model text cleanup chain
+#[derive(Debug, Clone, Default)]
+pub struct TextPreprocessor {
+    normalization: Option<UnicodeForm>,
+    lowercase: bool,
+    strip_html: bool,
+    strip_markdown: bool,
+    remove_control_chars: bool,
+    collapse_whitespace: bool,
+    max_chars: Option<usize>,
+}
+
+impl TextPreprocessor {
+    /// Build the chain from `[models.<id>.preprocessing]`
+    pub fn from_config(config: &PreprocessingConfig) -> Result<Self, EmbeddingError> {
+        let normalization = match config.unicode_normalization.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("none") => None,
+            Some("nfc") => Some(UnicodeForm::Nfc),
+            Some("nfkc") => Some(UnicodeForm::Nfkc),
+            Some(other) => {
+                return Err(EmbeddingError::ConfigError {
+                    message: format!("Unknown unicode_normalization '{}' (expected nfc, nfkc or none)", other),
+                });
+            }
+        };
+
+        if config.max_chars == Some(0) {
+            return Err(EmbeddingError::ConfigError {
+                message: "Preprocessing max_chars must be greater than zero".to_string(),
+            });
+        }
+
+        Ok(Self {
+            normalization,
+            lowercase: config.lowercase,
+            strip_html: config.strip_html,
+            strip_markdown: config.strip_markdown,
+            remove_control_chars: config.remove_control_chars,
+            collapse_whitespace: config.collapse_whitespace,
+            max_chars: config.max_chars,
+        })
+    }
+
+    /// Names of the enabled steps in the order they run, as reported in the model info
+    pub fn steps(&self) -> Vec<String> {
+        let mut steps = Vec::new();
+        if self.strip_html {
+            steps.push("strip_html".to_string());
+        }
+        if self.strip_markdown {
+            steps.push("strip_markdown".to_string());
+        }
+        match self.normalization {
+            Some(UnicodeForm::Nfc) => steps.push("nfc".to_string()),
+            Some(UnicodeForm::Nfkc) => steps.push("nfkc".to_string()),
+            None => {}
+        }
+        if self.remove_control_chars {
+            steps.push("remove_control_chars".to_string());
+        }
+        if self.lowercase {
+            steps.push("lowercase".to_string());
+        }
+        if self.collapse_whitespace {
+            steps.push("collapse_whitespace".to_string());
+        }
+        if let Some(max_chars) = self.max_chars {
+            steps.push(format!("max_chars={}", max_chars));
+        }
+        steps
+    }
+
+    /// Whether the chain leaves every text unchanged
+    pub fn is_identity(&self) -> bool {
+        self.steps().is_empty()
+    }
+
+    /// Run the chain over a text
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+
+        if self.strip_html {
+            text = strip_html(&text);
+        }
+        if self.strip_markdown {
+            text = strip_markdown(&text);
+        }
+        if let Some(form) = self.normalization {
+            let mut normalized = NormalizedString::from(text.as_str());
+            match form {
+                UnicodeForm::Nfc => normalized.nfc(),
+                UnicodeForm::Nfkc => normalized.nfkc(),
+            };
+            text = normalized.get().to_string();
+        }
+        if self.remove_control_chars {
+            text.retain(|c| !c.is_control() || c.is_whitespace());
+        }
+        if self.lowercase {
+            text = text.to_lowercase();
+        }
+        if self.collapse_whitespace {
+            text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        if let Some(max_chars) = self.max_chars {
+            if let Some((cut, _)) = text.char_indices().nth(max_chars) {
+                text.truncate(cut);
+            }
+        }
+
+        text
+    }
+}
+
+/// Tags whose removal should leave a word break behind
+const BLOCK_TAGS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "br", "dd", "div", "dl", "dt", "footer", "h1", "h2", "h3",
+    "h4", "h5", "h6", "header", "hr", "li", "main", "nav", "ol", "p", "pre", "section", "table", "td", "th",
+    "tr", "ul",
+];
+
+/// Remove HTML tags and comments, drop script/style content and decode entities
+fn strip_html(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        let tag = &rest[start..];
+
+        // A '<' not followed by a tag name is text ("a < b")
+        let is_tag = tag[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        let Some(end) = tag.find('>').filter(|_| is_tag) else {
+            output.push('<');
+            rest = &tag[1..];
+            continue;
+        };
+
+        if tag.starts_with("<!--") {
+            rest = match tag.find("-->") {
+                Some(close) => &tag[close + 3..],
+                None => "",
+            };
+            continue;
+        }
+
+        let name: String = tag[1..end]
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if (name == "script" || name == "style") && !tag[1..].starts_with('/') {
+            // ASCII lowercasing keeps byte offsets, so indices carry over
+            let closing = format!("</{}", name);
+            rest = match tag.to_ascii_lowercase().find(&closing) {
+                Some(close) => tag[close..].find('>').map(|gt| &tag[close + gt + 1..]).unwrap_or(""),
+                None => "",
+            };
+            continue;
+        }
+
+        if BLOCK_TAGS.contains(&name.as_str()) {
+            output.push(' ');
+        }
+        rest = &tag[end + 1..];
+    }
+    output.push_str(rest);
+
+    decode_entities(&output)
+}
+
+/// Decode named and numeric HTML character references
+fn decode_entities(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        output.push_str(&rest[..amp]);
+        let reference = &rest[amp..];
+        let decoded = reference
+            .char_indices()
+            .take(12)
+            .find(|(_, c)| *c == ';')
+            .and_then(|(semi, _)| entity_char(&reference[1..semi]).map(|c| (c, semi)));
+
+        match decoded {
+            Some((c, semi)) => {
+                output.push(c);
+                rest = &reference[semi + 1..];
+            }
+            None => {
+                output.push('&');
+                rest = &reference[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn entity_char(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let number = entity.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+/// Remove Markdown block markers and inline markup, keeping the text
+fn strip_markdown(text: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        // Horizontal rules
+        let compact: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+        if compact.len() >= 3 && ["-", "*", "_"].iter().any(|rule| compact.chars().all(|c| c.to_string() == *rule)) {
+            lines.push(String::new());
+            continue;
+        }
+
+        let mut content = trimmed;
+        while let Some(quoted) = content.strip_prefix('>') {
+            content = quoted.trim_start();
+        }
+        let hashes = content.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&hashes) && content[hashes..].starts_with(' ') {
+            content = content[hashes..].trim_start();
+        }
+        content = strip_list_marker(content);
+
+        lines.push(strip_markdown_inline(content));
+    }
+
+    lines.join("\n")
+}
+
+fn strip_list_marker(line: &str) -> &str {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return item;
+        }
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let marker = &line[digits..];
+        if marker.starts_with(". ") || marker.starts_with(") ") {
+            return &marker[2..];
+        }
+    }
+    line
+}
+
+/// Strip links, images, code spans and emphasis from one line
+fn strip_markdown_inline(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut output = String::with_capacity(line.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let link_start = match c {
+            '!' if chars.get(i + 1) == Some(&'[') => Some(i + 1),
+            '[' => Some(i),
+            _ => None,
+        };
+        if let Some((text_end, url_end)) = link_start.and_then(|open| find_link(&chars, open)) {
+            let label: String = chars[link_start.unwrap_or(i) + 1..text_end].iter().collect();
+            output.push_str(&strip_markdown_inline(&label));
+            i = url_end + 1;
+            continue;
+        }
+
+        match c {
+            '`' => {}
+            '*' | '_' | '~' if chars.get(i + 1) == Some(&c) => i += 1,
+            '*' | '_' => {
+                let before = i.checked_sub(1).map(|j| chars[j]);
+                let after = chars.get(i + 1).copied();
+                let boundary = |c: Option<char>| c.is_none_or(|c| c.is_whitespace() || c.is_ascii_punctuation());
+                let opening = boundary(before) && after.is_some_and(|c| !c.is_whitespace());
+                let closing = before.is_some_and(|c| !c.is_whitespace()) && boundary(after);
+                if !(opening || closing) {
+                    output.push(c);
+                }
+            }
+            _ => output.push(c),
+        }
+        i += 1;
+    }
+
+    output
+}
+
+/// Find `[text](url)` starting at `open`; returns the indices of `]` and `)`
+fn find_link(chars: &[char], open: usize) -> Option<(usize, usize)> {
+    let text_end = open + chars[open..].iter().position(|&c| c == ']')?;
+    if chars.get(text_end + 1) != Some(&'(') {
+        return None;
+    }
+    let url_end = text_end + 1 + chars[text_end + 1..].iter().position(|&c| c == ')')?;
+    Some((text_end, url_end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preprocessor(config: PreprocessingConfig) -> TextPreprocessor {
+        TextPreprocessor::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn test_strip_html() {
+        let html = "<p>Fish &amp; chips</p><script>alert('x')</script><div>caf&#233; <b>now</b></div><!-- note -->";
+        assert_eq!(strip_html(html), " Fish & chips  café now ");
+        assert_eq!(strip_html("a < b and c > d"), "a < b and c > d");
+    }
+
+    #[test]
+    fn test_strip_markdown() {
+        let markdown = "# Title\n\n> - **Bold** and _italic_ with [a link](https://example.com)\n```rust\nlet snake_case = 2 * 3;\n```\n---";
+        assert_eq!(strip_markdown(markdown), "Title\n\nBold and italic with a link\nlet snake_case = 2 * 3;\n");
+    }
+
+    #[test]
+    fn test_chain_order_and_steps() {
+        let chain = preprocessor(PreprocessingConfig {
+            unicode_normalization: Some("NFKC".to_string()),
+            lowercase: true,
+            strip_html: true,
+            remove_control_chars: true,
+            collapse_whitespace: true,
+            max_chars: Some(12),
+            ..Default::default()
+        });
+        assert_eq!(chain.apply("<h1>ＨＥＬＬＯ</h1>\u{0007}\n\n  Wörld  again"), "hello wörld ");
+        assert_eq!(chain.steps(), ["strip_html", "nfkc", "remove_control_chars", "lowercase", "collapse_whitespace", "max_chars=12"]);
+
+        assert!(preprocessor(PreprocessingConfig::default()).is_identity());
+        let invalid = PreprocessingConfig { unicode_normalization: Some("nfd".to_string()), ..Default::default() };
+        assert!(TextPreprocessor::from_config(&invalid).is_err());
+    }
+}
@@ -0,0 +1,586 @@
+//! Learned linear projections applied after pooling
+//!
+//! A projection maps a pooled embedding `x` to `(x - mean) · W`, optionally
+//! re-normalized. PCA reduction, whitening and plain mean-centering are all
+//! expressed this way, so one matrix file covers them. Storage cost in HelixDB
+//! scales with the vector size, which makes a reduced dimension worthwhile for
+//! large collections.
+//!
+//! Projections are fitted offline from a corpus embedded by the model itself
+//! (see `EmbeddingModelsManager::fit_projection`) and saved as a safetensors
+//! file holding the `projection` matrix and the `mean` vector.
+
+use crate::models::config::ProjectionConfig;
+use crate::models::pooling;
+use crate::models::weights::{self, Matrix};
+use crate::models::{EmbeddingError, EmbeddingResult};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Tensor names written by `Projection::save`, matching the config defaults
+const MATRIX_TENSOR: &str = "projection";
+const MEAN_TENSOR: &str = "mean";
+
+/// Eigenvalues below this are clamped before whitening divides by their root
+const WHITENING_EPSILON: f64 = 1e-10;
+
+/// A linear projection `(x - mean) · W`
+#[derive(Debug, Clone)]
+pub struct Projection {
+    /// Matrix of shape `[input_dimension, output_dimension]`
+    matrix: Matrix,
+    /// Mean subtracted before projecting
+    mean: Option<Vec<f32>>,
+    /// L2-normalize the projected vector
+    normalize: bool,
+}
+
+impl Projection {
+    /// Create a projection, checking that the mean matches the matrix
+    pub fn new(matrix: Matrix, mean: Option<Vec<f32>>, normalize: bool) -> EmbeddingResult<Self> {
+        if matrix.rows == 0 || matrix.cols == 0 {
+            return Err(EmbeddingError::ModelLoadFailed {
+                error: "Projection matrix is empty".to_string(),
+            });
+        }
+
+        if let Some(mean) = &mean {
+            if mean.len() != matrix.rows {
+                return Err(EmbeddingError::ModelLoadFailed {
+                    error: format!(
+                        "Projection mean has {} values, but the matrix expects {} inputs",
+                        mean.len(), matrix.rows
+                    ),
+                });
+            }
+        }
+
+        Ok(Self { matrix, mean, normalize })
+    }
+
+    /// Load the projection described by a model's configuration
+    ///
+    /// Returns `None` when no projection is configured.
+    pub fn load(config: &ProjectionConfig) -> EmbeddingResult<Option<Self>> {
+        let Some(path) = &config.path else {
+            return Ok(None);
+        };
+        let path = Path::new(path);
+
+        let (matrix, mean) = if path.extension().and_then(|ext| ext.to_str()) == Some("safetensors") {
+            let bytes = std::fs::read(path).map_err(|e| EmbeddingError::ModelLoadFailed {
+                error: format!("Failed to read projection file {}: {}", path.display(), e),
+            })?;
+            let matrix = weights::parse_safetensors(&bytes, Some(&config.matrix_tensor))?;
+            let has_mean = safetensors::SafeTensors::deserialize(&bytes)
+                .map(|tensors| tensors.names().iter().any(|name| **name == config.mean_tensor))
+                .unwrap_or(false);
+            let mean = if has_mean {
+                Some(weights::parse_safetensors(&bytes, Some(&config.mean_tensor))?)
+            } else {
+                None
+            };
+            (matrix, mean)
+        } else {
+            let matrix = weights::load_matrix(path, None)?;
+            let mean = config.mean_path.as_ref().map(|mean_path| weights::load_matrix(mean_path, None)).transpose()?;
+            (matrix, mean)
+        };
+
+        let mean = mean.map(|mean| {
+            if mean.rows != 1 {
+                return Err(EmbeddingError::ModelLoadFailed {
+                    error: format!("Projection mean must be a vector, got shape [{}, {}]", mean.rows, mean.cols),
+                });
+            }
+            Ok(mean.data)
+        }).transpose()?;
+
+        Self::new(matrix, mean, config.normalize).map(Some)
+    }
+
+    /// Dimension of the embeddings the projection accepts
+    pub fn input_dimension(&self) -> usize {
+        self.matrix.rows
+    }
+
+    /// Dimension of the projected embeddings
+    pub fn output_dimension(&self) -> usize {
+        self.matrix.cols
+    }
+
+    /// Whether a mean is subtracted before projecting
+    pub fn is_mean_centered(&self) -> bool {
+        self.mean.is_some()
+    }
+
+    /// Whether projected vectors are L2-normalized
+    pub fn normalizes(&self) -> bool {
+        self.normalize
+    }
+
+    /// Project one embedding
+    pub fn apply(&self, embedding: &[f32]) -> EmbeddingResult<Vec<f32>> {
+        if embedding.len() != self.input_dimension() {
+            return Err(EmbeddingError::EmbeddingFailed {
+                error: format!(
+                    "Projection expects {}-dimensional embeddings, got {}",
+                    self.input_dimension(), embedding.len()
+                ),
+            });
+        }
+
+        // Accumulate row by row so the row-major matrix is read sequentially
+        let mut projected = vec![0.0f32; self.output_dimension()];
+        for (i, &value) in embedding.iter().enumerate() {
+            let centered = match &self.mean {
+                Some(mean) => value - mean[i],
+                None => value,
+            };
+            if centered == 0.0 {
+                continue;
+            }
+            for (out, &weight) in projected.iter_mut().zip(self.matrix.row(i)) {
+                *out += centered * weight;
+            }
+        }
+
+        if self.normalize {
+            pooling::normalize_embedding(&projected)
+        } else {
+            Ok(projected)
+        }
+    }
+
+    /// Write the projection as a safetensors file loadable with the default tensor names
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> EmbeddingResult<()> {
+        use safetensors::tensor::TensorView;
+        use safetensors::Dtype;
+
+        let path = path.as_ref();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("safetensors") {
+            return Err(EmbeddingError::InvalidInput {
+                message: format!("Projection output {} must be a .safetensors file", path.display()),
+            });
+        }
+
+        let to_bytes = |values: &[f32]| values.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>();
+        let matrix_bytes = to_bytes(&self.matrix.data);
+        let mean_bytes = self.mean.as_deref().map(to_bytes);
+
+        let view_error = |e: safetensors::SafeTensorError| EmbeddingError::EmbeddingFailed {
+            error: format!("Failed to build projection tensor: {}", e),
+        };
+        let mut tensors = vec![(
+            MATRIX_TENSOR,
+            TensorView::new(Dtype::F32, vec![self.matrix.rows, self.matrix.cols], &matrix_bytes).map_err(view_error)?,
+        )];
+        if let Some(mean_bytes) = &mean_bytes {
+            tensors.push((
+                MEAN_TENSOR,
+                TensorView::new(Dtype::F32, vec![self.matrix.rows], mean_bytes).map_err(view_error)?,
+            ));
+        }
+
+        safetensors::serialize_to_file(tensors, &None, path).map_err(|e| EmbeddingError::EmbeddingFailed {
+            error: format!("Failed to write projection file {}: {}", path.display(), e),
+        })
+    }
+}
+
+/// Request to fit a projection from a corpus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectionFitRequest {
+    /// Model embedding the corpus (default model if unset)
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Corpus file, one text per line
+    pub corpus_path: String,
+    /// Where to write the fitted projection (.safetensors)
+    pub output_path: String,
+    /// Number of principal components to keep (all if unset)
+    #[serde(default)]
+    pub output_dimension: Option<usize>,
+    /// Scale components to unit variance
+    #[serde(default)]
+    pub whiten: bool,
+    /// Use at most this many texts from the corpus
+    #[serde(default)]
+    pub max_texts: Option<usize>,
+}
+
+/// Outcome of fitting a projection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectionFitReport {
+    /// Model that embedded the corpus
+    pub model: String,
+    /// Number of texts the projection was fitted on
+    pub samples: usize,
+    /// Dimension of the model's embeddings
+    pub input_dimension: usize,
+    /// Dimension of the projected embeddings
+    pub output_dimension: usize,
+    /// Fraction of the corpus variance kept by the retained components
+    pub explained_variance: f64,
+    /// Whether the components were whitened
+    pub whiten: bool,
+    /// File the projection was written to
+    pub output_path: String,
+}
+
+/// Read a corpus file, one text per line, skipping blank lines
+pub fn read_corpus<P: AsRef<Path>>(path: P, max_texts: Option<usize>) -> EmbeddingResult<Vec<String>> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path).map_err(|e| EmbeddingError::InvalidInput {
+        message: format!("Failed to read corpus {}: {}", path.display(), e),
+    })?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take(max_texts.unwrap_or(usize::MAX))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Fit a PCA projection to a set of embeddings
+///
+/// Keeps the `output_dimension` directions of largest variance (all of them
+/// when `None`) and, with `whiten`, scales each to unit variance. Returns the
+/// projection together with the fraction of variance it keeps.
+pub fn fit(
+    embeddings: &[Vec<f32>],
+    output_dimension: Option<usize>,
+    whiten: bool,
+) -> EmbeddingResult<(Projection, f64)> {
+    if embeddings.len() < 2 {
+        return Err(EmbeddingError::InvalidInput {
+            message: format!("Fitting a projection needs at least 2 embeddings, got {}", embeddings.len()),
+        });
+    }
+
+    let dimension = embeddings[0].len();
+    if embeddings.iter().any(|embedding| embedding.len() != dimension) {
+        return Err(EmbeddingError::InvalidInput {
+            message: "Embeddings used for fitting must all have the same dimension".to_string(),
+        });
+    }
+
+    let output_dimension = output_dimension.unwrap_or(dimension);
+    if output_dimension == 0 || output_dimension > dimension {
+        return Err(EmbeddingError::InvalidInput {
+            message: format!("output_dimension must be between 1 and {}, got {}", dimension, output_dimension),
+        });
+    }
+
+    let samples = embeddings.len() as f64;
+    let mut mean = vec![0.0f64; dimension];
+    for embedding in embeddings {
+        for (sum, &value) in mean.iter_mut().zip(embedding) {
+            *sum += value as f64;
+        }
+    }
+    mean.iter_mut().for_each(|sum| *sum /= samples);
+
+    // Upper triangle of the sample covariance, mirrored afterwards
+    let mut covariance = vec![0.0f64; dimension * dimension];
+    let mut centered = vec![0.0f64; dimension];
+    for embedding in embeddings {
+        for ((c, &value), &m) in centered.iter_mut().zip(embedding).zip(&mean) {
+            *c = value as f64 - m;
+        }
+        for i in 0..dimension {
+            let ci = centered[i];
+            if ci == 0.0 {
+                continue;
+            }
+            let row = &mut covariance[i * dimension..(i + 1) * dimension];
+            for j in i..dimension {
+                row[j] += ci * centered[j];
+            }
+        }
+    }
+    for i in 0..dimension {
+        for j in i..dimension {
+            let value = covariance[i * dimension + j] / (samples - 1.0);
+            covariance[i * dimension + j] = value;
+            covariance[j * dimension + i] = value;
+        }
+    }
+
+    let (eigenvalues, eigenvectors) = symmetric_eigen(covariance, dimension);
+
+    let mut order: Vec<usize> = (0..dimension).collect();
+    order.sort_by(|&a, &b| eigenvalues[b].total_cmp(&eigenvalues[a]));
+    let components = &order[..output_dimension];
+
+    let total_variance: f64 = eigenvalues.iter().map(|&value| value.max(0.0)).sum();
+    let kept_variance: f64 = components.iter().map(|&k| eigenvalues[k].max(0.0)).sum();
+    let explained_variance = if total_variance > 0.0 { kept_variance / total_variance } else { 1.0 };
+
+    let mut data = vec![0.0f32; dimension * output_dimension];
+    for (column, &k) in components.iter().enumerate() {
+        // Eigenvector signs are arbitrary; fix them so fits are reproducible
+        let pivot = (0..dimension)
+            .max_by(|&a, &b| eigenvectors[a * dimension + k].abs().total_cmp(&eigenvectors[b * dimension + k].abs()))
+            .unwrap_or(0);
+        let mut scale = if eigenvectors[pivot * dimension + k] < 0.0 { -1.0 } else { 1.0 };
+        if whiten {
+            scale /= eigenvalues[k].max(WHITENING_EPSILON).sqrt();
+        }
+        for row in 0..dimension {
+            data[row * output_dimension + column] = (eigenvectors[row * dimension + k] * scale) as f32;
+        }
+    }
+
+    let matrix = Matrix::new(dimension, output_dimension, data)?;
+    let mean = mean.into_iter().map(|value| value as f32).collect();
+    Ok((Projection::new(matrix, Some(mean), true)?, explained_variance))
+}
+
+/// Eigen-decomposition of a symmetric row-major matrix
+///
+/// Householder reduction to tridiagonal form followed by the implicit QL
+/// algorithm (the EISPACK `tred2` / `tql2` pair). Returns the eigenvalues and
+/// a row-major matrix whose columns are the matching eigenvectors.
+fn symmetric_eigen(matrix: Vec<f64>, n: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut v = matrix;
+    let mut d = vec![0.0f64; n];
+    let mut e = vec![0.0f64; n];
+    let at = |row: usize, col: usize| row * n + col;
+
+    // Householder tridiagonalization
+    d.copy_from_slice(&v[at(n - 1, 0)..at(n - 1, 0) + n]);
+    for i in (1..n).rev() {
+        let scale: f64 = d[..i].iter().map(|x| x.abs()).sum();
+        let mut h = 0.0;
+        if scale == 0.0 {
+            e[i] = d[i - 1];
+            for j in 0..i {
+                d[j] = v[at(i - 1, j)];
+                v[at(i, j)] = 0.0;
+                v[at(j, i)] = 0.0;
+            }
+        } else {
+            for value in &mut d[..i] {
+                *value /= scale;
+                h += *value * *value;
+            }
+            let mut f = d[i - 1];
+            let mut g = if f > 0.0 { -h.sqrt() } else { h.sqrt() };
+            e[i] = scale * g;
+            h -= f * g;
+            d[i - 1] = f - g;
+            e[..i].iter_mut().for_each(|value| *value = 0.0);
+
+            for j in 0..i {
+                f = d[j];
+                v[at(j, i)] = f;
+                g = e[j] + v[at(j, j)] * f;
+                for k in j + 1..i {
+                    g += v[at(k, j)] * d[k];
+                    e[k] += v[at(k, j)] * f;
+                }
+                e[j] = g;
+            }
+
+            f = 0.0;
+            for j in 0..i {
+                e[j] /= h;
+                f += e[j] * d[j];
+            }
+            let hh = f / (h + h);
+            for j in 0..i {
+                e[j] -= hh * d[j];
+            }
+            for j in 0..i {
+                f = d[j];
+                g = e[j];
+                for k in j..i {
+                    v[at(k, j)] -= f * e[k] + g * d[k];
+                }
+                d[j] = v[at(i - 1, j)];
+                v[at(i, j)] = 0.0;
+            }
+        }
+        d[i] = h;
+    }
+
+    // Accumulate the transformations
+    for i in 0..n - 1 {
+        v[at(n - 1, i)] = v[at(i, i)];
+        v[at(i, i)] = 1.0;
+        let h = d[i + 1];
+        if h != 0.0 {
+            for k in 0..=i {
+                d[k] = v[at(k, i + 1)] / h;
+            }
+            for j in 0..=i {
+                let g: f64 = (0..=i).map(|k| v[at(k, i + 1)] * v[at(k, j)]).sum();
+                for k in 0..=i {
+                    v[at(k, j)] -= g * d[k];
+                }
+            }
+        }
+        for k in 0..=i {
+            v[at(k, i + 1)] = 0.0;
+        }
+    }
+    for j in 0..n {
+        d[j] = v[at(n - 1, j)];
+        v[at(n - 1, j)] = 0.0;
+    }
+    v[at(n - 1, n - 1)] = 1.0;
+    e[0] = 0.0;
+
+    // Implicit QL iterations on the tridiagonal matrix
+    for i in 1..n {
+        e[i - 1] = e[i];
+    }
+    e[n - 1] = 0.0;
+
+    let mut f = 0.0f64;
+    let mut tst1 = 0.0f64;
+    let eps = f64::EPSILON;
+    for l in 0..n {
+        tst1 = tst1.max(d[l].abs() + e[l].abs());
+        let mut m = l;
+        while m < n - 1 && e[m].abs() > eps * tst1 {
+            m += 1;
+        }
+
+        if m > l {
+            loop {
+                let mut g = d[l];
+                let mut p = (d[l + 1] - g) / (2.0 * e[l]);
+                let mut r = p.hypot(1.0);
+                if p < 0.0 {
+                    r = -r;
+                }
+                d[l] = e[l] / (p + r);
+                d[l + 1] = e[l] * (p + r);
+                let dl1 = d[l + 1];
+                let mut h = g - d[l];
+                for value in &mut d[l + 2..n] {
+                    *value -= h;
+                }
+                f += h;
+
+                p = d[m];
+                let mut c = 1.0f64;
+                let mut c2 = c;
+                let mut c3 = c;
+                let el1 = e[l + 1];
+                let mut s = 0.0f64;
+                let mut s2 = 0.0f64;
+                for i in (l..m).rev() {
+                    c3 = c2;
+                    c2 = c;
+                    s2 = s;
+                    g = c * e[i];
+                    h = c * p;
+                    r = p.hypot(e[i]);
+                    e[i + 1] = s * r;
+                    s = e[i] / r;
+                    c = p / r;
+                    p = c * d[i] - s * g;
+                    d[i + 1] = h + s * (c * g + s * d[i]);
+                    for k in 0..n {
+                        h = v[at(k, i + 1)];
+                        v[at(k, i + 1)] = s * v[at(k, i)] + c * h;
+                        v[at(k, i)] = c * v[at(k, i)] - s * h;
+                    }
+                }
+                p = -s * s2 * c3 * el1 * e[l] / dl1;
+                e[l] = s * p;
+                d[l] = c * p;
+
+                if e[l].abs() <= eps * tst1 {
+                    break;
+                }
+            }
+        }
+        d[l] += f;
+        e[l] = 0.0;
+    }
+
+    (d, v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Points spread along (1, 1, 0) with a little noise on the other axes
+    fn elongated_cloud() -> Vec<Vec<f32>> {
+        (0..200)
+            .map(|i| {
+                let t = (i as f32 - 100.0) / 10.0;
+                let noise = ((i * 7919) % 13) as f32 / 13.0 - 0.5;
+                let other_noise = ((i * 104729) % 11) as f32 / 11.0 - 0.5;
+                vec![t + 1.0, t + 2.0 + 0.1 * noise, 0.05 * other_noise - 3.0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_apply_centers_projects_and_normalizes() {
+        let matrix = Matrix::new(3, 2, vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0]).unwrap();
+        let projection = Projection::new(matrix.clone(), Some(vec![1.0, 1.0, 1.0]), false).unwrap();
+        assert_eq!(projection.apply(&[4.0, 5.0, 9.0]).unwrap(), vec![3.0, 4.0]);
+        assert!(projection.apply(&[1.0, 2.0]).is_err());
+
+        let normalized = Projection::new(matrix.clone(), Some(vec![1.0, 1.0, 1.0]), true).unwrap();
+        assert_eq!(normalized.apply(&[4.0, 5.0, 9.0]).unwrap(), vec![0.6, 0.8]);
+
+        assert!(Projection::new(matrix, Some(vec![0.0; 2]), true).is_err());
+    }
+
+    #[test]
+    fn test_fit_finds_principal_direction() {
+        let (projection, explained) = fit(&elongated_cloud(), Some(1), false).unwrap();
+        assert_eq!((projection.input_dimension(), projection.output_dimension()), (3, 1));
+        assert!(explained > 0.99, "explained variance {}", explained);
+
+        let axis = projection.matrix.data.clone();
+        let expected = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((axis[0] - expected).abs() < 1e-2 && (axis[1] - expected).abs() < 1e-2, "{:?}", axis);
+        assert!(axis[2].abs() < 1e-2);
+
+        assert!(fit(&elongated_cloud(), Some(4), false).is_err());
+        assert!(fit(&elongated_cloud()[..1], None, false).is_err());
+    }
+
+    #[test]
+    fn test_whitened_components_have_unit_variance() {
+        let cloud = elongated_cloud();
+        let (mut projection, _) = fit(&cloud, None, true).unwrap();
+        projection.normalize = false;
+
+        let projected: Vec<Vec<f32>> = cloud.iter().map(|x| projection.apply(x).unwrap()).collect();
+        for component in 0..3 {
+            let variance: f32 = projected.iter().map(|p| p[component] * p[component]).sum::<f32>() / (cloud.len() - 1) as f32;
+            assert!((variance - 1.0).abs() < 1e-2, "component {} variance {}", component, variance);
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("projection-{}.safetensors", std::process::id()));
+        let (projection, _) = fit(&elongated_cloud(), Some(2), false).unwrap();
+        projection.save(&path).unwrap();
+
+        let config = ProjectionConfig {
+            path: Some(path.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+        let loaded = Projection::load(&config).unwrap().unwrap();
+        assert!(loaded.is_mean_centered());
+        assert_eq!(loaded.apply(&[1.0, 2.0, 3.0]).unwrap(), projection.apply(&[1.0, 2.0, 3.0]).unwrap());
+
+        assert!(Projection::load(&ProjectionConfig::default()).unwrap().is_none());
+        assert!(projection.save(path.with_extension("npy")).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -3,10 +3,45 @@
 //! This module provides a registry that can load, store, and manage
 //! multiple embedding models based on the configuration.
 
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{OnceCell, RwLock, Semaphore};
 use crate::models::{EmbeddingResult, Embedding};
+use crate::models::lifecycle::{Lifecycle, ModelState, ModelStatus};
+use crate::models::residency::{Residency, ResidencyMetrics, MB};
+
+/// When a model is loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadPolicy {
+    /// At startup, before the server accepts requests
+    Eager,
+    /// On the first request, then kept loaded
+    Lazy,
+    /// On the first request, and unloaded again after `idle_timeout_secs` without requests
+    OnDemand,
+}
+
+impl LoadPolicy {
+    /// Parse the `load_policy` value from embeddingmodels.toml
+    pub fn from_config(load_policy: &str) -> EmbeddingResult<Self> {
+        match load_policy.to_lowercase().as_str() {
+            "eager" => Ok(LoadPolicy::Eager),
+            "lazy" => Ok(LoadPolicy::Lazy),
+            "on_demand" | "on-demand" => Ok(LoadPolicy::OnDemand),
+            other => Err(crate::EmbeddingError::ConfigError {
+                message: format!("Unknown load policy '{}' (expected eager, lazy or on_demand)", other),
+            }),
+        }
+    }
+}
+
+/// Longest wait for requests in flight when a model is unloaded
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of a load shared by every request waiting on it
+type SharedLoad = Arc<OnceCell<Result<(), String>>>;
 
 /// Model registry for managing multiple models
 pub struct ModelRegistry {
@@ -14,6 +49,27 @@ pub struct ModelRegistry {
     models: RwLock<HashMap<String, Arc<dyn crate::models::model::EmbeddingModel>>>,
     /// Model information cache
     model_infos: RwLock<HashMap<String, crate::models::model::ModelInfo>>,
+    /// Models taken out of service, with the reason
+    quarantined: RwLock<HashMap<String, String>>,
+    /// Configurations `get_or_load` loads from: lazy and on-demand models,
+    /// and loaded models so they come back after an eviction
+    deferred: RwLock<HashMap<String, crate::models::config::ModelConfig>>,
+    /// Loads in progress; concurrent requests for a model wait on the same one
+    loading: Mutex<HashMap<String, SharedLoad>>,
+    /// Longest time a load may take (`init_timeout`)
+    init_timeout: Mutex<Duration>,
+    /// Last request to each loaded model
+    last_used: Mutex<HashMap<String, Instant>>,
+    /// Memory budget and estimates
+    residency: Mutex<Residency>,
+    /// Held from eviction planning until the loaded model is recorded, so
+    /// concurrent loads cannot plan against the same free memory
+    residency_lock: tokio::sync::Mutex<()>,
+    /// Models unloaded while requests still held them; their memory counts
+    /// against the budget until the last reference is dropped
+    lingering: Mutex<Vec<(String, Weak<dyn crate::models::model::EmbeddingModel>)>>,
+    /// Lifecycle state of every configured or loaded model
+    lifecycles: Mutex<HashMap<String, Lifecycle>>,
 }
 
 impl ModelRegistry {
@@ -22,45 +78,457 @@ impl ModelRegistry {
         Self {
             models: RwLock::new(HashMap::new()),
             model_infos: RwLock::new(HashMap::new()),
+            quarantined: RwLock::new(HashMap::new()),
+            deferred: RwLock::new(HashMap::new()),
+            loading: Mutex::new(HashMap::new()),
+            init_timeout: Mutex::new(Duration::from_secs(
+                crate::models::config::EmbeddingModelsConfig::default().global.init_timeout,
+            )),
+            last_used: Mutex::new(HashMap::new()),
+            residency: Mutex::new(Residency::default()),
+            residency_lock: tokio::sync::Mutex::new(()),
+            lingering: Mutex::new(Vec::new()),
+            lifecycles: Mutex::new(HashMap::new()),
         }
     }
 
     /// Load models from configuration
+    ///
+    /// Only eager models are loaded here; lazy and on-demand models are
+    /// recorded and loaded by `get_or_load` on first use. Up to
+    /// `load_parallelism` models load at once, each within `init_timeout`.
+    /// Ensembles are loaded last, once the models they combine are available.
+    ///
+    /// A model that fails to load is recorded (see `load_failures`) and the
+    /// others still load. Only failures of required models, including an
+    /// eager default model, fail the whole call.
     pub async fn load_from_config(
-        &self,
+        self: &Arc<Self>,
         config: &crate::models::config::EmbeddingModelsConfig,
     ) -> EmbeddingResult<()> {
-        for (model_name, model_config) in &config.models {
-            if model_config.enabled {
-                self.load_model(model_config).await?;
+        let mut deferred = HashMap::new();
+        let mut eager = Vec::new();
+        for model_config in config.models.values().filter(|model_config| model_config.enabled) {
+            match LoadPolicy::from_config(&model_config.load_policy)? {
+                LoadPolicy::Eager => eager.push(model_config),
+                LoadPolicy::Lazy | LoadPolicy::OnDemand => {
+                    deferred.insert(model_config.id().to_string(), model_config.clone());
+                }
             }
         }
-        Ok(())
+        *self.deferred.write().await = deferred;
+        *self.lifecycles.lock().unwrap() = config.models
+            .values()
+            .filter(|model_config| model_config.enabled)
+            .map(|model_config| (model_config.id().to_string(), Lifecycle::new()))
+            .collect();
+
+        // The default model and ensemble members are never evicted
+        let mut pinned: HashSet<String> = config.models.values()
+            .filter(|model_config| model_config.enabled && model_config.pinned)
+            .map(|model_config| model_config.id().to_string())
+            .collect();
+        pinned.insert(config.global.default_model.clone());
+        for model_config in config.models.values().filter(|model_config| model_config.enabled) {
+            pinned.extend(model_config.ensemble.members.iter().cloned());
+        }
+        self.residency.lock().unwrap().configure(config.global.memory_budget_mb.map(|mb| mb * MB), pinned);
+
+        let (ensembles, models): (Vec<_>, Vec<_>) = eager
+            .into_iter()
+            .partition(|model_config| model_config.backend == "ensemble");
+
+        let init_timeout = Duration::from_secs(config.global.init_timeout);
+        *self.init_timeout.lock().unwrap() = init_timeout;
+        let parallelism = Arc::new(Semaphore::new(config.global.load_parallelism.max(1)));
+        let loads: Vec<_> = models
+            .into_iter()
+            .map(|model_config| {
+                let registry = Arc::clone(self);
+                let parallelism = Arc::clone(&parallelism);
+                let owned = model_config.clone();
+                let load = tokio::spawn(async move {
+                    let _permit = parallelism.acquire_owned().await;
+                    registry.load_with_timeout(&owned, init_timeout).await
+                });
+                (model_config, load)
+            })
+            .collect();
+
+        let mut outcomes = Vec::with_capacity(loads.len() + ensembles.len());
+        for (model_config, load) in loads {
+            let outcome = load.await.unwrap_or_else(|e| Err(crate::EmbeddingError::ModelLoadError {
+                model_name: model_config.id().to_string(),
+                error: format!("Load task failed: {}", e),
+            }));
+            outcomes.push((model_config, outcome));
+        }
+        for model_config in ensembles {
+            outcomes.push((model_config, self.load_with_timeout(model_config, init_timeout).await));
+        }
+
+        let mut required_failures = Vec::new();
+        for (model_config, outcome) in outcomes {
+            let Err(e) = outcome else { continue };
+            let required = model_config.required || model_config.id() == config.global.default_model;
+            if required {
+                tracing::error!("❌ Required model '{}' failed to load: {}", model_config.id(), e);
+                required_failures.push((model_config.id().to_string(), e.to_string()));
+            } else {
+                tracing::error!("❌ Model '{}' failed to load and is unavailable: {}", model_config.id(), e);
+            }
+            // Panicked loads have not recorded their failure themselves
+            self.set_state(model_config.id(), ModelState::Failed, Some(e.to_string()));
+        }
+
+        if required_failures.is_empty() {
+            return Ok(());
+        }
+        let (names, errors): (Vec<_>, Vec<_>) = required_failures.into_iter().unzip();
+        Err(crate::EmbeddingError::ModelLoadError {
+            model_name: names.join(", "),
+            error: format!("Required model failed to load: {}", errors.join("; ")),
+        })
+    }
+
+    /// Load a model, giving up after `timeout`
+    ///
+    /// Initialization runs on the blocking pool (see `load`), so the timeout
+    /// fires even while a backend blocks its thread reading the model. A load
+    /// that times out is dropped and leaves nothing registered; the blocking
+    /// initialization runs to completion in the background and its model is
+    /// discarded. A load that has already started registering the model is
+    /// allowed to finish instead.
+    async fn load_with_timeout(
+        &self,
+        config: &crate::models::config::ModelConfig,
+        timeout: Duration,
+    ) -> EmbeddingResult<()> {
+        let registering = AtomicBool::new(false);
+        let load = self.load_model_tracked(config, &registering);
+        tokio::pin!(load);
+        match tokio::time::timeout(timeout, &mut load).await {
+            Ok(outcome) => outcome,
+            // Dropping the load halfway through registration would leave part of it behind
+            Err(_) if registering.load(Ordering::Acquire) => load.await,
+            Err(_) => {
+                let error = crate::EmbeddingError::ModelLoadError {
+                    model_name: config.id().to_string(),
+                    error: format!("Not loaded within init_timeout ({}s)", timeout.as_secs()),
+                };
+                self.set_state(config.id(), ModelState::Failed, Some(error.to_string()));
+                Err(error)
+            }
+        }
+    }
+
+    /// Models whose last load failed, with the error
+    pub async fn load_failures(&self) -> HashMap<String, String> {
+        self.lifecycles
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, lifecycle)| lifecycle.state() == ModelState::Failed)
+            .map(|(name, lifecycle)| (name.clone(), lifecycle.status(name).reason.unwrap_or_default()))
+            .collect()
+    }
+
+    /// Lifecycle state and history of every configured or loaded model, by name
+    pub async fn model_statuses(&self) -> Vec<ModelStatus> {
+        let model_infos = self.model_infos.read().await;
+        let mut statuses: Vec<ModelStatus> = self.lifecycles
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, lifecycle)| ModelStatus {
+                fingerprint: model_infos.get(name).and_then(|info| info.fingerprint.clone()),
+                ..lifecycle.status(name)
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    /// Lifecycle state and history of a model, with its output fingerprint once loaded
+    pub async fn model_status(&self, name: &str) -> Option<ModelStatus> {
+        let fingerprint = self.fingerprint(name).await;
+        self.lifecycles
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|lifecycle| ModelStatus { fingerprint, ..lifecycle.status(name) })
+    }
+
+    /// Move a model to `state`, ignoring transitions the state machine does not allow
+    fn set_state(&self, name: &str, state: ModelState, reason: Option<String>) {
+        let mut lifecycles = self.lifecycles.lock().unwrap();
+        let lifecycle = lifecycles.entry(name.to_string()).or_default();
+        let previous = lifecycle.state();
+        if lifecycle.transition(state, reason) {
+            tracing::debug!("'{}' is {} (was {})", name, state, previous);
+        } else if previous != state {
+            tracing::warn!("⚠️ Ignoring transition of '{}' from {} to {}", name, previous, state);
+        }
     }
 
     /// Load a single model
+    ///
+    /// The model is warmed up (see `WarmupConfig`) before it is registered, so
+    /// it is only listed and served once warm. Loading an ensemble loads its
+    /// lazy members first; other members must be loaded already. With a
+    /// memory budget, least recently used models are evicted first to make
+    /// room for the model.
     pub async fn load_model(
         &self,
         config: &crate::models::config::ModelConfig,
     ) -> EmbeddingResult<()> {
-        let mut model = crate::models::model::ModelFactory::create_model(config);
+        self.load_model_tracked(config, &AtomicBool::new(false)).await
+    }
 
-        // Initialize the model
-        model.initialize().await?;
+    /// Load a single model, setting `registering` once the model is about to be registered
+    async fn load_model_tracked(
+        &self,
+        config: &crate::models::config::ModelConfig,
+        registering: &AtomicBool,
+    ) -> EmbeddingResult<()> {
+        self.set_state(config.id(), ModelState::Loading, None);
+        let outcome = self.load(config, registering).await;
+        if let Err(e) = &outcome {
+            self.set_state(config.id(), ModelState::Failed, Some(e.to_string()));
+        }
+        outcome
+    }
+
+    async fn load(&self, config: &crate::models::config::ModelConfig, registering: &AtomicBool) -> EmbeddingResult<()> {
+        LoadPolicy::from_config(&config.load_policy)?;
+        let mut model = if config.backend == "ensemble" {
+            let mut members = Vec::with_capacity(config.ensemble.members.len());
+            for member in &config.ensemble.members {
+                let model = Box::pin(self.get_or_load(member)).await?.ok_or_else(|| crate::EmbeddingError::ModelLoadError {
+                    model_name: config.id().to_string(),
+                    error: format!("Ensemble member '{}' is not loaded", member),
+                })?;
+                members.push(model);
+            }
+            crate::models::model::ModelFactory::create_ensemble(config, members)?
+        } else {
+            crate::models::model::ModelFactory::create_model(config)?
+        };
+
+        // With a budget, loads are planned and recorded one at a time
+        let budgeted = self.residency.lock().unwrap().has_budget();
+        let _residency_guard = if budgeted { Some(self.residency_lock.lock().await) } else { None };
+        let estimate = self.make_room(config).await?;
+        let rss_before = crate::models::memory::resident_memory_bytes();
+
+        // Initialization blocks its thread while the backend reads the model,
+        // so it runs on the blocking pool where a timeout can give up on it
+        let runtime = tokio::runtime::Handle::current();
+        let model = tokio::task::spawn_blocking(move || {
+            runtime.block_on(model.initialize()).map(|()| model)
+        })
+        .await
+        .map_err(|e| crate::EmbeddingError::ModelLoadError {
+            model_name: config.id().to_string(),
+            error: format!("Initialization task failed: {}", e),
+        })??;
+
+        // Warm up before the model becomes available; a failed warmup is not fatal
+        self.set_state(config.id(), ModelState::WarmingUp, None);
+        let mut info = model.info().clone();
+        if config.warmup.enabled {
+            match crate::models::warmup::warm_up(model.as_ref(), &config.warmup).await {
+                Ok(report) => {
+                    tracing::info!(
+                        "🔥 Warmed up '{}' with {} requests in {}ms",
+                        config.id(), report.passes.len(), report.total_ms
+                    );
+                    info.warmup = Some(report);
+                }
+                Err(e) => tracing::warn!("⚠️ Warmup of '{}' failed: {}", config.id(), e),
+            }
+        }
+        match crate::models::fingerprint::compute(model.as_ref()).await {
+            Ok(fingerprint) => {
+                tracing::info!("🔖 Output fingerprint of '{}': {}", config.id(), fingerprint);
+                info.fingerprint = Some(fingerprint);
+            }
+            Err(e) => tracing::warn!("⚠️ Could not compute the output fingerprint of '{}': {}", config.id(), e),
+        }
+
+        // Measured growth of the process, else the size of the files read
+        let measured = rss_before
+            .zip(crate::models::memory::resident_memory_bytes())
+            .map(|(before, after)| after.saturating_sub(before))
+            .filter(|&grown| grown > 0)
+            .or_else(|| info.load_report.as_ref().map(|report| report.total_bytes));
+        let memory_bytes = config.memory_estimate_mb.map(|mb| mb * MB).or(measured).unwrap_or(estimate);
+        registering.store(true, Ordering::Release);
+        if self.residency.lock().unwrap().record_load(config.id(), memory_bytes) {
+            tracing::info!("♻️ Reloaded evicted model '{}' ({} MB)", config.id(), memory_bytes / MB);
+        }
+        info.memory_bytes = Some(memory_bytes);
 
         // Store model info
-        let info = model.info().clone();
-        self.model_infos.write().await.insert(config.name.clone(), info);
+        self.model_infos.write().await.insert(config.id().to_string(), info);
 
-        // Store the model
+        // Store the model; a fresh load lifts any quarantine
         self.models.write().await.insert(
-            config.name.clone(),
+            config.id().to_string(),
             Arc::from(model),
         );
+        self.quarantined.write().await.remove(config.id());
+        self.last_used.lock().unwrap().insert(config.id().to_string(), Instant::now());
+        self.deferred.write().await.insert(config.id().to_string(), config.clone());
+        self.set_state(config.id(), ModelState::Ready, None);
 
         Ok(())
     }
 
+    /// Evict least recently used models until `config`'s model fits the memory budget
+    ///
+    /// Returns the memory the model is expected to need.
+    async fn make_room(&self, config: &crate::models::config::ModelConfig) -> EmbeddingResult<u64> {
+        let known = self.residency.lock().unwrap().estimate(config.id());
+        let estimate = config.memory_estimate_mb
+            .map(|mb| mb * MB)
+            .or(known)
+            .unwrap_or_else(|| file_sizes(config));
+
+        let victims = {
+            let resident: Vec<(String, Instant)> = self.last_used
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(name, _)| **name != config.id())
+                .map(|(name, used)| (name.clone(), *used))
+                .collect();
+            let lingering = self.lingering_models();
+            self.residency.lock().unwrap().plan_evictions(config.id(), estimate, &resident, &lingering)?
+        };
+        for victim in victims {
+            tracing::info!("📤 Evicting '{}' to stay within the memory budget while loading '{}'", victim, config.id());
+            if !self.remove(&victim).await? {
+                return Err(crate::EmbeddingError::ModelLoadError {
+                    model_name: config.id().to_string(),
+                    error: format!(
+                        "evicting '{}' did not finish: requests still held it after {}s, so its memory is still in use",
+                        victim, DRAIN_TIMEOUT.as_secs()
+                    ),
+                });
+            }
+            self.residency.lock().unwrap().record_eviction(&victim);
+        }
+        Ok(estimate)
+    }
+
+    /// Names of unloaded models whose memory requests still hold
+    fn lingering_models(&self) -> Vec<String> {
+        let mut lingering = self.lingering.lock().unwrap();
+        lingering.retain(|(_, model)| model.strong_count() > 0);
+        lingering.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Get a model by name, loading it first if it is lazy or on-demand
+    ///
+    /// Requests arriving while the model loads wait for the same load and
+    /// share its outcome. The load gets `init_timeout` like a startup load.
+    /// Returns `None` for models that are neither loaded nor deferred.
+    pub async fn get_or_load(&self, name: &str) -> EmbeddingResult<Option<Arc<dyn crate::models::model::EmbeddingModel>>> {
+        if let Some(model) = self.get_model(name).await {
+            if let Some(last_used) = self.last_used.lock().unwrap().get_mut(name) {
+                *last_used = Instant::now();
+            }
+            return Ok(Some(model));
+        }
+        // A draining model is reloaded only once it is fully unloaded
+        if let Some(lifecycle) = self.lifecycles.lock().unwrap().get(name) {
+            if lifecycle.state() == ModelState::Draining {
+                return Err(lifecycle.unavailable(name));
+            }
+        }
+        let Some(config) = self.deferred.read().await.get(name).cloned() else {
+            return match self.lifecycles.lock().unwrap().get(name) {
+                Some(lifecycle) => Err(lifecycle.unavailable(name)),
+                None => Ok(None),
+            };
+        };
+
+        let load = self.loading.lock().unwrap().entry(name.to_string()).or_default().clone();
+        let outcome = load
+            .get_or_init(|| async {
+                tracing::info!("⏳ Loading '{}' on first use ({})", name, config.load_policy);
+                let timeout = *self.init_timeout.lock().unwrap();
+                self.load_with_timeout(&config, timeout).await.map_err(|e| e.to_string())
+            })
+            .await
+            .clone();
+        {
+            // Later requests start a new load if this one failed or the model is unloaded again
+            let mut loading = self.loading.lock().unwrap();
+            if loading.get(name).is_some_and(|current| Arc::ptr_eq(current, &load)) {
+                loading.remove(name);
+            }
+        }
+
+        match outcome {
+            Ok(()) => Ok(self.get_model(name).await),
+            Err(error) => Err(crate::EmbeddingError::ModelLoadError {
+                model_name: name.to_string(),
+                error,
+            }),
+        }
+    }
+
+    /// Unload on-demand models that received no request within their idle timeout
+    ///
+    /// Returns the names of the unloaded models.
+    pub async fn unload_idle(&self) -> EmbeddingResult<Vec<String>> {
+        let idle: Vec<String> = {
+            let deferred = self.deferred.read().await;
+            let last_used = self.last_used.lock().unwrap();
+            last_used
+                .iter()
+                .filter(|(name, used)| {
+                    deferred.get(*name).is_some_and(|config| {
+                        LoadPolicy::from_config(&config.load_policy).ok() == Some(LoadPolicy::OnDemand)
+                            && used.elapsed() >= Duration::from_secs(config.idle_timeout_secs)
+                    })
+                })
+                .map(|(name, _)| name.clone())
+                .collect()
+        };
+
+        for name in &idle {
+            tracing::info!("💤 Unloading idle model '{}'", name);
+            self.remove(name).await?;
+        }
+        Ok(idle)
+    }
+
+    /// Shortest idle timeout of the configured on-demand models
+    pub async fn shortest_idle_timeout(&self) -> Option<Duration> {
+        self.deferred
+            .read()
+            .await
+            .values()
+            .filter(|config| LoadPolicy::from_config(&config.load_policy).ok() == Some(LoadPolicy::OnDemand))
+            .map(|config| Duration::from_secs(config.idle_timeout_secs))
+            .min()
+    }
+
+    /// Memory budget, resident estimate and eviction counters
+    pub async fn residency_metrics(&self) -> ResidencyMetrics {
+        let models = self.models.read().await;
+        let lingering = self.lingering_models();
+        self.residency.lock().unwrap().metrics(models.keys().chain(&lingering).map(String::as_str))
+    }
+
+    /// Output fingerprint of a loaded model, when it could be computed
+    pub async fn fingerprint(&self, name: &str) -> Option<String> {
+        self.model_infos.read().await.get(name).and_then(|info| info.fingerprint.clone())
+    }
+
     /// Get a model by name
     pub async fn get_model(&self, name: &str) -> Option<Arc<dyn crate::models::model::EmbeddingModel>> {
         self.models.read().await.get(name).cloned()
@@ -68,7 +536,9 @@ impl ModelRegistry {
 
     /// Get model information by name
     pub async fn get_model_info(&self, name: &str) -> Option<crate::models::model::ModelInfo> {
-        self.model_infos.read().await.get(name).cloned()
+        let mut info = self.model_infos.read().await.get(name).cloned()?;
+        info.lifecycle = self.lifecycles.lock().unwrap().get(name).map(|lifecycle| lifecycle.status(name));
+        Some(info)
     }
 
     /// List all loaded models
@@ -78,7 +548,12 @@ impl ModelRegistry {
 
     /// List all model information
     pub async fn list_model_infos(&self) -> Vec<crate::models::model::ModelInfo> {
-        self.model_infos.read().await.values().cloned().collect()
+        let mut infos: Vec<_> = self.model_infos.read().await.values().cloned().collect();
+        let lifecycles = self.lifecycles.lock().unwrap();
+        for info in &mut infos {
+            info.lifecycle = lifecycles.get(&info.name).map(|lifecycle| lifecycle.status(&info.name));
+        }
+        infos
     }
 
     /// Check if a model is loaded
@@ -87,16 +562,70 @@ impl ModelRegistry {
     }
 
     /// Unload a model
+    ///
+    /// Eager models stay unloaded; lazy and on-demand models load again on
+    /// their next request.
     pub async fn unload_model(&self, name: &str) -> EmbeddingResult<()> {
-        if let Some(model) = self.models.write().await.remove(name) {
-            // The model will be dropped when the Arc is released
-            self.model_infos.write().await.remove(name);
-            Ok(())
-        } else {
-            Err(crate::EmbeddingError::ModelNotFound {
+        self.remove(name).await?;
+        let mut deferred = self.deferred.write().await;
+        if deferred.get(name).is_some_and(|config| LoadPolicy::from_config(&config.load_policy).ok() == Some(LoadPolicy::Eager)) {
+            deferred.remove(name);
+        }
+        Ok(())
+    }
+
+    /// Drop a loaded model, keeping its configuration for `get_or_load`
+    ///
+    /// New requests are refused while requests in flight, which hold their
+    /// own reference to the model, finish or `DRAIN_TIMEOUT` passes; a
+    /// drained model is then shut down. Returns whether the model drained;
+    /// one still held after the timeout is unloaded from the registry, but
+    /// its memory stays counted until the last request finishes.
+    async fn remove(&self, name: &str) -> EmbeddingResult<bool> {
+        let Some(mut model) = self.models.write().await.remove(name) else {
+            return Err(crate::EmbeddingError::ModelNotFound {
                 model_name: name.to_string(),
-            })
+            });
+        };
+        self.set_state(name, ModelState::Draining, None);
+        self.model_infos.write().await.remove(name);
+        self.quarantined.write().await.remove(name);
+        self.last_used.lock().unwrap().remove(name);
+
+        let draining = tokio::time::Instant::now();
+        while Arc::strong_count(&model) > 1 && draining.elapsed() < DRAIN_TIMEOUT {
+            tokio::time::sleep(Duration::from_millis(10)).await;
         }
+
+        let in_flight = Arc::strong_count(&model) - 1;
+        if in_flight > 0 {
+            tracing::warn!(
+                "⚠️ '{}' still has {} requests in flight after {}s; its memory stays in use until they finish",
+                name, in_flight, DRAIN_TIMEOUT.as_secs()
+            );
+            self.lingering.lock().unwrap().push((name.to_string(), Arc::downgrade(&model)));
+            self.set_state(name, ModelState::Unloaded, Some(format!("drain timed out with {} requests in flight", in_flight)));
+            return Ok(false);
+        }
+        if let Some(model) = Arc::get_mut(&mut model) {
+            if let Err(e) = model.shutdown().await {
+                tracing::warn!("⚠️ Shutdown of '{}' failed: {}", name, e);
+            }
+        }
+        drop(model);
+        self.set_state(name, ModelState::Unloaded, None);
+        Ok(true)
+    }
+
+    /// Take a model out of service until it is reloaded
+    pub async fn quarantine(&self, name: &str, reason: String) {
+        self.set_state(name, ModelState::Degraded, Some(reason.clone()));
+        self.quarantined.write().await.insert(name.to_string(), reason);
+    }
+
+    /// Why a model is quarantined, if it is
+    pub async fn quarantine_reason(&self, name: &str) -> Option<String> {
+        self.quarantined.read().await.get(name).cloned()
     }
 
     /// Get the default model
@@ -141,14 +670,39 @@ impl ModelRegistry {
     }
 
     /// Shutdown all models
+    ///
+    /// Each model is drained and shut down as in `unload_model`. Ensembles go
+    /// first, so the members they hold are free to drain.
     pub async fn shutdown_all(&self) -> EmbeddingResult<()> {
-        let mut models = self.models.write().await;
-        models.clear();
-        self.model_infos.write().await.clear();
+        let mut names: Vec<(bool, String)> = self.models
+            .read()
+            .await
+            .iter()
+            .map(|(name, model)| (model.info().backend != "ensemble", name.clone()))
+            .collect();
+        names.sort();
+
+        for (_, name) in names {
+            match self.remove(&name).await {
+                // Unloaded concurrently
+                Ok(_) | Err(crate::EmbeddingError::ModelNotFound { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
         Ok(())
     }
 }
 
+/// Size of a model's files on disk, the memory estimate before it first loads
+fn file_sizes(config: &crate::models::config::ModelConfig) -> u64 {
+    [&config.model_path, &config.tokenizer_path]
+        .into_iter()
+        .filter(|path| !path.is_empty())
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
 impl Default for ModelRegistry {
     fn default() -> Self {
         Self::new()
@@ -158,6 +712,7 @@ impl Default for ModelRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::model::mock::{mock_config, mock_model_config, mock_model_toml};
 
     #[tokio::test]
     async fn test_registry_creation() {
@@ -177,4 +732,334 @@ mod tests {
         assert!(!registry.is_model_loaded("test-model").await);
         assert!(registry.get_model("test-model").await.is_none());
     }
+
+    #[tokio::test]
+    async fn test_registry_loads_mock_model() {
+        let config = mock_model_config("mock-model", "embedding_dimension = 8");
+
+        let registry = ModelRegistry::new();
+        registry.load_model(&config).await.unwrap();
+        assert!(registry.is_model_loaded("mock-model").await);
+        let info = registry.get_model_info("mock-model").await.unwrap();
+        assert_eq!(info.backend, "mock");
+        assert_eq!(info.warmup.unwrap().passes.len(), 4);
+        assert_eq!(registry.fingerprint("mock-model").await, info.fingerprint);
+        assert!(info.fingerprint.is_some());
+
+        let model = registry.get_model("mock-model").await.unwrap();
+        assert_eq!(model.embed_text("hello").await.unwrap().len(), 8);
+
+        // Unloading waits for outstanding references to be released
+        drop(model);
+        registry.unload_model("mock-model").await.unwrap();
+        assert!(!registry.is_model_loaded("mock-model").await);
+    }
+
+    #[tokio::test]
+    async fn test_ensemble_combines_members() {
+        let config = mock_config("combined", &[
+            mock_model_toml("small", ""),
+            mock_model_toml("large", "max_sequence_length = 256\nembedding_dimension = 6\n[models.large.mock]\nseed = 7"),
+            mock_model_toml("combined", r#"
+                description = "Concatenation of small and large"
+                max_sequence_length = 512
+                embedding_dimension = 0
+                backend = "ensemble"
+                [models.combined.ensemble]
+                members = ["small", "large"]
+                weights = [1.0, 3.0]
+            "#),
+        ]);
+        config.validate().unwrap();
+
+        let registry = Arc::new(ModelRegistry::new());
+        registry.load_from_config(&config).await.unwrap();
+        let info = registry.get_model_info("combined").await.unwrap();
+        assert_eq!((info.dimension, info.max_sequence_length), (10, 128));
+
+        let combined = registry.get_model("combined").await.unwrap();
+        let embedding = combined.embed_text("hello").await.unwrap();
+        let small = registry.get_model("small").await.unwrap().embed_text("hello").await.unwrap();
+        assert_eq!(embedding.len(), 10);
+        let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+        // Unit members weighted 1:3 end up scaled by 1/sqrt(10) and 3/sqrt(10)
+        let expected = 1.0 / 10f32.sqrt();
+        assert!(embedding.iter().zip(&small).all(|(a, b)| (a - b * expected).abs() < 1e-5));
+
+        let batch = combined.embed_batch(&["hello".to_string(), "world".to_string()]).await.unwrap();
+        assert_eq!(batch[0], embedding);
+
+        // A shut down ensemble can be initialized again in place
+        let members = vec![registry.get_model("small").await.unwrap(), registry.get_model("large").await.unwrap()];
+        let mut reloaded = crate::models::model::ModelFactory::create_ensemble(config.get_model("combined").unwrap(), members.clone()).unwrap();
+        reloaded.initialize().await.unwrap();
+        reloaded.shutdown().await.unwrap();
+        assert!(!reloaded.is_ready().await);
+        reloaded.initialize().await.unwrap();
+        assert_eq!(reloaded.embed_text("hello").await.unwrap(), embedding);
+
+        // Averaging members of different sizes is rejected at load time
+        let mut average = config.get_model("combined").unwrap().clone();
+        average.ensemble.combine = "average".to_string();
+        assert!(registry.load_model(&average).await.is_err());
+
+        // So is an unknown combine mode, in validation and when building the model
+        let mut typo = config.clone();
+        typo.models.get_mut("combined").unwrap().ensemble.combine = "avg".to_string();
+        assert!(typo.validate().is_err());
+        let typo = typo.get_model("combined").unwrap();
+        assert!(crate::models::model::ModelFactory::create_ensemble(typo, members).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lazy_and_on_demand_loading() {
+        let config = mock_config("lazy", &[
+            mock_model_toml("lazy", "load_policy = \"lazy\"\n[models.lazy.mock]\nlatency_ms = 20"),
+            mock_model_toml("rare", "load_policy = \"on_demand\"\nidle_timeout_secs = 0\n[models.rare.warmup]\nenabled = false"),
+        ]);
+        config.validate().unwrap();
+
+        let registry = Arc::new(ModelRegistry::new());
+        registry.load_from_config(&config).await.unwrap();
+        assert!(registry.list_models().await.is_empty());
+
+        // Concurrent first requests share a single load
+        let requests: Vec<_> = (0..4)
+            .map(|_| {
+                let registry = Arc::clone(&registry);
+                tokio::spawn(async move { registry.get_or_load("lazy").await.unwrap().unwrap() })
+            })
+            .collect();
+        let mut models = Vec::new();
+        for request in requests {
+            models.push(request.await.unwrap());
+        }
+        assert!(models.iter().all(|model| Arc::ptr_eq(model, &models[0])));
+        assert!(registry.get_or_load("missing").await.unwrap().is_none());
+
+        registry.get_or_load("rare").await.unwrap().unwrap();
+        assert!(registry.is_model_loaded("rare").await);
+        assert_eq!(registry.unload_idle().await.unwrap(), vec!["rare".to_string()]);
+        assert!(!registry.is_model_loaded("rare").await);
+        assert!(registry.is_model_loaded("lazy").await);
+        assert!(registry.get_or_load("rare").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_memory_budget_evicts_least_recently_used() {
+        let model = |name: &str, extra: &str| mock_model_toml(
+            name,
+            &format!("memory_estimate_mb = 100\n{}\n[models.{}.warmup]\nenabled = false", extra, name),
+        );
+        let mut config = mock_config("main", &[model("main", ""), model("first", ""), model("second", "load_policy = \"lazy\"")]);
+        config.global.memory_budget_mb = Some(250);
+        config.validate().unwrap();
+
+        let registry = Arc::new(ModelRegistry::new());
+        registry.load_from_config(&config).await.unwrap();
+        assert_eq!(registry.residency_metrics().await.resident_bytes, 200 * MB);
+
+        // The pinned default model stays; the least recently used other model makes room
+        registry.get_or_load("main").await.unwrap().unwrap();
+        registry.get_or_load("second").await.unwrap().unwrap();
+        assert!(!registry.is_model_loaded("first").await);
+        assert!(registry.is_model_loaded("main").await);
+
+        // Evicted eager models load again on their next request
+        registry.get_or_load("first").await.unwrap().unwrap();
+        assert!(!registry.is_model_loaded("second").await);
+        assert_eq!(registry.get_model_info("first").await.unwrap().memory_bytes, Some(100 * MB));
+
+        let metrics = registry.residency_metrics().await;
+        assert_eq!(metrics.budget_bytes, Some(250 * MB));
+        assert_eq!((metrics.resident_bytes, metrics.evictions, metrics.reloads), (200 * MB, 2, 1));
+
+        // A model that cannot fit even after evicting everything unpinned fails to load
+        let mut huge = config.get_model("second").unwrap().clone();
+        huge.memory_estimate_mb = Some(200);
+        assert!(registry.load_model(&huge).await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_memory_budget_under_concurrent_loads_and_slow_drains() {
+        let model = |name: &str| mock_model_toml(
+            name,
+            &format!("memory_estimate_mb = 100\nload_policy = \"lazy\"\n[models.{}.mock]\nlatency_ms = 20", name),
+        );
+        let mut config = mock_config("main", &[model("main"), model("first"), model("second")]);
+        config.global.memory_budget_mb = Some(250);
+
+        let registry = Arc::new(ModelRegistry::new());
+        registry.load_from_config(&config).await.unwrap();
+        registry.get_or_load("main").await.unwrap().unwrap();
+
+        // Both loads warm up concurrently, but the second plans only once the first is recorded
+        let loaded = |name| {
+            let registry = Arc::clone(&registry);
+            async move { registry.get_or_load(name).await.map(|model| model.is_some()) }
+        };
+        let (first, second) = tokio::join!(loaded("first"), loaded("second"));
+        assert!(first.unwrap() && second.unwrap());
+        let metrics = registry.residency_metrics().await;
+        assert_eq!((metrics.resident_bytes, metrics.evictions), (200 * MB, 1));
+
+        // A victim still held by a request is not freed: the eviction fails and its memory stays counted
+        let resident = if registry.is_model_loaded("first").await { "first" } else { "second" };
+        let evicted = if resident == "first" { "second" } else { "first" };
+        let in_flight = registry.get_model(resident).await.unwrap();
+        let error = registry.get_or_load(evicted).await.err().unwrap().to_string();
+        assert!(error.contains("still in use"), "{}", error);
+        assert!(!registry.is_model_loaded(resident).await);
+        let metrics = registry.residency_metrics().await;
+        assert_eq!((metrics.resident_bytes, metrics.evictions), (200 * MB, 1));
+
+        // Once the request finishes, the memory is available again
+        drop(in_flight);
+        registry.get_or_load(evicted).await.unwrap().unwrap();
+        assert_eq!(registry.residency_metrics().await.resident_bytes, 200 * MB);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failed_models_do_not_block_startup() {
+        let config_with = |broken: &str| {
+            let mut config = mock_config("main", &[
+                mock_model_toml("main", ""),
+                mock_model_toml("broken", broken),
+                // Warmup and fingerprint embeddings of 150ms each exceed the timeout on the paused clock
+                mock_model_toml("slow", "[models.slow.mock]\nlatency_ms = 150"),
+                mock_model_toml("slow-lazy", "load_policy = \"lazy\"\n[models.slow-lazy.mock]\nlatency_ms = 150"),
+            ]);
+            config.global.init_timeout = 1;
+            config.global.load_parallelism = 2;
+            config
+        };
+
+        let config = config_with("[models.broken.mock]\nfailure_rate = 1.5");
+        config.validate().unwrap();
+        let registry = Arc::new(ModelRegistry::new());
+        registry.load_from_config(&config).await.unwrap();
+        assert_eq!(registry.list_models().await, vec!["main".to_string()]);
+
+        let failures = registry.load_failures().await;
+        assert!(failures["broken"].contains("failure_rate"));
+        assert!(failures["slow"].contains("init_timeout"));
+        assert!(registry.get_or_load("broken").await.is_err());
+        // The dropped load never registers the model, however long it would have taken
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert!(!registry.is_model_loaded("slow").await);
+
+        // Loads on first use have the same timeout
+        let error = registry.get_or_load("slow-lazy").await.err().unwrap().to_string();
+        assert!(error.contains("init_timeout"), "{}", error);
+        assert_eq!(registry.model_status("slow-lazy").await.unwrap().state, ModelState::Failed);
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert!(!registry.is_model_loaded("slow-lazy").await);
+
+        // A required model that fails fails startup, but the others still load
+        let config = config_with("required = true\n[models.broken.mock]\nfailure_rate = 1.5");
+        let registry = Arc::new(ModelRegistry::new());
+        let error = registry.load_from_config(&config).await.unwrap_err().to_string();
+        assert!(error.contains("broken") && !error.contains("slow"));
+        assert!(registry.is_model_loaded("main").await);
+    }
+
+    // Real time: the paused clock does not advance while a blocking task runs
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_blocking_initialize_times_out() {
+        let mut config = mock_config("main", &[
+            mock_model_toml("main", ""),
+            // Blocks its thread without yielding for longer than init_timeout
+            mock_model_toml("stuck", "[models.stuck.mock]\ninit_delay_ms = 2000"),
+        ]);
+        config.global.init_timeout = 1;
+
+        let registry = Arc::new(ModelRegistry::new());
+        let started = Instant::now();
+        registry.load_from_config(&config).await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(2000), "{:?}", started.elapsed());
+        assert!(registry.load_failures().await["stuck"].contains("init_timeout"));
+
+        // Initialization finishing afterwards does not register the model
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert_eq!(registry.list_models().await, vec!["main".to_string()]);
+        assert_eq!(registry.model_status("stuck").await.unwrap().state, ModelState::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_states() {
+        let model = |name: &str, extra: &str| mock_model_toml(name, &format!("{}\n[models.{}.warmup]\nenabled = false", extra, name));
+        let config = mock_config("main", &[
+            model("main", ""),
+            model("lazy", "load_policy = \"lazy\""),
+            model("broken", "[models.broken.mock]\nfailure_rate = 1.5"),
+        ]);
+
+        let registry = Arc::new(ModelRegistry::new());
+        registry.load_from_config(&config).await.unwrap();
+        let states: Vec<_> = registry.model_statuses().await.into_iter().map(|status| (status.name, status.state)).collect();
+        assert_eq!(states, vec![
+            ("broken".to_string(), ModelState::Failed),
+            ("lazy".to_string(), ModelState::Configured),
+            ("main".to_string(), ModelState::Ready),
+        ]);
+        let info = registry.get_model_info("main").await.unwrap();
+        let history: Vec<_> = info.lifecycle.unwrap().history.into_iter().map(|transition| transition.state).collect();
+        assert_eq!(history, vec![ModelState::Configured, ModelState::Loading, ModelState::WarmingUp, ModelState::Ready]);
+        let error = registry.get_or_load("broken").await.err().unwrap().to_string();
+        assert!(error.contains("'broken' is failed") && error.contains("failure_rate"), "{}", error);
+
+        // A model draining for a request in flight refuses new requests with a retry hint
+        let in_flight = registry.get_model("main").await.unwrap();
+        let unload = tokio::spawn({
+            let registry = Arc::clone(&registry);
+            async move { registry.unload_model("main").await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(registry.model_status("main").await.unwrap().state, ModelState::Draining);
+        match registry.get_or_load("main").await {
+            Err(crate::EmbeddingError::ModelUnavailable { state, retry_after_secs, .. }) => {
+                assert_eq!((state.as_str(), retry_after_secs), ("draining", Some(1)));
+            }
+            other => panic!("expected the model to be unavailable, got {:?}", other.map(|model| model.is_some())),
+        }
+        drop(in_flight);
+        unload.await.unwrap().unwrap();
+        assert_eq!(registry.model_status("main").await.unwrap().state, ModelState::Unloaded);
+        assert!(registry.get_or_load("main").await.err().unwrap().to_string().contains("is unloaded"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_drains_every_model() {
+        let config = mock_config("main", &[
+            mock_model_toml("main", "[models.main.warmup]\nenabled = false"),
+            mock_model_toml("combined", "backend = \"ensemble\"\n[models.combined.ensemble]\nmembers = [\"main\"]"),
+        ]);
+        config.validate().unwrap();
+
+        let registry = Arc::new(ModelRegistry::new());
+        registry.load_from_config(&config).await.unwrap();
+
+        // Shutdown waits for the request in flight instead of only recording a drain
+        let in_flight = registry.get_model("combined").await.unwrap();
+        let shutdown = tokio::spawn({
+            let registry = Arc::clone(&registry);
+            async move { registry.shutdown_all().await }
+        });
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(!shutdown.is_finished());
+        assert_eq!(registry.model_status("combined").await.unwrap().state, ModelState::Draining);
+        assert!(in_flight.is_ready().await);
+        drop(in_flight);
+        shutdown.await.unwrap().unwrap();
+
+        assert!(registry.list_models().await.is_empty());
+        for name in ["combined", "main"] {
+            let status = registry.model_status(name).await.unwrap();
+            // The ensemble went first, so its member drained without waiting out the timeout
+            assert_eq!((status.state, status.reason), (ModelState::Unloaded, None), "{}", name);
+            let history: Vec<_> = status.history.iter().map(|transition| transition.state).collect();
+            assert_eq!(history[history.len() - 2..], [ModelState::Draining, ModelState::Unloaded]);
+        }
+    }
 }
@@ -0,0 +1,175 @@
+//! Memory-budgeted model residency
+//!
+//! With `global.memory_budget_mb` set, the registry keeps the estimated
+//! memory of loaded models within the budget. Before a model loads, the
+//! least recently used unpinned models are evicted to make room for it. The
+//! default model, models with `pinned = true` and ensemble members are never
+//! evicted.
+//!
+//! Each model's memory is estimated from the resident memory it added while
+//! loading and warming up. Where that is not available, the size of its
+//! files is used instead. `memory_estimate_mb` overrides both. Estimates are
+//! kept after a model is unloaded, so a reload is planned with the measured
+//! size.
+//!
+//! Under a budget, loads are planned and recorded one at a time, so
+//! concurrent loads cannot count on the same free memory. A model that is
+//! unloaded while requests still hold it keeps counting until they finish,
+//! and an eviction that cannot drain its victim fails the load.
+
+use crate::models::{EmbeddingError, EmbeddingResult};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+/// Bytes per megabyte
+pub const MB: u64 = 1024 * 1024;
+
+/// Residency counters, exported with the manager metrics
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ResidencyMetrics {
+    /// Configured budget, if any
+    pub budget_bytes: Option<u64>,
+    /// Estimated memory of the loaded models
+    pub resident_bytes: u64,
+    /// Models evicted to stay within the budget
+    pub evictions: u64,
+    /// Loads of models that had been evicted before
+    pub reloads: u64,
+}
+
+/// Budget, pins and memory estimates of a registry's models
+#[derive(Debug, Default)]
+pub struct Residency {
+    budget_bytes: Option<u64>,
+    pinned: HashSet<String>,
+    estimates: HashMap<String, u64>,
+    evicted: HashSet<String>,
+    evictions: u64,
+    reloads: u64,
+}
+
+impl Residency {
+    /// Set the budget and the models that must stay resident
+    pub fn configure(&mut self, budget_bytes: Option<u64>, pinned: HashSet<String>) {
+        self.budget_bytes = budget_bytes;
+        self.pinned = pinned;
+    }
+
+    /// Whether a memory budget is configured
+    pub fn has_budget(&self) -> bool {
+        self.budget_bytes.is_some()
+    }
+
+    /// Last known memory estimate of a model
+    pub fn estimate(&self, name: &str) -> Option<u64> {
+        self.estimates.get(name).copied()
+    }
+
+    /// Record a model's memory after loading; returns whether it had been evicted before
+    pub fn record_load(&mut self, name: &str, bytes: u64) -> bool {
+        self.estimates.insert(name.to_string(), bytes);
+        let reload = self.evicted.remove(name);
+        if reload {
+            self.reloads += 1;
+        }
+        reload
+    }
+
+    /// Record that a model was evicted
+    pub fn record_eviction(&mut self, name: &str) {
+        self.evicted.insert(name.to_string());
+        self.evictions += 1;
+    }
+
+    /// Models to evict so that `needed` more bytes fit in the budget
+    ///
+    /// `resident` lists the other loaded models with their last use, and
+    /// `lingering` the unloaded models whose memory is still held. Fails when
+    /// evicting every unpinned model would still not make enough room.
+    pub fn plan_evictions(
+        &self,
+        loading: &str,
+        needed: u64,
+        resident: &[(String, Instant)],
+        lingering: &[String],
+    ) -> EmbeddingResult<Vec<String>> {
+        let Some(budget) = self.budget_bytes else {
+            return Ok(Vec::new());
+        };
+
+        let mut used = self.resident_bytes(resident.iter().map(|(name, _)| name.as_str()).chain(lingering.iter().map(String::as_str)));
+        let mut candidates: Vec<&(String, Instant)> = resident
+            .iter()
+            .filter(|(name, _)| !self.pinned.contains(name))
+            .collect();
+        candidates.sort_by_key(|(_, last_used)| *last_used);
+
+        let mut victims = Vec::new();
+        let mut candidates = candidates.into_iter();
+        while used + needed > budget {
+            let Some((name, _)) = candidates.next() else {
+                return Err(EmbeddingError::ModelLoadError {
+                    model_name: loading.to_string(),
+                    error: format!(
+                        "needs about {} MB, but the {} MB memory budget has only {} MB left after evicting every unpinned model",
+                        needed / MB, budget / MB, budget.saturating_sub(used) / MB
+                    ),
+                });
+            };
+            used = used.saturating_sub(self.estimate(name).unwrap_or(0));
+            victims.push(name.clone());
+        }
+        Ok(victims)
+    }
+
+    /// Current counters, given the names of the loaded models
+    pub fn metrics<'a>(&self, loaded: impl Iterator<Item = &'a str>) -> ResidencyMetrics {
+        ResidencyMetrics {
+            budget_bytes: self.budget_bytes,
+            resident_bytes: self.resident_bytes(loaded),
+            evictions: self.evictions,
+            reloads: self.reloads,
+        }
+    }
+
+    fn resident_bytes<'a>(&self, loaded: impl Iterator<Item = &'a str>) -> u64 {
+        loaded.map(|name| self.estimate(name).unwrap_or(0)).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_evicts_least_recently_used_unpinned_models() {
+        let mut residency = Residency::default();
+        residency.configure(Some(300 * MB), HashSet::from(["default".to_string()]));
+        for name in ["default", "old", "recent"] {
+            residency.record_load(name, 100 * MB);
+        }
+
+        let now = Instant::now();
+        let resident = vec![
+            ("default".to_string(), now - Duration::from_secs(60)),
+            ("old".to_string(), now - Duration::from_secs(30)),
+            ("recent".to_string(), now),
+        ];
+
+        assert!(residency.plan_evictions("new", 0, &resident, &[]).unwrap().is_empty());
+        assert_eq!(residency.plan_evictions("new", 100 * MB, &resident, &[]).unwrap(), vec!["old"]);
+        assert_eq!(residency.plan_evictions("new", 200 * MB, &resident, &[]).unwrap(), vec!["old", "recent"]);
+        assert!(residency.plan_evictions("new", 250 * MB, &resident, &[]).is_err());
+        // Memory still held by an unloaded model is not available
+        assert!(residency.plan_evictions("new", 200 * MB, &resident, &["gone".to_string()]).is_ok());
+        residency.record_load("gone", 50 * MB);
+        assert!(residency.plan_evictions("new", 200 * MB, &resident, &["gone".to_string()]).is_err());
+
+        residency.record_eviction("old");
+        assert!(residency.record_load("old", 100 * MB));
+        let metrics = residency.metrics(["default", "old"].into_iter());
+        assert_eq!((metrics.resident_bytes, metrics.evictions, metrics.reloads), (200 * MB, 1, 1));
+    }
+}
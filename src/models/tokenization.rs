@@ -0,0 +1,138 @@
+//! Tokenizer inspection helpers
+//!
+//! Chunkers and clients that budget by tokens need the exact tokenization of
+//! the model serving their embeddings. Models expose their tokenizer through
+//! `EmbeddingModel::tokenizer`, and these helpers turn it into the
+//! tokenize / decode / count responses served over HTTP and OVNT.
+
+use crate::models::{EmbeddingError, EmbeddingResult};
+use serde::{Deserialize, Serialize};
+use tokenizers::Tokenizer;
+
+/// Result of tokenizing a text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenizedText {
+    /// Token ids
+    pub ids: Vec<u32>,
+    /// Token strings
+    pub tokens: Vec<String>,
+    /// Byte offsets `(start, end)` of each token in the input text
+    pub offsets: Vec<(usize, usize)>,
+    /// Whether each token is a special token added by the tokenizer
+    pub special_tokens_mask: Vec<bool>,
+}
+
+/// Copy of a model's tokenizer with padding and truncation removed
+///
+/// Engines pad and truncate to fit their inputs, but token counts must
+/// reflect the whole text or chunkers would never see a chunk as too long.
+pub fn for_inspection(tokenizer: &Tokenizer) -> Tokenizer {
+    let mut tokenizer = tokenizer.clone();
+    tokenizer.with_padding(None);
+    // Removing truncation cannot fail; only invalid truncation params are rejected
+    let _ = tokenizer.with_truncation(None);
+    tokenizer
+}
+
+/// Error for models that have no tokenizer to inspect
+pub fn missing_tokenizer(model_name: &str) -> EmbeddingError {
+    EmbeddingError::InvalidInput {
+        message: format!("Model '{}' does not have a tokenizer", model_name),
+    }
+}
+
+/// Tokenize a text
+pub fn tokenize(tokenizer: &Tokenizer, text: &str, add_special_tokens: bool) -> EmbeddingResult<TokenizedText> {
+    let encoding = tokenizer.encode(text, add_special_tokens)
+        .map_err(|e| EmbeddingError::EmbeddingFailed {
+            error: format!("Tokenization failed: {}", e),
+        })?;
+
+    Ok(TokenizedText {
+        ids: encoding.get_ids().to_vec(),
+        tokens: encoding.get_tokens().to_vec(),
+        offsets: encoding.get_offsets().to_vec(),
+        special_tokens_mask: encoding.get_special_tokens_mask().iter().map(|&flag| flag == 1).collect(),
+    })
+}
+
+/// Count the tokens of a text
+pub fn count_tokens(tokenizer: &Tokenizer, text: &str, add_special_tokens: bool) -> EmbeddingResult<usize> {
+    let encoding = tokenizer.encode(text, add_special_tokens)
+        .map_err(|e| EmbeddingError::EmbeddingFailed {
+            error: format!("Tokenization failed: {}", e),
+        })?;
+
+    Ok(encoding.len())
+}
+
+/// Decode token ids back to text
+pub fn decode(tokenizer: &Tokenizer, ids: &[u32], skip_special_tokens: bool) -> EmbeddingResult<String> {
+    let vocab_size = tokenizer.get_vocab_size(true);
+    if let Some(id) = ids.iter().find(|&&id| id as usize >= vocab_size) {
+        return Err(EmbeddingError::InvalidInput {
+            message: format!("Token id {} is outside the vocabulary (size {})", id, vocab_size),
+        });
+    }
+
+    tokenizer.decode(ids, skip_special_tokens)
+        .map_err(|e| EmbeddingError::EmbeddingFailed {
+            error: format!("Decoding failed: {}", e),
+        })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tokenizers::models::wordlevel::WordLevel;
+    use tokenizers::pre_tokenizers::whitespace::Whitespace;
+    use tokenizers::processors::template::TemplateProcessing;
+    use tokenizers::{AddedToken, PaddingParams, TruncationParams};
+
+    /// Word-level tokenizer wrapping inputs in [CLS] ... [SEP]
+    pub(crate) fn test_tokenizer() -> Tokenizer {
+        let vocab: HashMap<String, u32> = [("[UNK]", 0), ("[CLS]", 1), ("[SEP]", 2), ("hello", 3), ("world", 4)]
+            .into_iter()
+            .map(|(token, id)| (token.to_string(), id))
+            .collect();
+        let model = WordLevel::builder().vocab(vocab).unk_token("[UNK]".to_string()).build().unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Whitespace {});
+        tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true), AddedToken::from("[SEP]", true)]);
+        tokenizer.with_post_processor(
+            TemplateProcessing::builder()
+                .try_single("[CLS] $A [SEP]")
+                .unwrap()
+                .special_tokens(vec![("[CLS]", 1), ("[SEP]", 2)])
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+    }
+
+    #[test]
+    fn test_tokenize_and_decode() {
+        let tokenizer = test_tokenizer();
+        let tokenized = tokenize(&tokenizer, "hello world", true).unwrap();
+        assert_eq!(tokenized.ids, vec![1, 3, 4, 2]);
+        assert_eq!(tokenized.tokens, vec!["[CLS]", "hello", "world", "[SEP]"]);
+        assert_eq!(tokenized.offsets[2], (6, 11));
+        assert_eq!(tokenized.special_tokens_mask, vec![true, false, false, true]);
+
+        assert_eq!(count_tokens(&tokenizer, "hello world", false).unwrap(), 2);
+        assert_eq!(decode(&tokenizer, &tokenized.ids, true).unwrap(), "hello world");
+        assert!(decode(&tokenizer, &[99], true).is_err());
+    }
+
+    #[test]
+    fn test_inspection_ignores_truncation() {
+        let mut tokenizer = test_tokenizer();
+        tokenizer.with_padding(Some(PaddingParams::default()));
+        tokenizer
+            .with_truncation(Some(TruncationParams { max_length: 3, ..Default::default() }))
+            .unwrap();
+        assert_eq!(count_tokens(&tokenizer, "hello world hello", true).unwrap(), 3);
+        assert_eq!(count_tokens(&for_inspection(&tokenizer), "hello world hello", true).unwrap(), 5);
+    }
+}
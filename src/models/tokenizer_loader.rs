@@ -0,0 +1,358 @@
+//! Tokenizer loading with Hugging Face fallbacks
+//!
+//! `tokenizer_path` normally points at a `tokenizer.json`, but many BERT-style
+//! checkpoints only ship the older files. The loader resolves, in order:
+//!
+//! 1. the configured file, when it exists (`tokenizer.json`, or a `vocab.txt`)
+//! 2. `tokenizer.json` in the configured directory (or the file's directory)
+//! 3. `vocab.txt` in that directory, built into a BERT WordPiece tokenizer
+//!
+//! Settings from `tokenizer_config.json` (`do_lower_case`, `model_max_length`,
+//! `padding_side`) and special tokens from `special_tokens_map.json` next to
+//! the tokenizer are then applied, as the `transformers` library would.
+
+use crate::models::EmbeddingError;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tokenizers::decoders::wordpiece::WordPiece as WordPieceDecoder;
+use tokenizers::models::wordpiece::WordPiece;
+use tokenizers::normalizers::{BertNormalizer, Lowercase, NormalizerWrapper, Sequence};
+use tokenizers::pre_tokenizers::bert::BertPreTokenizer;
+use tokenizers::processors::bert::BertProcessing;
+use tokenizers::{AddedToken, PaddingDirection, PaddingParams, Tokenizer, TruncationParams};
+use tracing::{debug, info};
+
+/// `transformers` stores "no limit" as a huge model_max_length (1e30)
+const UNBOUNDED_MODEL_MAX_LENGTH: f64 = 1e9;
+
+/// Where the tokenizer itself comes from
+#[derive(Debug, Clone, PartialEq)]
+enum TokenizerSource {
+    Json(PathBuf),
+    Vocab(PathBuf),
+}
+
+/// A special token, written either as a string or as an AddedToken object
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum TokenValue {
+    Plain(String),
+    Added { content: String },
+}
+
+impl TokenValue {
+    fn content(&self) -> &str {
+        match self {
+            TokenValue::Plain(content) | TokenValue::Added { content } => content,
+        }
+    }
+}
+
+/// Special tokens, as named in special_tokens_map.json and tokenizer_config.json
+#[derive(Debug, Clone, Default, Deserialize)]
+struct SpecialTokens {
+    unk_token: Option<TokenValue>,
+    sep_token: Option<TokenValue>,
+    pad_token: Option<TokenValue>,
+    cls_token: Option<TokenValue>,
+    mask_token: Option<TokenValue>,
+    #[serde(default)]
+    additional_special_tokens: Vec<TokenValue>,
+}
+
+impl SpecialTokens {
+    /// Fill tokens missing here from `fallback`
+    fn or(self, fallback: SpecialTokens) -> SpecialTokens {
+        SpecialTokens {
+            unk_token: self.unk_token.or(fallback.unk_token),
+            sep_token: self.sep_token.or(fallback.sep_token),
+            pad_token: self.pad_token.or(fallback.pad_token),
+            cls_token: self.cls_token.or(fallback.cls_token),
+            mask_token: self.mask_token.or(fallback.mask_token),
+            additional_special_tokens: if self.additional_special_tokens.is_empty() {
+                fallback.additional_special_tokens
+            } else {
+                self.additional_special_tokens
+            },
+        }
+    }
+
+    /// The tokens of the original BERT vocabularies
+    fn bert_defaults() -> SpecialTokens {
+        let token = |content: &str| Some(TokenValue::Plain(content.to_string()));
+        SpecialTokens {
+            unk_token: token("[UNK]"),
+            sep_token: token("[SEP]"),
+            pad_token: token("[PAD]"),
+            cls_token: token("[CLS]"),
+            mask_token: token("[MASK]"),
+            additional_special_tokens: Vec::new(),
+        }
+    }
+
+    fn all(&self) -> impl Iterator<Item = &str> {
+        [&self.unk_token, &self.sep_token, &self.pad_token, &self.cls_token, &self.mask_token]
+            .into_iter()
+            .flatten()
+            .chain(&self.additional_special_tokens)
+            .map(TokenValue::content)
+    }
+}
+
+/// The subset of tokenizer_config.json the loader understands
+#[derive(Debug, Clone, Default, Deserialize)]
+struct TokenizerConfigFile {
+    do_lower_case: Option<bool>,
+    model_max_length: Option<f64>,
+    padding_side: Option<String>,
+    #[serde(flatten)]
+    special_tokens: SpecialTokens,
+}
+
+/// Load the tokenizer for `tokenizer_path`, falling back to vocab.txt
+///
+/// # Arguments
+/// * `tokenizer_path` - Path to tokenizer.json, vocab.txt or the directory holding them
+pub fn load_tokenizer(tokenizer_path: &str) -> Result<Tokenizer, EmbeddingError> {
+    let path = Path::new(tokenizer_path);
+    let source = resolve_source(path)?;
+    let dir = match &source {
+        TokenizerSource::Json(file) | TokenizerSource::Vocab(file) => {
+            file.parent().map(Path::to_path_buf).unwrap_or_default()
+        }
+    };
+
+    let config: TokenizerConfigFile = read_optional_json(&dir.join("tokenizer_config.json"))?.unwrap_or_default();
+    let special_tokens_map: SpecialTokens = read_optional_json(&dir.join("special_tokens_map.json"))?.unwrap_or_default();
+    let mut special_tokens = special_tokens_map.or(config.special_tokens.clone());
+    if matches!(source, TokenizerSource::Vocab(_)) {
+        special_tokens = special_tokens.or(SpecialTokens::bert_defaults());
+    }
+
+    let mut tokenizer = match &source {
+        TokenizerSource::Json(file) => {
+            let mut tokenizer = Tokenizer::from_file(file)
+                .map_err(|e| EmbeddingError::ModelLoadFailed {
+                    error: format!("Failed to load tokenizer: {}", e),
+                })?;
+            if config.do_lower_case == Some(true) {
+                ensure_lowercase(&mut tokenizer);
+            }
+            tokenizer
+        }
+        TokenizerSource::Vocab(file) => {
+            info!("Building WordPiece tokenizer from {}", file.display());
+            build_wordpiece(file, &special_tokens, config.do_lower_case.unwrap_or(true))?
+        }
+    };
+
+    // Only tokens the vocabulary knows can be special; adding others would
+    // create ids the model has no embedding for
+    let known: Vec<AddedToken> = special_tokens
+        .all()
+        .filter(|token| tokenizer.token_to_id(token).is_some())
+        .map(|token| AddedToken::from(token.to_string(), true))
+        .collect();
+    tokenizer.add_special_tokens(&known);
+
+    if let Some(max_length) = config.model_max_length {
+        if (1.0..UNBOUNDED_MODEL_MAX_LENGTH).contains(&max_length) && tokenizer.get_truncation().is_none() {
+            tokenizer
+                .with_truncation(Some(TruncationParams {
+                    max_length: max_length as usize,
+                    ..Default::default()
+                }))
+                .map_err(|e| EmbeddingError::ModelLoadFailed {
+                    error: format!("Failed to apply model_max_length {}: {}", max_length, e),
+                })?;
+        }
+    }
+
+    if let Some(side) = &config.padding_side {
+        let direction = match side.to_lowercase().as_str() {
+            "left" => PaddingDirection::Left,
+            "right" => PaddingDirection::Right,
+            other => {
+                return Err(EmbeddingError::ConfigError {
+                    message: format!("Unknown padding_side '{}' in tokenizer_config.json (expected left or right)", other),
+                });
+            }
+        };
+        apply_padding_side(&mut tokenizer, direction, &special_tokens);
+    }
+
+    debug!("Loaded tokenizer from {:?} with {} tokens", source, tokenizer.get_vocab_size(true));
+    Ok(tokenizer)
+}
+
+/// Pick tokenizer.json or vocab.txt for a configured path
+fn resolve_source(path: &Path) -> Result<TokenizerSource, EmbeddingError> {
+    let classify = |file: PathBuf| {
+        if file.extension().is_some_and(|extension| extension == "txt") {
+            TokenizerSource::Vocab(file)
+        } else {
+            TokenizerSource::Json(file)
+        }
+    };
+
+    if path.is_file() {
+        return Ok(classify(path.to_path_buf()));
+    }
+
+    let dir = if path.is_dir() { path } else { path.parent().unwrap_or(Path::new(".")) };
+    for candidate in ["tokenizer.json", "vocab.txt"] {
+        let file = dir.join(candidate);
+        if file.is_file() {
+            if !path.is_dir() {
+                info!("Tokenizer {} not found, falling back to {}", path.display(), file.display());
+            }
+            return Ok(classify(file));
+        }
+    }
+
+    Err(EmbeddingError::ModelLoadFailed {
+        error: format!("Failed to load tokenizer: no tokenizer.json or vocab.txt found for {}", path.display()),
+    })
+}
+
+/// Read a JSON settings file, or None when it does not exist
+fn read_optional_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<T>, EmbeddingError> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let contents = std::fs::read_to_string(path)?;
+    serde_json::from_str(&contents)
+        .map(Some)
+        .map_err(|e| EmbeddingError::ModelLoadFailed {
+            error: format!("Failed to parse {}: {}", path.display(), e),
+        })
+}
+
+/// Build the standard BERT pipeline around a WordPiece vocabulary
+///
+/// `special_tokens` must already be filled with the BERT defaults.
+fn build_wordpiece(vocab_path: &Path, special_tokens: &SpecialTokens, lowercase: bool) -> Result<Tokenizer, EmbeddingError> {
+    let token = |value: &Option<TokenValue>| value.as_ref().map(|value| value.content().to_string()).unwrap_or_default();
+    let unk_token = token(&special_tokens.unk_token);
+    let sep_token = token(&special_tokens.sep_token);
+    let cls_token = token(&special_tokens.cls_token);
+
+    let vocab_path = vocab_path.to_str().ok_or_else(|| EmbeddingError::ModelLoadFailed {
+        error: format!("Vocabulary path is not valid UTF-8: {}", vocab_path.display()),
+    })?;
+    let model = WordPiece::from_file(vocab_path)
+        .unk_token(unk_token)
+        .build()
+        .map_err(|e| EmbeddingError::ModelLoadFailed {
+            error: format!("Failed to load WordPiece vocabulary {}: {}", vocab_path, e),
+        })?;
+
+    let mut tokenizer = Tokenizer::new(model);
+    let special_id = |tokenizer: &Tokenizer, token: &str| {
+        tokenizer.token_to_id(token).ok_or_else(|| EmbeddingError::ModelLoadFailed {
+            error: format!("Special token '{}' is missing from {}", token, vocab_path),
+        })
+    };
+    let sep_id = special_id(&tokenizer, &sep_token)?;
+    let cls_id = special_id(&tokenizer, &cls_token)?;
+
+    tokenizer.with_normalizer(BertNormalizer::new(true, true, None, lowercase));
+    tokenizer.with_pre_tokenizer(BertPreTokenizer);
+    tokenizer.with_post_processor(BertProcessing::new((sep_token, sep_id), (cls_token, cls_id)));
+    tokenizer.with_decoder(WordPieceDecoder::default());
+    Ok(tokenizer)
+}
+
+/// Make sure the normalizer lowercases, as `do_lower_case` asks
+fn ensure_lowercase(tokenizer: &mut Tokenizer) {
+    let normalizer = match tokenizer.get_normalizer().cloned() {
+        None => NormalizerWrapper::Lowercase(Lowercase),
+        Some(NormalizerWrapper::BertNormalizer(mut bert)) => {
+            bert.lowercase = true;
+            NormalizerWrapper::BertNormalizer(bert)
+        }
+        Some(NormalizerWrapper::Lowercase(lowercase)) => NormalizerWrapper::Lowercase(lowercase),
+        Some(other) => NormalizerWrapper::Sequence(Sequence::new(vec![other, NormalizerWrapper::Lowercase(Lowercase)])),
+    };
+    tokenizer.with_normalizer(normalizer);
+}
+
+/// Pad on the configured side, keeping any padding settings from tokenizer.json
+fn apply_padding_side(tokenizer: &mut Tokenizer, direction: PaddingDirection, special_tokens: &SpecialTokens) {
+    if let Some(padding) = tokenizer.get_padding_mut() {
+        padding.direction = direction;
+        return;
+    }
+
+    let pad_token = special_tokens
+        .pad_token
+        .as_ref()
+        .map(|token| token.content().to_string())
+        .unwrap_or_else(|| "[PAD]".to_string());
+    if let Some(pad_id) = tokenizer.token_to_id(&pad_token) {
+        tokenizer.with_padding(Some(PaddingParams {
+            direction,
+            pad_id,
+            pad_token,
+            ..Default::default()
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tokenizer-loader-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in files {
+            std::fs::write(dir.join(file), contents).unwrap();
+        }
+        dir
+    }
+
+    const VOCAB: &str = "[PAD]\n[UNK]\n[CLS]\n[SEP]\n[MASK]\nhello\nworld\n##s\n";
+
+    #[test]
+    fn test_vocab_txt_fallback() {
+        let dir = model_dir("vocab", &[
+            ("vocab.txt", VOCAB),
+            ("tokenizer_config.json", r#"{"do_lower_case": true, "model_max_length": 4, "padding_side": "left"}"#),
+        ]);
+
+        // tokenizer.json is configured but missing, so vocab.txt is used
+        let tokenizer = load_tokenizer(dir.join("tokenizer.json").to_str().unwrap()).unwrap();
+        let encoding = tokenizer.encode("Hello Worlds", true).unwrap();
+        assert_eq!(encoding.get_tokens(), ["[CLS]", "hello", "world", "[SEP]"]);
+        assert!(matches!(tokenizer.get_padding().unwrap().direction, PaddingDirection::Left));
+        assert_eq!(tokenizer.decode(encoding.get_ids(), true).unwrap(), "hello world");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_special_tokens_map() {
+        let vocab = "<pad>\n<unk>\n<s>\n</s>\nhello\n";
+        let dir = model_dir("special", &[
+            ("vocab.txt", vocab),
+            ("tokenizer_config.json", r#"{"do_lower_case": false, "model_max_length": 1e30, "unk_token": "[UNK]"}"#),
+            ("special_tokens_map.json", r#"{"unk_token": "<unk>", "cls_token": {"content": "<s>"}, "sep_token": "</s>", "pad_token": "<pad>"}"#),
+        ]);
+
+        let tokenizer = load_tokenizer(dir.to_str().unwrap()).unwrap();
+        let encoding = tokenizer.encode("hello Hello", true).unwrap();
+        assert_eq!(encoding.get_tokens(), ["<s>", "hello", "<unk>", "</s>"]);
+        assert!(tokenizer.get_truncation().is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_tokenizer() {
+        let dir = model_dir("missing", &[]);
+        assert!(load_tokenizer(dir.join("tokenizer.json").to_str().unwrap()).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,276 @@
+//! Runtime checks on produced embeddings
+//!
+//! Enabled by `development.validate_outputs`. A broken runtime or model file
+//! tends to produce NaNs, zero vectors or one vector for every input, which
+//! otherwise only shows up downstream as poor search results. Every embedding
+//! the manager returns is checked here first.
+
+use crate::models::config::OutputValidationConfig;
+use crate::models::{Embedding, EmbeddingError};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Fewest distinct inputs sharing one vector that count as a collapse
+const MIN_COLLAPSED_INPUTS: usize = 3;
+
+/// Kind of output check that failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputCheck {
+    /// A value is NaN or infinite
+    NonFinite,
+    /// The vector length differs from the model's dimension
+    Dimension,
+    /// The L2 norm is outside the configured range
+    Norm,
+    /// Distinct inputs produced near-identical vectors
+    Collapsed,
+}
+
+impl OutputCheck {
+    /// Name used in errors and metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputCheck::NonFinite => "non_finite",
+            OutputCheck::Dimension => "dimension",
+            OutputCheck::Norm => "norm",
+            OutputCheck::Collapsed => "collapsed",
+        }
+    }
+}
+
+/// An input as the model tells it apart from others
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ModelInput {
+    /// Token ids after preprocessing, for models with a tokenizer
+    Tokens(Vec<u32>),
+    /// The text as sent, for models without one
+    Text(String),
+}
+
+/// A failed output check
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputViolation {
+    /// Check that failed
+    pub check: OutputCheck,
+    /// Description including the offending batch position
+    pub message: String,
+}
+
+impl OutputViolation {
+    /// Convert into the error returned to the caller
+    pub fn into_error(self, model_name: &str) -> EmbeddingError {
+        EmbeddingError::InvalidOutput {
+            model_name: model_name.to_string(),
+            check: self.check.as_str().to_string(),
+            message: self.message,
+        }
+    }
+}
+
+/// Counters of checked embeddings and violations
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ValidationMetrics {
+    /// Embeddings checked
+    pub checked: u64,
+    /// Embeddings with NaN or infinite values
+    pub non_finite: u64,
+    /// Embeddings with an unexpected dimension
+    pub dimension: u64,
+    /// Embeddings with a norm outside the accepted range
+    pub norm: u64,
+    /// Batches with collapsed vectors
+    pub collapsed: u64,
+    /// Models quarantined after a violation
+    pub quarantined: u64,
+}
+
+/// Output validator shared by all models of a manager
+#[derive(Debug)]
+pub struct OutputValidator {
+    config: OutputValidationConfig,
+    checked: AtomicU64,
+    non_finite: AtomicU64,
+    dimension: AtomicU64,
+    norm: AtomicU64,
+    collapsed: AtomicU64,
+    quarantined: AtomicU64,
+}
+
+impl OutputValidator {
+    /// Create a validator with the given thresholds
+    pub fn new(config: OutputValidationConfig) -> Self {
+        Self {
+            config,
+            checked: AtomicU64::new(0),
+            non_finite: AtomicU64::new(0),
+            dimension: AtomicU64::new(0),
+            norm: AtomicU64::new(0),
+            collapsed: AtomicU64::new(0),
+            quarantined: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether a violation should quarantine the model
+    pub fn quarantines(&self) -> bool {
+        self.config.quarantine
+    }
+
+    /// Record that a model was quarantined
+    pub fn record_quarantine(&self) {
+        self.quarantined.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Check a batch of embeddings produced by a model
+    ///
+    /// `inputs` are what the embeddings were produced from, when known. The
+    /// batch counts as collapsed when at least `MIN_COLLAPSED_INPUTS`, and
+    /// more than half, of its distinct inputs share one vector. A single pair
+    /// never does: variants a backend normalizes away, or texts that only
+    /// differ past the truncation point, legitimately embed the same.
+    pub fn check(
+        &self,
+        dimension: usize,
+        inputs: Option<&[ModelInput]>,
+        embeddings: &[Embedding],
+    ) -> Result<(), OutputViolation> {
+        self.checked.fetch_add(embeddings.len() as u64, Ordering::Relaxed);
+
+        let mut norms = Vec::with_capacity(embeddings.len());
+        for (index, embedding) in embeddings.iter().enumerate() {
+            if embedding.len() != dimension {
+                return Err(self.violation(OutputCheck::Dimension, format!(
+                    "embedding {} has {} values, expected {}",
+                    index, embedding.len(), dimension
+                )));
+            }
+
+            if let Some(position) = embedding.iter().position(|value| !value.is_finite()) {
+                return Err(self.violation(OutputCheck::NonFinite, format!(
+                    "embedding {} has {} at position {}",
+                    index, embedding[position], position
+                )));
+            }
+
+            let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm < self.config.min_norm || norm > self.config.max_norm {
+                return Err(self.violation(OutputCheck::Norm, format!(
+                    "embedding {} has norm {}, expected {} to {}",
+                    index, norm, self.config.min_norm, self.config.max_norm
+                )));
+            }
+            norms.push(norm);
+        }
+
+        if let Some(inputs) = inputs {
+            // Group near-identical vectors with the distinct inputs behind each
+            let mut groups: Vec<(usize, Vec<&ModelInput>)> = Vec::new();
+            for (index, input) in inputs.iter().enumerate().take(embeddings.len()) {
+                if norms[index] == 0.0 {
+                    continue;
+                }
+                let similar = groups.iter_mut().find(|(first, _)| {
+                    let dot: f32 = embeddings[*first].iter().zip(&embeddings[index]).map(|(a, b)| a * b).sum();
+                    dot / (norms[*first] * norms[index]) >= self.config.collapse_similarity
+                });
+                match similar {
+                    Some((_, members)) if !members.contains(&input) => members.push(input),
+                    Some(_) => {}
+                    None => groups.push((index, vec![input])),
+                }
+            }
+
+            let distinct = inputs.iter().take(embeddings.len()).collect::<HashSet<_>>().len();
+            if let Some((first, members)) = groups.iter().max_by_key(|(_, members)| members.len()) {
+                if members.len() >= MIN_COLLAPSED_INPUTS && members.len() * 2 > distinct {
+                    return Err(self.violation(OutputCheck::Collapsed, format!(
+                        "{} of {} distinct inputs have near-identical embeddings (like embedding {})",
+                        members.len(), distinct, first
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Current counter values
+    pub fn metrics(&self) -> ValidationMetrics {
+        ValidationMetrics {
+            checked: self.checked.load(Ordering::Relaxed),
+            non_finite: self.non_finite.load(Ordering::Relaxed),
+            dimension: self.dimension.load(Ordering::Relaxed),
+            norm: self.norm.load(Ordering::Relaxed),
+            collapsed: self.collapsed.load(Ordering::Relaxed),
+            quarantined: self.quarantined.load(Ordering::Relaxed),
+        }
+    }
+
+    fn violation(&self, check: OutputCheck, message: String) -> OutputViolation {
+        let counter = match check {
+            OutputCheck::NonFinite => &self.non_finite,
+            OutputCheck::Dimension => &self.dimension,
+            OutputCheck::Norm => &self.norm,
+            OutputCheck::Collapsed => &self.collapsed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        OutputViolation { check, message }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validator() -> OutputValidator {
+        OutputValidator::new(OutputValidationConfig::default())
+    }
+
+    fn texts(texts: &[&str]) -> Vec<ModelInput> {
+        texts.iter().map(|text| ModelInput::Text(text.to_string())).collect()
+    }
+
+    #[test]
+    fn test_accepts_valid_outputs() {
+        let validator = validator();
+        let inputs = texts(&["a", "b", "a"]);
+        let embeddings = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0]];
+        assert!(validator.check(2, Some(&inputs), &embeddings).is_ok());
+        assert_eq!(validator.metrics().checked, 3);
+    }
+
+    #[test]
+    fn test_detects_each_violation() {
+        let validator = validator();
+        let check = |dimension, inputs: Option<&[ModelInput]>, embeddings: Vec<Embedding>| {
+            validator.check(dimension, inputs, &embeddings).unwrap_err().check
+        };
+
+        assert_eq!(check(2, None, vec![vec![f32::NAN, 1.0]]), OutputCheck::NonFinite);
+        assert_eq!(check(3, None, vec![vec![1.0, 0.0]]), OutputCheck::Dimension);
+        assert_eq!(check(2, None, vec![vec![0.0, 0.0]]), OutputCheck::Norm);
+        let inputs = texts(&["a", "b", "c", "d"]);
+        let embeddings = vec![vec![0.6, 0.8], vec![0.6, 0.8], vec![0.0, 1.0], vec![0.6, 0.8]];
+        assert_eq!(check(2, Some(&inputs), embeddings), OutputCheck::Collapsed);
+
+        let metrics = validator.metrics();
+        assert_eq!((metrics.non_finite, metrics.dimension, metrics.norm, metrics.collapsed), (1, 1, 1, 1));
+    }
+    #[test]
+    fn test_variants_sharing_a_vector_are_not_collapsed() {
+        let validator = validator();
+
+        // Texts an uncased or lexical backend normalizes alike, in a pair or a minority
+        let same = vec![0.6, 0.8];
+        let inputs = texts(&["Hello", "hello"]);
+        assert!(validator.check(2, Some(&inputs), &[same.clone(), same.clone()]).is_ok());
+        let inputs = texts(&["Hello", "hello", "a", "b", "c"]);
+        let embeddings = vec![same.clone(), same.clone(), vec![1.0, 0.0], vec![0.0, 1.0], vec![-1.0, 0.0]];
+        assert!(validator.check(2, Some(&inputs), &embeddings).is_ok());
+
+        // Equal token ids are one input however many texts produced them
+        let inputs = vec![ModelInput::Tokens(vec![1, 3, 2]); 3];
+        assert!(validator.check(2, Some(&inputs), &[same.clone(), same.clone(), same]).is_ok());
+        assert_eq!(validator.metrics().collapsed, 0);
+    }
+}
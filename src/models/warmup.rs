@@ -0,0 +1,135 @@
+//! Model warmup at load time
+//!
+//! ONNX Runtime allocates its arenas and selects kernels during the first
+//! runs of each input shape. The registry warms every model with requests of
+//! the configured sizes before inserting it, so readiness is only reported
+//! once that cost has been paid instead of by the first real request.
+
+use crate::models::config::WarmupConfig;
+use crate::models::model::{EmbeddingModel, WarmupPass, WarmupReport};
+use crate::models::{EmbeddingError, EmbeddingResult};
+use std::time::Instant;
+
+/// Words cycled to build synthetic warmup texts
+const WORDS: [&str; 8] = ["the", "quick", "brown", "fox", "jumps", "over", "lazy", "dogs"];
+
+/// Texts of one warmup request
+#[derive(Debug, PartialEq)]
+struct WarmupBatch {
+    /// Approximate tokens per text, for synthetic texts
+    sequence_length: Option<usize>,
+    texts: Vec<String>,
+}
+
+/// Run the configured warmup requests against an initialized model
+pub async fn warm_up(model: &dyn EmbeddingModel, config: &WarmupConfig) -> EmbeddingResult<WarmupReport> {
+    let started = Instant::now();
+    let mut passes = Vec::new();
+
+    for batch in batches(config, model.info().max_sequence_length)? {
+        let pass_started = Instant::now();
+        match batch.texts.as_slice() {
+            [text] => {
+                model.embed_text(text).await?;
+            }
+            texts => {
+                model.embed_batch(texts).await?;
+            }
+        }
+        passes.push(WarmupPass {
+            batch_size: batch.texts.len(),
+            sequence_length: batch.sequence_length,
+            duration_ms: pass_started.elapsed().as_millis() as u64,
+        });
+    }
+
+    Ok(WarmupReport {
+        passes,
+        total_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Warmup requests: every batch size for each sequence length, or for the sample file
+fn batches(config: &WarmupConfig, max_sequence_length: usize) -> EmbeddingResult<Vec<WarmupBatch>> {
+    let batch_sizes: Vec<usize> = config.batch_sizes.iter().copied().filter(|&size| size > 0).collect();
+
+    if let Some(path) = &config.sample_file {
+        let samples: Vec<String> = std::fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        if samples.is_empty() {
+            return Err(EmbeddingError::ConfigError {
+                message: format!("Warmup sample file '{}' contains no texts", path),
+            });
+        }
+
+        return Ok(batch_sizes
+            .iter()
+            .map(|&size| WarmupBatch {
+                sequence_length: None,
+                texts: samples.iter().cycle().take(size).cloned().collect(),
+            })
+            .collect());
+    }
+
+    let mut lengths: Vec<usize> = config.sequence_lengths
+        .iter()
+        .map(|&length| length.min(max_sequence_length))
+        .filter(|&length| length > 0)
+        .collect();
+    lengths.dedup();
+
+    Ok(lengths
+        .iter()
+        .flat_map(|&length| {
+            let text = synthetic_text(length);
+            batch_sizes.iter().map(move |&size| WarmupBatch {
+                sequence_length: Some(length),
+                texts: vec![text.clone(); size],
+            })
+        })
+        .collect())
+}
+
+/// A text of roughly `length` tokens, leaving room for the special tokens
+fn synthetic_text(length: usize) -> String {
+    let words = length.saturating_sub(2).max(1);
+    WORDS.iter().cycle().take(words).copied().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warmup_batches() {
+        let config = WarmupConfig {
+            sequence_lengths: vec![16, 512, 1024],
+            batch_sizes: vec![1, 0, 4],
+            ..Default::default()
+        };
+        let synthetic = batches(&config, 256).unwrap();
+        let shapes: Vec<_> = synthetic.iter().map(|batch| (batch.sequence_length, batch.texts.len())).collect();
+        assert_eq!(shapes, vec![(Some(16), 1), (Some(16), 4), (Some(256), 1), (Some(256), 4)]);
+        assert_eq!(synthetic[0].texts[0].split(' ').count(), 14);
+
+        let path = std::env::temp_dir().join(format!("warmup-samples-{}.txt", std::process::id()));
+        std::fs::write(&path, "first text\n\nsecond text\n").unwrap();
+        let config = WarmupConfig {
+            sample_file: Some(path.to_str().unwrap().to_string()),
+            batch_sizes: vec![3],
+            ..Default::default()
+        };
+        assert_eq!(batches(&config, 256).unwrap(), vec![WarmupBatch {
+            sequence_length: None,
+            texts: vec!["first text".to_string(), "second text".to_string(), "first text".to_string()],
+        }]);
+
+        std::fs::write(&path, "\n").unwrap();
+        assert!(batches(&config, 256).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+//! Loading dense matrices from weight files
+//!
+//! Lookup-table models and post-pooling projections ship their weights as a
+//! single 2D matrix, either in a NumPy `.npy` file or as a named tensor in a
+//! `.safetensors` file. Half-precision and f64 data is converted to f32.
+//! `to_npy` writes arrays back out, for debug dumps.
+
+use crate::models::{EmbeddingError, EmbeddingResult};
+use std::path::Path;
+
+/// A dense row-major f32 matrix
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    /// Number of rows
+    pub rows: usize,
+    /// Number of columns
+    pub cols: usize,
+    /// Row-major data (`rows * cols` values)
+    pub data: Vec<f32>,
+}
+
+impl Matrix {
+    /// Create a matrix, checking that the data matches the shape
+    pub fn new(rows: usize, cols: usize, data: Vec<f32>) -> EmbeddingResult<Self> {
+        if data.len() != rows * cols {
+            return Err(EmbeddingError::ModelLoadFailed {
+                error: format!("Matrix data has {} values, expected {}x{}", data.len(), rows, cols),
+            });
+        }
+        Ok(Self { rows, cols, data })
+    }
+
+    /// Borrow one row
+    pub fn row(&self, idx: usize) -> &[f32] {
+        &self.data[idx * self.cols..(idx + 1) * self.cols]
+    }
+}
+
+/// Load a 2D matrix from a `.npy` or `.safetensors` file
+///
+/// # Arguments
+/// * `path` - Path to the weights file; the format is chosen by extension
+/// * `tensor_name` - Tensor to read from a safetensors file. When `None`, the
+///   file must contain exactly one tensor. Ignored for `.npy` files.
+pub fn load_matrix<P: AsRef<Path>>(path: P, tensor_name: Option<&str>) -> EmbeddingResult<Matrix> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).map_err(|e| EmbeddingError::ModelLoadFailed {
+        error: format!("Failed to read weights file {}: {}", path.display(), e),
+    })?;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("npy") => parse_npy(&bytes),
+        Some("safetensors") => parse_safetensors(&bytes, tensor_name),
+        _ => Err(EmbeddingError::ModelLoadFailed {
+            error: format!("Unsupported weights file {} (expected .npy or .safetensors)", path.display()),
+        }),
+    }
+}
+
+/// Parse a safetensors buffer into a matrix
+pub fn parse_safetensors(bytes: &[u8], tensor_name: Option<&str>) -> EmbeddingResult<Matrix> {
+    use safetensors::SafeTensors;
+
+    let tensors = SafeTensors::deserialize(bytes).map_err(|e| EmbeddingError::ModelLoadFailed {
+        error: format!("Invalid safetensors file: {}", e),
+    })?;
+
+    let name = match tensor_name {
+        Some(name) => name.to_string(),
+        None => {
+            let names = tensors.names();
+            if names.len() != 1 {
+                return Err(EmbeddingError::ModelLoadFailed {
+                    error: format!("Safetensors file has {} tensors; specify which one to load", names.len()),
+                });
+            }
+            names[0].clone()
+        }
+    };
+
+    tensor_matrix(&tensors, &name)
+}
+
+/// Convert one tensor of a deserialized safetensors file into a matrix
+pub fn tensor_matrix(tensors: &safetensors::SafeTensors, name: &str) -> EmbeddingResult<Matrix> {
+    use safetensors::Dtype;
+
+    let tensor = tensors.tensor(name).map_err(|e| EmbeddingError::ModelLoadFailed {
+        error: format!("Tensor '{}' not found: {}", name, e),
+    })?;
+
+    let (rows, cols) = matrix_shape(tensor.shape())?;
+    let data = tensor.data();
+    let values = match tensor.dtype() {
+        Dtype::F32 => decode(data, 4, |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        Dtype::F64 => decode(data, 8, |b| f64::from_le_bytes(b.try_into().unwrap()) as f32),
+        Dtype::F16 => decode(data, 2, |b| half::f16::from_le_bytes([b[0], b[1]]).to_f32()),
+        Dtype::BF16 => decode(data, 2, |b| half::bf16::from_le_bytes([b[0], b[1]]).to_f32()),
+        other => {
+            return Err(EmbeddingError::ModelLoadFailed {
+                error: format!("Unsupported tensor dtype {:?} for '{}'", other, name),
+            });
+        }
+    };
+
+    Matrix::new(rows, cols, values)
+}
+
+/// Parse a NumPy `.npy` buffer into a matrix
+///
+/// Only little-endian float arrays in C order are supported, which is what
+/// `np.save` produces for the arrays we care about.
+pub fn parse_npy(bytes: &[u8]) -> EmbeddingResult<Matrix> {
+    const MAGIC: &[u8] = b"\x93NUMPY";
+
+    let invalid = |message: &str| EmbeddingError::ModelLoadFailed {
+        error: format!("Invalid .npy file: {}", message),
+    };
+
+    if bytes.len() < 10 || &bytes[..6] != MAGIC {
+        return Err(invalid("missing magic bytes"));
+    }
+
+    // Version 1.0 uses a u16 header length, 2.0 and 3.0 use u32
+    let (header_len, header_start) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize, 12),
+        _ => return Err(invalid("unsupported format version")),
+    };
+    let data_start = header_start + header_len;
+    if bytes.len() < data_start {
+        return Err(invalid("truncated header"));
+    }
+    let header = std::str::from_utf8(&bytes[header_start..data_start]).map_err(|_| invalid("header is not UTF-8"))?;
+
+    if header_value(header, "fortran_order").map(|v| v.starts_with("True")).unwrap_or(false) {
+        return Err(invalid("Fortran-ordered arrays are not supported"));
+    }
+
+    let descr = header_value(header, "descr")
+        .and_then(|v| v.split('\'').nth(1))
+        .ok_or_else(|| invalid("missing descr"))?;
+
+    let shape_str = header_value(header, "shape")
+        .and_then(|v| v.strip_prefix('('))
+        .and_then(|v| v.split(')').next())
+        .ok_or_else(|| invalid("missing shape"))?;
+    let shape = shape_str
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<usize>().map_err(|_| invalid("bad shape")))
+        .collect::<EmbeddingResult<Vec<_>>>()?;
+    let (rows, cols) = matrix_shape(&shape)?;
+
+    let data = &bytes[data_start..];
+    let values = match descr {
+        "<f4" => decode(data, 4, |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        "<f8" => decode(data, 8, |b| f64::from_le_bytes(b.try_into().unwrap()) as f32),
+        "<f2" => decode(data, 2, |b| half::f16::from_le_bytes([b[0], b[1]]).to_f32()),
+        other => {
+            return Err(EmbeddingError::ModelLoadFailed {
+                error: format!("Unsupported .npy dtype '{}'", other),
+            });
+        }
+    };
+
+    Matrix::new(rows, cols, values)
+}
+
+/// Encode a little-endian f32 array as a NumPy `.npy` (version 1.0) buffer
+pub fn to_npy(shape: &[usize], values: &[f32]) -> Vec<u8> {
+    let shape = match shape {
+        [len] => format!("({},)", len),
+        dims => format!("({})", dims.iter().map(|dim| dim.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+    // The header is padded with spaces so the data starts on a 64-byte boundary
+    let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {}, }}", shape);
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for value in values {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+/// Find the text following `'key':` in a .npy header dict
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("'{}':", key);
+    header.find(&pattern).map(|pos| header[pos + pattern.len()..].trim_start())
+}
+
+fn matrix_shape(shape: &[usize]) -> EmbeddingResult<(usize, usize)> {
+    match shape {
+        [rows, cols] => Ok((*rows, *cols)),
+        [len] => Ok((1, *len)),
+        _ => Err(EmbeddingError::ModelLoadFailed {
+            error: format!("Expected a 1D or 2D tensor, got shape {:?}", shape),
+        }),
+    }
+}
+
+fn decode(data: &[u8], width: usize, convert: impl Fn(&[u8]) -> f32) -> Vec<f32> {
+    data.chunks_exact(width).map(convert).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_npy() {
+        let matrix = parse_npy(&to_npy(&[2, 3], &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0])).unwrap();
+        assert_eq!((matrix.rows, matrix.cols), (2, 3));
+        assert_eq!(matrix.row(1), &[4.0, 5.0, 6.0]);
+
+        let vector = parse_npy(&to_npy(&[3], &[1.0, 2.0, 3.0])).unwrap();
+        assert_eq!((vector.rows, vector.cols), (1, 3));
+
+        assert!(parse_npy(b"not a numpy file").is_err());
+    }
+
+    #[test]
+    fn test_parse_safetensors() {
+        let data: Vec<u8> = [1.0f32, 2.0, 3.0, 4.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let view = safetensors::tensor::TensorView::new(safetensors::Dtype::F32, vec![2, 2], &data).unwrap();
+        let bytes = safetensors::serialize([("embeddings", view)], &None).unwrap();
+
+        let matrix = parse_safetensors(&bytes, Some("embeddings")).unwrap();
+        assert_eq!(matrix.row(0), &[1.0, 2.0]);
+        assert_eq!(parse_safetensors(&bytes, None).unwrap(), matrix);
+        assert!(parse_safetensors(&bytes, Some("missing")).is_err());
+    }
+}
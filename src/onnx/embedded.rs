@@ -0,0 +1,38 @@
+//! # Embedded Model Assets
+//!
+//! With the `embedded-model` feature the build script compiles one model's
+//! ONNX file, tokenizer.json and configuration into the binary (see build.rs
+//! for how the model is selected). The engine then loads the session and
+//! tokenizer from memory, so a single executable can be deployed without any
+//! model files next to it.
+
+use crate::models::config::ModelConfig;
+use crate::models::{EmbeddingError, EmbeddingResult};
+use std::path::PathBuf;
+
+include!(concat!(env!("OUT_DIR"), "/embedded_model.rs"));
+
+/// Configuration of the embedded model
+pub fn model_config() -> EmbeddingResult<ModelConfig> {
+    Ok(toml::from_str(MODEL_CONFIG)?)
+}
+
+/// Bytes of the embedded asset behind an `embedded://` path
+pub fn asset(path: &str) -> EmbeddingResult<&'static [u8]> {
+    match path {
+        MODEL_PATH => Ok(MODEL_ONNX),
+        TOKENIZER_PATH => Ok(TOKENIZER_JSON),
+        other => Err(EmbeddingError::ConfigError {
+            message: format!("Unknown embedded asset '{}'", other),
+        }),
+    }
+}
+
+/// Directory of the running executable, used instead of `current_dir()` to
+/// resolve the ONNX Runtime library of an embedded model
+pub fn executable_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
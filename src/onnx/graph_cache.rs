@@ -0,0 +1,324 @@
+//! # Optimized Graph Cache
+//!
+//! ONNX Runtime re-runs graph optimization every time a session is created,
+//! which dominates cold starts for large models. This cache writes the
+//! optimized graph out on first load so later starts can load it directly.
+//!
+//! Entries are keyed by a checksum of the model files plus a runtime
+//! fingerprint (ONNX Runtime build and the session options that shape the
+//! optimized graph). A changed model, runtime upgrade, different device or
+//! thread count therefore misses the cache instead of loading a mismatched
+//! graph.
+//!
+//! Checksums are recorded next to the cache entries together with each
+//! file's size and timestamps, so multi-gigabyte models are only read again
+//! when their metadata changes. The change time is part of that metadata, so
+//! a model copied over in place with its modification time preserved is
+//! still hashed again.
+
+use crate::models::hashing::{fnv1a_64, fnv1a_64_extend, FNV1A_64_OFFSET_BASIS};
+use crate::models::EmbeddingError;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// Optimized graphs bigger than this keep their initializers in a side file
+const EXTERNAL_INITIALIZERS_MIN_BYTES: &str = "1024";
+
+/// Files changed this close to being hashed are hashed again on the next load
+const RACY_WINDOW_NANOS: u64 = 1_000_000_000;
+
+/// Cache entry for one model under one runtime fingerprint
+#[derive(Debug, Clone)]
+pub struct OptimizedGraphCache {
+    dir: PathBuf,
+    /// `<file stem>-<hash of the model path>`, shared by all entries of this model
+    prefix: String,
+    /// Hex key of this entry
+    key: String,
+}
+
+impl OptimizedGraphCache {
+    /// Locate the cache entry for a model
+    ///
+    /// # Arguments
+    /// * `dir` - Cache directory, created if missing
+    /// * `model_files` - The model file followed by its external-data files
+    /// * `fingerprint` - Runtime build and session options
+    pub fn new(dir: &str, model_files: &[PathBuf], fingerprint: &str) -> Result<Self, EmbeddingError> {
+        let model_path = model_files.first().ok_or_else(|| EmbeddingError::ConfigError {
+            message: "Optimized graph cache needs a model file".to_string(),
+        })?;
+
+        std::fs::create_dir_all(dir).map_err(|e| EmbeddingError::ModelLoadFailed {
+            error: format!("Failed to create optimized graph cache {}: {}", dir, e),
+        })?;
+
+        let canonical = model_path.canonicalize().unwrap_or_else(|_| model_path.clone());
+        let stem = model_path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let prefix = format!("{}-{:08x}", stem, fnv1a_64(canonical.to_string_lossy().as_bytes()) as u32);
+
+        let index_path = PathBuf::from(dir).join(format!("{}.checksums.json", prefix));
+        let recorded = read_checksums(&index_path);
+        let checksums = model_files
+            .iter()
+            .map(|path| file_checksum(&recorded, path))
+            .collect::<Result<Vec<_>, _>>()?;
+        if checksums != recorded {
+            write_checksums(&index_path, &checksums);
+        }
+
+        let mut key = FNV1A_64_OFFSET_BASIS;
+        for file in &checksums {
+            key = fnv1a_64_extend(key, &file.checksum.to_le_bytes());
+        }
+        key = fnv1a_64_extend(key, fingerprint.as_bytes());
+
+        Ok(Self {
+            dir: PathBuf::from(dir),
+            prefix,
+            key: format!("{:016x}", key),
+        })
+    }
+
+    /// Cache key of this entry
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Path of the optimized graph
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(format!("{}-{}.onnx", self.prefix, self.key))
+    }
+
+    /// File name of the optimized graph's external initializers, relative to the graph
+    pub fn external_data_name(&self) -> String {
+        format!("{}-{}.onnx.data", self.prefix, self.key)
+    }
+
+    /// Where ONNX Runtime writes the graph before it is published with `commit`
+    pub fn pending_path(&self) -> PathBuf {
+        self.dir.join(format!("{}-{}.onnx.tmp-{}", self.prefix, self.key, std::process::id()))
+    }
+
+    /// Session config entries that make ONNX Runtime store initializers in a
+    /// side file, needed when the optimized graph exceeds the protobuf limit
+    pub fn external_initializer_entries(&self) -> [(&'static str, String); 2] {
+        [
+            ("session.optimized_model_external_initializers_file_name", self.external_data_name()),
+            ("session.optimized_model_external_initializers_min_size_in_bytes", EXTERNAL_INITIALIZERS_MIN_BYTES.to_string()),
+        ]
+    }
+
+    /// Publish the pending graph and remove entries of this model with other keys
+    pub fn commit(&self) {
+        if let Err(e) = std::fs::rename(self.pending_path(), self.path()) {
+            warn!("Failed to store optimized graph {}: {}", self.path().display(), e);
+            return;
+        }
+        debug!("Stored optimized graph {}", self.path().display());
+
+        let current = format!("{}-{}.", self.prefix, self.key);
+        let stale_prefix = format!("{}-", self.prefix);
+        if let Ok(entries) = std::fs::read_dir(&self.dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                // Another process may still be writing its own pending file
+                if name.starts_with(&stale_prefix) && !name.starts_with(&current) && !name.contains(".tmp-") {
+                    debug!("Removing stale optimized graph {}", name);
+                    let _ = std::fs::remove_file(entry.path());
+                }
+            }
+        }
+    }
+
+    /// Drop this entry after it failed to load
+    pub fn discard(&self) {
+        let _ = std::fs::remove_file(self.path());
+        let _ = std::fs::remove_file(self.dir.join(self.external_data_name()));
+    }
+
+    /// Remove a pending graph left behind by a failed load
+    pub fn abandon(&self) {
+        let _ = std::fs::remove_file(self.pending_path());
+    }
+}
+
+/// Recorded content checksum of one model file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FileChecksum {
+    /// Canonical path of the file
+    path: String,
+    size: u64,
+    /// Modification time, in nanoseconds since the Unix epoch
+    modified_nanos: u64,
+    /// Status change time (0 where the platform has none)
+    changed_nanos: u64,
+    /// When the checksum was computed
+    recorded_nanos: u64,
+    checksum: u64,
+}
+
+impl FileChecksum {
+    /// Whether this record still holds for a file with `current`'s metadata
+    ///
+    /// As with git's racily clean index entries, a file changed within
+    /// `RACY_WINDOW_NANOS` of being hashed may have been rewritten again
+    /// without its timestamps moving, so it is not trusted.
+    fn describes(&self, current: &FileChecksum) -> bool {
+        self.path == current.path
+            && self.size == current.size
+            && self.modified_nanos == current.modified_nanos
+            && self.changed_nanos == current.changed_nanos
+            && self.modified_nanos.max(self.changed_nanos).saturating_add(RACY_WINDOW_NANOS) <= self.recorded_nanos
+    }
+}
+
+/// Checksum of a file, reusing the recorded one while the file's metadata is unchanged
+fn file_checksum(recorded: &[FileChecksum], path: &Path) -> Result<FileChecksum, EmbeddingError> {
+    let metadata = std::fs::metadata(path).map_err(|e| EmbeddingError::ModelLoadFailed {
+        error: format!("Failed to read metadata of {}: {}", path.display(), e),
+    })?;
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    let mut current = FileChecksum {
+        path: canonical.to_string_lossy().to_string(),
+        size: metadata.len(),
+        modified_nanos: metadata.modified().map(nanos_since_epoch).unwrap_or_default(),
+        changed_nanos: changed_nanos(&metadata),
+        recorded_nanos: 0,
+        checksum: 0,
+    };
+    if let Some(previous) = recorded.iter().find(|previous| previous.describes(&current)) {
+        return Ok(previous.clone());
+    }
+
+    debug!("Computing checksum of {}", path.display());
+    current.recorded_nanos = nanos_since_epoch(SystemTime::now());
+    current.checksum = checksum_file(path)?;
+    Ok(current)
+}
+
+/// FNV-1a checksum of a file's contents, read in chunks
+fn checksum_file(path: &Path) -> Result<u64, EmbeddingError> {
+    let read_error = |e: std::io::Error| EmbeddingError::ModelLoadFailed {
+        error: format!("Failed to checksum {}: {}", path.display(), e),
+    };
+
+    let mut file = std::fs::File::open(path).map_err(read_error)?;
+    let mut buffer = vec![0u8; 1 << 20];
+    let mut hash = FNV1A_64_OFFSET_BASIS;
+    loop {
+        let read = file.read(&mut buffer).map_err(read_error)?;
+        if read == 0 {
+            return Ok(hash);
+        }
+        hash = fnv1a_64_extend(hash, &buffer[..read]);
+    }
+}
+
+fn nanos_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|since_epoch| since_epoch.as_nanos() as u64).unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn changed_nanos(metadata: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.ctime().max(0) as u64).saturating_mul(1_000_000_000).saturating_add(metadata.ctime_nsec().max(0) as u64)
+}
+
+#[cfg(not(unix))]
+fn changed_nanos(_metadata: &std::fs::Metadata) -> u64 {
+    0
+}
+
+/// Read the recorded checksums of a model, empty if missing or unreadable
+fn read_checksums(path: &Path) -> Vec<FileChecksum> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Record checksums for the next load; failing only costs a checksum pass later
+fn write_checksums(path: &Path, checksums: &[FileChecksum]) {
+    let pending = path.with_extension(format!("json.tmp-{}", std::process::id()));
+    let written = serde_json::to_vec_pretty(checksums)
+        .map_err(std::io::Error::other)
+        .and_then(|bytes| std::fs::write(&pending, bytes))
+        .and_then(|()| std::fs::rename(&pending, path));
+    if let Err(e) = written {
+        warn!("Failed to record model checksums in {}: {}", path.display(), e);
+        let _ = std::fs::remove_file(&pending);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_keys_and_commit() {
+        let root = std::env::temp_dir().join(format!("onnx-graph-cache-{}", std::process::id()));
+        let cache_dir = root.join("cache");
+        std::fs::create_dir_all(&root).unwrap();
+        let model = root.join("model.onnx");
+        std::fs::write(&model, b"graph v1").unwrap();
+        let cache_dir = cache_dir.to_string_lossy().to_string();
+
+        let first = OptimizedGraphCache::new(&cache_dir, std::slice::from_ref(&model), "ort 1.22|cpu").unwrap();
+        assert_eq!(first.key(), OptimizedGraphCache::new(&cache_dir, std::slice::from_ref(&model), "ort 1.22|cpu").unwrap().key());
+        assert_ne!(first.key(), OptimizedGraphCache::new(&cache_dir, std::slice::from_ref(&model), "ort 1.22|cuda").unwrap().key());
+
+        std::fs::write(first.pending_path(), b"optimized").unwrap();
+        first.commit();
+        assert!(first.path().is_file());
+        assert!(!first.pending_path().exists());
+
+        // A new model version replaces the old entry
+        std::fs::write(&model, b"graph v2, re-exported").unwrap();
+        let second = OptimizedGraphCache::new(&cache_dir, std::slice::from_ref(&model), "ort 1.22|cpu").unwrap();
+        assert_ne!(first.key(), second.key());
+        std::fs::write(second.pending_path(), b"optimized").unwrap();
+        second.commit();
+        assert!(second.path().is_file());
+        assert!(!first.path().exists());
+
+        // Same size, modification time restored (`cp -p`, `rsync -a`): the contents still decide
+        let modified = std::fs::metadata(&model).unwrap().modified().unwrap();
+        std::fs::write(&model, b"graph v3, re-exported").unwrap();
+        std::fs::File::options().write(true).open(&model).unwrap().set_modified(modified).unwrap();
+        let third = OptimizedGraphCache::new(&cache_dir, std::slice::from_ref(&model), "ort 1.22|cpu").unwrap();
+        assert_ne!(second.key(), third.key());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_recorded_checksums_are_reused() {
+        let root = std::env::temp_dir().join(format!("onnx-graph-checksums-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let model = root.join("model.onnx");
+        std::fs::write(&model, b"graph").unwrap();
+
+        let fresh = file_checksum(&[], &model).unwrap();
+        assert_eq!(fresh.checksum, fnv1a_64(b"graph"));
+        // Hashed right after being written, so the file may still change under the same timestamps
+        let racy = FileChecksum { checksum: 7, ..fresh.clone() };
+        assert_eq!(file_checksum(std::slice::from_ref(&racy), &model).unwrap().checksum, fresh.checksum);
+
+        let settled = FileChecksum {
+            recorded_nanos: fresh.modified_nanos.max(fresh.changed_nanos) + RACY_WINDOW_NANOS,
+            checksum: 7,
+            ..fresh.clone()
+        };
+        assert_eq!(file_checksum(std::slice::from_ref(&settled), &model).unwrap(), settled);
+
+        std::fs::write(&model, b"other").unwrap();
+        assert_eq!(file_checksum(std::slice::from_ref(&settled), &model).unwrap().checksum, fnv1a_64(b"other"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -2,5 +2,11 @@
 //!
 //! This module provides ONNX-based embedding functionality
 
+#[cfg(feature = "embedded-model")]
+pub mod embedded;
+pub mod graph_cache;
 pub mod onnx_engine;
-pub use onnx_engine::{OnnxEmbeddingEngine, OnnxConfig};
+pub use onnx_engine::{ModelLoadMode, OnnxEmbeddingEngine, OnnxConfig};
+
+/// Path prefix for model assets compiled into the binary
+pub const EMBEDDED_PREFIX: &str = "embedded://";
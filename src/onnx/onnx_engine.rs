@@ -22,12 +22,59 @@
 //! - 384-dimensional embeddings from all-MiniLM-L6-v2
 //! - Async/await support for non-blocking operations
 
+use crate::models::intermediates::Intermediates;
+use crate::models::memory;
+use crate::models::model::{LoadReport, LoadedFile};
+use crate::models::precision::Precision;
+use crate::models::pooling::{self, PoolingMode};
 use crate::models::EmbeddingError;
-use ndarray::{ArrayViewD};
-use ort::session::{Session, builder::GraphOptimizationLevel};
-use ort::value::Tensor;
+use crate::models::tokenizer_loader::load_tokenizer;
+use crate::models::weights::Matrix;
+use crate::onnx::graph_cache::OptimizedGraphCache;
+use memmap2::Mmap;
+use ort::session::{Session, builder::{GraphOptimizationLevel, SessionBuilder}};
+use ort::tensor::TensorElementType;
+use ort::value::{DynValue, TensorRef};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 use tokenizers::Tokenizer;
-use tracing::{debug, info, instrument};
+use tracing::{debug, info, instrument, warn};
+
+/// How model bytes are handed to ONNX Runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelLoadMode {
+    /// ONNX Runtime reads the model, and any external data next to it, from disk
+    File,
+    /// Model and external data are memory-mapped and passed as buffers, so
+    /// sessions of the same model share page-cache pages
+    Mmap,
+    /// Model and external data are read into memory and passed as buffers
+    Memory,
+}
+
+impl ModelLoadMode {
+    /// Parse the `load_mode` value from embeddingmodels.toml
+    pub fn from_config(load_mode: &str) -> Result<Self, EmbeddingError> {
+        match load_mode.to_lowercase().as_str() {
+            "file" => Ok(ModelLoadMode::File),
+            "mmap" => Ok(ModelLoadMode::Mmap),
+            "memory" => Ok(ModelLoadMode::Memory),
+            other => Err(EmbeddingError::ConfigError {
+                message: format!("Unknown ONNX load_mode '{}' (expected file, mmap or memory)", other),
+            }),
+        }
+    }
+
+    /// Name used in configuration and load reports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelLoadMode::File => "file",
+            ModelLoadMode::Mmap => "mmap",
+            ModelLoadMode::Memory => "memory",
+        }
+    }
+}
 
 /// Configuration for ONNX Runtime
 #[derive(Debug, Clone)]
@@ -42,6 +89,14 @@ pub struct OnnxConfig {
     pub enable_memory_optimization: bool,
     /// Thread pool size for inference
     pub thread_pool_size: usize,
+    /// How the model bytes are handed to ONNX Runtime
+    pub load_mode: ModelLoadMode,
+    /// External-data file names (relative to the model file); empty to auto-detect
+    pub external_data: Vec<String>,
+    /// Directory for optimized graphs reused across restarts
+    pub optimized_cache_dir: Option<String>,
+    /// Single-threaded sequential execution with deterministic kernels
+    pub deterministic: bool,
 }
 
 impl Default for OnnxConfig {
@@ -49,10 +104,8 @@ impl Default for OnnxConfig {
         // Use absolute path to the ONNX runtime library
         let (runtime_dir, lib_name) = if cfg!(target_os = "windows") {
             ("onnxruntime-win-x64-1.22.0", "onnxruntime.dll")
-        } else if cfg!(target_os = "linux") {
-            ("onnxruntime-linux-x64-1.22.0", "libonnxruntime.so")
         } else {
-            ("onnxruntime-linux-x64-1.22.0", "libonnxruntime.so") // fallback to linux
+            ("onnxruntime-linux-x64-1.22.0", "libonnxruntime.so") // linux, and fallback for other targets
         };
 
         let runtime_path = std::env::current_dir()
@@ -67,6 +120,10 @@ impl Default for OnnxConfig {
             enable_profiling: false,
             enable_memory_optimization: true,
             thread_pool_size: 4,
+            load_mode: ModelLoadMode::File,
+            external_data: Vec::new(),
+            optimized_cache_dir: None,
+            deterministic: false,
         }
     }
 }
@@ -74,16 +131,20 @@ impl Default for OnnxConfig {
 impl OnnxConfig {
     /// Create a new OnnxConfig with a custom runtime path
     pub fn with_runtime_path(runtime_path: &str) -> Self {
+        let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+        Self::with_runtime_path_in(&base_dir, runtime_path)
+    }
+
+    /// Create a new OnnxConfig with a runtime path resolved against `base_dir`
+    /// rather than the current working directory
+    pub fn with_runtime_path_in(base_dir: &std::path::Path, runtime_path: &str) -> Self {
         let lib_name = if cfg!(target_os = "windows") {
             "onnxruntime.dll"
-        } else if cfg!(target_os = "linux") {
-            "libonnxruntime.so"
         } else {
-            "libonnxruntime.so" // fallback
+            "libonnxruntime.so" // linux, and fallback for other targets
         };
 
-        let runtime_path_full = std::env::current_dir()
-            .unwrap_or_else(|_| std::path::PathBuf::from("."))
+        let runtime_path_full = base_dir
             .join(runtime_path)
             .join("lib")
             .join(lib_name);
@@ -94,10 +155,39 @@ impl OnnxConfig {
             enable_profiling: false,
             enable_memory_optimization: true,
             thread_pool_size: 4,
+            load_mode: ModelLoadMode::File,
+            external_data: Vec::new(),
+            optimized_cache_dir: None,
+            deterministic: false,
         }
     }
 }
 
+/// Model inputs as the i64 tensors ONNX Runtime expects
+///
+/// Kept on the engine and refilled for every sequence, so steady-state
+/// requests do not allocate input buffers.
+#[cfg(feature = "onnx")]
+#[derive(Debug, Default)]
+struct InputBuffers {
+    input_ids: Vec<i64>,
+    attention_mask: Vec<i64>,
+    token_type_ids: Vec<i64>,
+}
+
+#[cfg(feature = "onnx")]
+impl InputBuffers {
+    /// Replace the contents with one sequence (token type ids are all zero)
+    fn fill(&mut self, input_ids: &[u32], attention_mask: &[u32]) {
+        self.input_ids.clear();
+        self.input_ids.extend(input_ids.iter().map(|&x| x as i64));
+        self.attention_mask.clear();
+        self.attention_mask.extend(attention_mask.iter().map(|&x| x as i64));
+        self.token_type_ids.clear();
+        self.token_type_ids.resize(input_ids.len(), 0);
+    }
+}
+
 /// ONNX-based embedding engine for generating text embeddings
 #[cfg(feature = "onnx")]
 #[derive(Debug)]
@@ -114,6 +204,15 @@ pub struct OnnxEmbeddingEngine {
     batch_size: usize,
     /// Maximum sequence length
     max_seq_length: usize,
+    /// Files read and memory used while loading
+    load_report: LoadReport,
+    /// Element type of the hidden-state output
+    precision: Precision,
+    /// Input tensors' backing buffers, reused across requests
+    inputs: InputBuffers,
+    /// Memory-mapped external data backing the session's initializers;
+    /// declared after `session` so the mappings outlive it
+    _mapped_files: Vec<Mmap>,
 }
 
 #[cfg(feature = "onnx")]
@@ -149,50 +248,248 @@ impl OnnxEmbeddingEngine {
         batch_size: usize,
         max_seq_length: usize,
     ) -> Result<Self, EmbeddingError> {
-        info!("Initializing ONNX embedding engine with model: {} (ONNX Runtime v{})", 
-              model_path, onnx_config.version);
+        info!("Initializing ONNX embedding engine with model: {} (ONNX Runtime v{}, {} load)", 
+              model_path, onnx_config.version, onnx_config.load_mode.as_str());
 
-        // Set environment variable for ONNX Runtime library path if specified
-        if !onnx_config.library_path.is_empty() {
-            unsafe {
-                std::env::set_var("ORT_DYLIB_PATH", &onnx_config.library_path);
-            }
-            debug!("Set ORT_DYLIB_PATH to: {}", onnx_config.library_path);
+        let started = Instant::now();
+        let rss_before_bytes = memory::resident_memory_bytes();
+
+        let external_data = external_data_files(model_path, &onnx_config.external_data)?;
+        let mut files = vec![loaded_file(Path::new(model_path))?];
+        for (_, path) in &external_data {
+            files.push(loaded_file(path)?);
         }
 
-        // Configure session based on device
-        let session = if device == "cuda" {
-            Session::builder()?
-                .with_optimization_level(GraphOptimizationLevel::Level3)?
-                .with_intra_threads(1)? // CUDA doesn't benefit from multiple threads
-                .commit_from_file(model_path)
-        } else {
-            Session::builder()?
-                .with_optimization_level(GraphOptimizationLevel::Level3)?
-                .with_intra_threads(onnx_config.thread_pool_size)?
-                .commit_from_file(model_path)
+        let graph_cache = match &onnx_config.optimized_cache_dir {
+            Some(dir) => {
+                Self::configure_runtime(onnx_config);
+                let model_files: Vec<PathBuf> = std::iter::once(PathBuf::from(model_path))
+                    .chain(external_data.iter().map(|(_, path)| path.clone()))
+                    .collect();
+                let fingerprint = format!(
+                    "{}|{}|level3|{}|intra_threads={}|deterministic={}",
+                    ort::info(), onnx_config.version, device, Self::intra_threads(onnx_config, device), onnx_config.deterministic
+                );
+                Some(OptimizedGraphCache::new(dir, &model_files, &fingerprint)?)
+            }
+            None => None,
+        };
+
+        let mut cache_outcome = graph_cache.as_ref().map(|_| "miss");
+        let mut session = None;
+        if let Some(cache) = graph_cache.as_ref().filter(|cache| cache.path().is_file()) {
+            // The cached graph is already optimized
+            match Self::session_builder(onnx_config, device, GraphOptimizationLevel::Disable)?
+                .commit_from_file(cache.path())
+            {
+                Ok(cached) => {
+                    info!("Loaded optimized graph from cache: {}", cache.path().display());
+                    cache_outcome = Some("hit");
+                    session = Some(cached);
+                }
+                Err(e) => {
+                    warn!("Cached optimized graph {} could not be loaded, rebuilding: {}", cache.path().display(), e);
+                    cache.discard();
+                    cache_outcome = Some("rebuilt");
+                }
+            }
         }
-        .map_err(|e| EmbeddingError::ModelLoadFailed {
-            error: format!("Failed to load ONNX model: {}", e),
-        })?;
+
+        let mut mapped_files = Vec::new();
+        let session = match session {
+            Some(session) => session,
+            None => {
+                let mut builder = Self::session_builder(onnx_config, device, GraphOptimizationLevel::Level3)?;
+                if let Some(cache) = &graph_cache {
+                    builder = builder.with_optimized_model_path(cache.pending_path())?;
+                    if !external_data.is_empty() {
+                        for (key, value) in cache.external_initializer_entries() {
+                            builder = builder.with_config_entry(key, value)?;
+                        }
+                    }
+                }
+
+                let session = match onnx_config.load_mode {
+                    // ONNX Runtime resolves external data relative to the model file itself
+                    ModelLoadMode::File => builder.commit_from_file(model_path),
+                    ModelLoadMode::Memory => {
+                        for (name, path) in &external_data {
+                            builder = builder.with_external_initializer_file_in_memory(name, Cow::Owned(read_file(path)?))?;
+                        }
+                        builder.commit_from_memory(&read_file(Path::new(model_path))?)
+                    }
+                    ModelLoadMode::Mmap => {
+                        for (name, path) in &external_data {
+                            let mapped = map_file(path)?;
+                            // SAFETY: the mapping is stored in `_mapped_files`, which is dropped
+                            // after the session, and mmap'd memory does not move
+                            let bytes: &'static [u8] = unsafe { std::slice::from_raw_parts(mapped.as_ptr(), mapped.len()) };
+                            builder = builder.with_external_initializer_file_in_memory(name, Cow::Borrowed(bytes))?;
+                            mapped_files.push(mapped);
+                        }
+                        // The graph itself is only read while the session is created
+                        builder.commit_from_memory(&map_file(Path::new(model_path))?)
+                    }
+                };
+
+                match (session, &graph_cache) {
+                    (Ok(session), Some(cache)) => {
+                        cache.commit();
+                        Ok(session)
+                    }
+                    (Err(e), Some(cache)) => {
+                        cache.abandon();
+                        Err(e)
+                    }
+                    (session, None) => session,
+                }
+                .map_err(|e| EmbeddingError::ModelLoadFailed {
+                    error: format!("Failed to load ONNX model: {}", e),
+                })?
+            }
+        };
 
         // Load tokenizer
-        let tokenizer = Tokenizer::from_file(tokenizer_path)
+        let tokenizer = load_tokenizer(tokenizer_path)?;
+
+        let load_report = LoadReport {
+            load_mode: onnx_config.load_mode.as_str().to_string(),
+            total_bytes: files.iter().map(|file| file.bytes).sum(),
+            files,
+            rss_before_bytes,
+            rss_after_bytes: memory::resident_memory_bytes(),
+            load_time_ms: started.elapsed().as_millis() as u64,
+            optimized_graph_cache: cache_outcome.map(str::to_string),
+        };
+        log_load_report(&load_report);
+
+        info!("ONNX embedding engine initialized successfully with {} threads", onnx_config.thread_pool_size);
+        Ok(Self {
+            precision: output_precision(&session),
+            session,
+            tokenizer,
+            _config: onnx_config.clone(),
+            device: device.to_string(),
+            batch_size,
+            max_seq_length,
+            load_report,
+            inputs: InputBuffers::default(),
+            _mapped_files: mapped_files,
+        })
+    }
+
+    /// Create an ONNX embedding engine from in-memory model and tokenizer bytes
+    ///
+    /// Used for model assets compiled into the binary, so nothing is read from disk.
+    ///
+    /// # Arguments
+    /// * `model_bytes` - Contents of the ONNX model file
+    /// * `tokenizer_json` - Contents of tokenizer.json
+    pub fn from_memory(
+        model_bytes: &[u8],
+        tokenizer_json: &[u8],
+        onnx_config: &OnnxConfig,
+        device: &str,
+        batch_size: usize,
+        max_seq_length: usize,
+    ) -> Result<Self, EmbeddingError> {
+        info!("Initializing ONNX embedding engine from {} bytes of in-memory model (ONNX Runtime v{})",
+              model_bytes.len(), onnx_config.version);
+
+        let started = Instant::now();
+        let rss_before_bytes = memory::resident_memory_bytes();
+
+        let session = Self::session_builder(onnx_config, device, GraphOptimizationLevel::Level3)?
+            .commit_from_memory(model_bytes)
+            .map_err(|e| EmbeddingError::ModelLoadFailed {
+                error: format!("Failed to load in-memory ONNX model: {}", e),
+            })?;
+
+        let tokenizer = Tokenizer::from_bytes(tokenizer_json)
             .map_err(|e| EmbeddingError::ModelLoadFailed {
-                error: format!("Failed to load tokenizer: {}", e),
+                error: format!("Failed to load in-memory tokenizer: {}", e),
             })?;
 
+        let load_report = LoadReport {
+            load_mode: "embedded".to_string(),
+            files: Vec::new(),
+            total_bytes: (model_bytes.len() + tokenizer_json.len()) as u64,
+            rss_before_bytes,
+            rss_after_bytes: memory::resident_memory_bytes(),
+            load_time_ms: started.elapsed().as_millis() as u64,
+            optimized_graph_cache: None,
+        };
+        log_load_report(&load_report);
+
         info!("ONNX embedding engine initialized successfully with {} threads", onnx_config.thread_pool_size);
         Ok(Self {
+            precision: output_precision(&session),
             session,
             tokenizer,
             _config: onnx_config.clone(),
             device: device.to_string(),
             batch_size,
             max_seq_length,
+            load_report,
+            inputs: InputBuffers::default(),
+            _mapped_files: Vec::new(),
         })
     }
 
+    /// Element type of the model's hidden-state output
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// Files read and memory used while loading the model
+    pub fn load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
+
+    /// Point ONNX Runtime at its library before its first use
+    fn configure_runtime(onnx_config: &OnnxConfig) {
+        // Set environment variable for ONNX Runtime library path if specified
+        if !onnx_config.library_path.is_empty() {
+            unsafe {
+                std::env::set_var("ORT_DYLIB_PATH", &onnx_config.library_path);
+            }
+            debug!("Set ORT_DYLIB_PATH to: {}", onnx_config.library_path);
+        }
+    }
+
+    /// Point ONNX Runtime at its library and configure a session builder for `device`
+    fn session_builder(
+        onnx_config: &OnnxConfig,
+        device: &str,
+        optimization_level: GraphOptimizationLevel,
+    ) -> Result<SessionBuilder, EmbeddingError> {
+        Self::configure_runtime(onnx_config);
+
+        // Configure session based on device
+        let builder = Session::builder()?
+            .with_optimization_level(optimization_level)?;
+        let builder = builder.with_intra_threads(Self::intra_threads(onnx_config, device))?;
+        if onnx_config.deterministic {
+            // Reductions split across threads sum in varying order; one thread and
+            // deterministic kernels give bit-identical outputs run to run
+            return Ok(builder
+                .with_intra_threads(1)?
+                .with_inter_threads(1)?
+                .with_parallel_execution(false)?
+                .with_deterministic_compute(true)?);
+        }
+        Ok(builder)
+    }
+
+    /// Intra-op threads of a session, part of the optimized graph cache key
+    fn intra_threads(onnx_config: &OnnxConfig, device: &str) -> usize {
+        if device == "cuda" || onnx_config.deterministic {
+            1 // CUDA doesn't benefit from multiple threads
+        } else {
+            onnx_config.thread_pool_size
+        }
+    }
+
     /// Generate embeddings for a batch of texts
     ///
     /// # Arguments
@@ -229,138 +526,107 @@ impl OnnxEmbeddingEngine {
                     error: format!("Tokenization failed: {}", e),
                 })?;
 
-            let input_ids = encoding.get_ids();
-            let attention_mask = encoding.get_attention_mask();
-
-            // Convert to tensors using v2.x API - Create 2D tensors [batch_size=1, seq_len]
-            let input_ids_vec: Vec<i64> = input_ids.iter().map(|&x| x as i64).collect();
-            let attention_mask_vec: Vec<i64> = attention_mask.iter().map(|&x| x as i64).collect();
-
-            // Create token_type_ids (all zeros for single sequence)
-            let token_type_ids_vec: Vec<i64> = vec![0i64; input_ids_vec.len()];
-
-            // Create ONNX tensors with proper 2D shape [1, seq_len] for single sequence
-            let input_ids_tensor = Tensor::from_array(([1i64, input_ids_vec.len() as i64], input_ids_vec))
-                .map_err(|e| EmbeddingError::EmbeddingFailed {
-                    error: format!("Failed to create input_ids tensor: {}", e),
-                })?;
-
-            let attention_mask_tensor = Tensor::from_array(([1i64, attention_mask_vec.len() as i64], attention_mask_vec))
-                .map_err(|e| EmbeddingError::EmbeddingFailed {
-                    error: format!("Failed to create attention_mask tensor: {}", e),
-                })?;
-
-            let token_type_ids_tensor = Tensor::from_array(([1i64, token_type_ids_vec.len() as i64], token_type_ids_vec))
-                .map_err(|e| EmbeddingError::EmbeddingFailed {
-                    error: format!("Failed to create token_type_ids tensor: {}", e),
-                })?;
-
-            // Run inference using ort v2.x API
-            let outputs = self.session.run(vec![
-                ("input_ids", input_ids_tensor),
-                ("attention_mask", attention_mask_tensor),
-                ("token_type_ids", token_type_ids_tensor),
-            ])
-            .map_err(|e| EmbeddingError::EmbeddingFailed {
-                error: format!("ONNX inference failed: {}", e),
-            })?;
-
-            // Extract the output tensor (last_hidden_state) using v2.x API
-            let (shape, data) = outputs["last_hidden_state"]
-                .try_extract_tensor::<f32>()
-                .map_err(|e| EmbeddingError::EmbeddingFailed {
-                    error: format!("Failed to extract output tensor: {}", e),
-                })?;
-
-            // Convert to ndarray for processing
-            let dims: Vec<usize> = shape.iter().map(|&x| x as usize).collect();
-            let output_array = ndarray::ArrayView::from_shape(dims.as_slice(), data)
-                .map_err(|e| EmbeddingError::EmbeddingFailed {
-                    error: format!("Failed to create output array view: {:?}", e),
-                })?;
-
-            // Apply mean pooling over the sequence dimension (excluding padding tokens)
-            let embedding = Self::mean_pooling(&output_array, attention_mask)?;
-
-            // Normalize the embedding (L2 normalization)
-            let normalized_embedding = Self::normalize_embedding(&embedding)?;
-
-            embeddings.push(normalized_embedding);
+            embeddings.push(self.embed_encoded(encoding.get_ids(), encoding.get_attention_mask(), None)?);
         }
 
         debug!("Successfully generated {} embeddings", embeddings.len());
         Ok(embeddings)
     }
 
-    /// Apply mean pooling to the token embeddings
+    /// Generate an embedding for already tokenized input, bypassing the tokenizer
     ///
-    /// # Arguments
-    /// * `output_tensor` - Output tensor from the model [batch_size, seq_len, hidden_size]
-    /// * `attention_mask` - Attention mask indicating which tokens are real (1) vs padding (0)
-    ///
-    /// # Returns
-    /// Mean-pooled embedding vector
-    fn mean_pooling(output_tensor: &ArrayViewD<f32>, attention_mask: &[u32]) -> Result<Vec<f32>, EmbeddingError> {
-        let shape = output_tensor.shape();
-        if shape.len() != 3 {
-            return Err(EmbeddingError::EmbeddingFailed {
-                error: format!("Expected 3D output tensor, got {}D", shape.len()),
-            });
-        }
-
-        let seq_len = shape[1];
-        let hidden_size = shape[2];
-
-        if attention_mask.len() != seq_len {
-            return Err(EmbeddingError::EmbeddingFailed {
-                error: format!("Attention mask length {} doesn't match sequence length {}", attention_mask.len(), seq_len),
+    /// The ids must come from this model's vocabulary, including any special
+    /// tokens the tokenizer would add.
+    pub async fn embed_token_ids(&mut self, input_ids: Vec<u32>) -> Result<Vec<f32>, EmbeddingError> {
+        if input_ids.is_empty() {
+            return Err(EmbeddingError::InvalidInput {
+                message: "Cannot embed empty input_ids".to_string(),
             });
         }
 
-        let mut pooled = vec![0.0f32; hidden_size];
-        let mut valid_tokens = 0;
+        let attention_mask = vec![1u32; input_ids.len()];
+        self.embed_encoded(&input_ids, &attention_mask, None)
+    }
 
-        // Sum embeddings for valid tokens (attention_mask == 1)
-        for seq_idx in 0..seq_len {
-            if attention_mask[seq_idx] == 1 {
-                for hidden_idx in 0..hidden_size {
-                    pooled[hidden_idx] += output_tensor[[0, seq_idx, hidden_idx]];
-                }
-                valid_tokens += 1;
-            }
-        }
+    /// Generate an embedding for one text, capturing the token ids, attention
+    /// mask, raw `last_hidden_state` and pooled vector along the way
+    pub async fn trace_text(&mut self, text: &str) -> Result<Intermediates, EmbeddingError> {
+        let encoding = self.tokenizer.encode(text, true)
+            .map_err(|e| EmbeddingError::EmbeddingFailed {
+                error: format!("Tokenization failed: {}", e),
+            })?;
 
-        if valid_tokens == 0 {
-            return Err(EmbeddingError::EmbeddingFailed {
-                error: "No valid tokens found in attention mask".to_string(),
-            });
-        }
+        let mut intermediates = Intermediates {
+            text: text.to_string(),
+            token_ids: Some(encoding.get_ids().to_vec()),
+            attention_mask: Some(encoding.get_attention_mask().to_vec()),
+            ..Default::default()
+        };
+        intermediates.embedding = self.embed_encoded(
+            encoding.get_ids(),
+            encoding.get_attention_mask(),
+            Some(&mut intermediates),
+        )?;
+        Ok(intermediates)
+    }
 
-        // Compute mean
-        for val in &mut pooled {
-            *val /= valid_tokens as f32;
-        }
+    /// Number of entries in the tokenizer vocabulary, including added tokens
+    pub fn vocab_size(&self) -> usize {
+        self.tokenizer.get_vocab_size(true)
+    }
 
-        Ok(pooled)
+    /// Tokenizer used to prepare model inputs
+    pub fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
     }
 
-    /// Normalize embedding using L2 normalization
-    ///
-    /// # Arguments
-    /// * `embedding` - Input embedding vector
+    /// Run one tokenized sequence through the model and pool it
     ///
-    /// # Returns
-    /// L2-normalized embedding vector
-    fn normalize_embedding(embedding: &[f32]) -> Result<Vec<f32>, EmbeddingError> {
-        let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    /// With `capture`, the raw hidden state and pooled vector are recorded too.
+    fn embed_encoded(
+        &mut self,
+        input_ids: &[u32],
+        attention_mask: &[u32],
+        capture: Option<&mut Intermediates>,
+    ) -> Result<Vec<f32>, EmbeddingError> {
+        self.inputs.fill(input_ids, attention_mask);
+
+        // Tensors borrow the reused buffers instead of taking fresh allocations
+        let input_ids_tensor = input_tensor(&self.inputs.input_ids, "input_ids")?;
+        let attention_mask_tensor = input_tensor(&self.inputs.attention_mask, "attention_mask")?;
+        let token_type_ids_tensor = input_tensor(&self.inputs.token_type_ids, "token_type_ids")?;
+
+        // Run inference using ort v2.x API
+        let outputs = self.session.run(vec![
+            ("input_ids", input_ids_tensor),
+            ("attention_mask", attention_mask_tensor),
+            ("token_type_ids", token_type_ids_tensor),
+        ])
+        .map_err(|e| EmbeddingError::EmbeddingFailed {
+            error: format!("ONNX inference failed: {}", e),
+        })?;
 
-        if norm == 0.0 {
-            return Err(EmbeddingError::EmbeddingFailed {
-                error: "Cannot normalize zero vector".to_string(),
-            });
+        // Extract the output tensor (last_hidden_state) using v2.x API, widening half precision to f32;
+        // f32 outputs are borrowed from the session's output buffer
+        let (dims, data) = extract_f32(&outputs["last_hidden_state"])?;
+
+        // Apply mean pooling over the sequence dimension (excluding padding tokens)
+        let mut embedding = pooling::pool_batch(PoolingMode::Mean, &data, &dims, attention_mask)?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        if let Some(capture) = capture {
+            let hidden_size = dims.last().copied().unwrap_or(0);
+            let token_count = data.len().checked_div(hidden_size).unwrap_or(0);
+            capture.hidden_state = Some(Matrix::new(token_count, hidden_size, data.to_vec())?);
+            capture.pooled = Some(embedding.clone());
         }
 
-        Ok(embedding.iter().map(|x| x / norm).collect())
+        // Normalize the embedding (L2 normalization)
+        pooling::normalize_in_place(&mut embedding)?;
+
+        Ok(embedding)
     }
 
     /// Get information about the loaded model
@@ -449,6 +715,122 @@ impl OnnxEmbeddingEngine {
     }
 }
 
+/// Element type of the hidden-state output, f32 unless the export is half precision
+fn output_precision(session: &Session) -> Precision {
+    let output = session
+        .outputs
+        .iter()
+        .find(|output| output.name == "last_hidden_state")
+        .or_else(|| session.outputs.first());
+
+    match output.and_then(|output| output.output_type.tensor_type()) {
+        Some(TensorElementType::Float16) => Precision::F16,
+        Some(TensorElementType::Bfloat16) => Precision::Bf16,
+        _ => Precision::F32,
+    }
+}
+
+/// Borrow one input buffer as a [1, seq_len] tensor
+fn input_tensor<'a>(data: &'a [i64], name: &str) -> Result<TensorRef<'a, i64>, EmbeddingError> {
+    TensorRef::from_array_view(([1usize, data.len()], data)).map_err(|e| EmbeddingError::EmbeddingFailed {
+        error: format!("Failed to create {} tensor: {}", name, e),
+    })
+}
+
+/// Extract a float tensor as f32, converting fp16/bf16 outputs
+fn extract_f32(value: &DynValue) -> Result<(Vec<usize>, Cow<'_, [f32]>), EmbeddingError> {
+    let extract_error = |e: ort::Error| EmbeddingError::EmbeddingFailed {
+        error: format!("Failed to extract output tensor: {}", e),
+    };
+    let dims = |shape: &[i64]| shape.iter().map(|&x| x as usize).collect::<Vec<usize>>();
+
+    match value.dtype().tensor_type() {
+        Some(TensorElementType::Float16) => {
+            let (shape, data) = value.try_extract_tensor::<half::f16>().map_err(extract_error)?;
+            Ok((dims(shape), Cow::Owned(data.iter().map(|v| v.to_f32()).collect())))
+        }
+        Some(TensorElementType::Bfloat16) => {
+            let (shape, data) = value.try_extract_tensor::<half::bf16>().map_err(extract_error)?;
+            Ok((dims(shape), Cow::Owned(data.iter().map(|v| v.to_f32()).collect())))
+        }
+        _ => {
+            let (shape, data) = value.try_extract_tensor::<f32>().map_err(extract_error)?;
+            Ok((dims(shape), Cow::Borrowed(data)))
+        }
+    }
+}
+
+/// External-data files of a model as (name referenced by the graph, path on disk)
+///
+/// Models above the 2 GB protobuf limit keep their weights in side files, by
+/// convention `<model>.onnx_data` (Optimum) or `<model>.onnx.data` (torch.onnx).
+fn external_data_files(model_path: &str, configured: &[String]) -> Result<Vec<(String, PathBuf)>, EmbeddingError> {
+    let model_path = Path::new(model_path);
+    let model_dir = model_path.parent().unwrap_or(Path::new("."));
+
+    if !configured.is_empty() {
+        return configured
+            .iter()
+            .map(|name| {
+                let path = model_dir.join(name);
+                if path.is_file() {
+                    Ok((name.clone(), path))
+                } else {
+                    Err(EmbeddingError::ModelLoadFailed {
+                        error: format!("External data file not found: {}", path.display()),
+                    })
+                }
+            })
+            .collect();
+    }
+
+    let file_name = model_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    Ok([format!("{}_data", file_name), format!("{}.data", file_name)]
+        .into_iter()
+        .map(|name| {
+            let path = model_dir.join(&name);
+            (name, path)
+        })
+        .filter(|(_, path)| path.is_file())
+        .collect())
+}
+
+fn loaded_file(path: &Path) -> Result<LoadedFile, EmbeddingError> {
+    let metadata = std::fs::metadata(path).map_err(|e| EmbeddingError::ModelLoadFailed {
+        error: format!("Failed to read {}: {}", path.display(), e),
+    })?;
+    Ok(LoadedFile {
+        path: path.display().to_string(),
+        bytes: metadata.len(),
+    })
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, EmbeddingError> {
+    std::fs::read(path).map_err(|e| EmbeddingError::ModelLoadFailed {
+        error: format!("Failed to read {}: {}", path.display(), e),
+    })
+}
+
+fn map_file(path: &Path) -> Result<Mmap, EmbeddingError> {
+    let file = std::fs::File::open(path).map_err(|e| EmbeddingError::ModelLoadFailed {
+        error: format!("Failed to open {}: {}", path.display(), e),
+    })?;
+    // SAFETY: model files are treated as read-only while the server runs
+    unsafe { Mmap::map(&file) }.map_err(|e| EmbeddingError::ModelLoadFailed {
+        error: format!("Failed to memory-map {}: {}", path.display(), e),
+    })
+}
+
+fn log_load_report(report: &LoadReport) {
+    let to_mb = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+    info!("Model loaded in {} ms ({} mode{}): {:.1} MB in {} file(s), RSS {} -> {}",
+          report.load_time_ms, report.load_mode,
+          report.optimized_graph_cache.as_ref().map(|outcome| format!(", optimized graph cache {}", outcome)).unwrap_or_default(),
+          to_mb(report.total_bytes), report.files.len(),
+          report.rss_before_bytes.map(|b| format!("{:.1} MB", to_mb(b))).unwrap_or_else(|| "n/a".to_string()),
+          report.rss_after_bytes.map(|b| format!("{:.1} MB", to_mb(b))).unwrap_or_else(|| "n/a".to_string()));
+}
+
 /// Information about the loaded ONNX model
 #[cfg(feature = "onnx")]
 #[derive(Debug, Clone)]
@@ -459,4 +841,34 @@ pub struct ModelInfo {
     pub output_names: Vec<String>,
     /// Dimension of output embeddings
     pub embedding_dimension: usize,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_mode_from_config() {
+        assert_eq!(ModelLoadMode::from_config("MMAP").unwrap(), ModelLoadMode::Mmap);
+        assert_eq!(ModelLoadMode::from_config("file").unwrap().as_str(), "file");
+        assert!(ModelLoadMode::from_config("lazy").is_err());
+    }
+
+    #[test]
+    fn test_external_data_detection() {
+        let dir = std::env::temp_dir().join(format!("onnx-external-data-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let model_path = dir.join("model.onnx");
+        std::fs::write(&model_path, b"graph").unwrap();
+        std::fs::write(dir.join("model.onnx_data"), b"weights").unwrap();
+        let model_path = model_path.to_string_lossy().to_string();
+
+        let detected = external_data_files(&model_path, &[]).unwrap();
+        assert_eq!(detected.len(), 1);
+        assert_eq!(detected[0].0, "model.onnx_data");
+        assert_eq!(loaded_file(&detected[0].1).unwrap().bytes, 7);
+
+        assert!(external_data_files(&model_path, &["weights.bin".to_string()]).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -7,17 +7,20 @@
 //! - Request body: {"text": "...", "chunk_style": "recursive", "chunk_size": 100}
 //! - Response body: {"embedding": [0.1, 0.2, 0.3, ...]}
 
+use crate::models::precision::{self, Precision};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
 use serde::{Deserialize, Serialize};
 
 /// HTTP Embedding Request - HelixDB Format
 /// 
 /// HelixDB sends these fields:
-/// - text: The text to embed (required)
+/// - text: The text to embed (required unless `input_ids` is given)
 /// - chunk_style: Text chunking style (required by HelixDB, set to "recursive")
 /// - chunk_size: Size of text chunks (required by HelixDB, set to 100)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpEmbedRequest {
     /// Text to embed
+    #[serde(default)]
     pub text: String,
     
     /// Chunking style (required by HelixDB)
@@ -31,6 +34,14 @@ pub struct HttpEmbedRequest {
     /// Optional model name (extension for multi-model support)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
+
+    /// Optional packed response precision ("f32", "f16" or "bf16")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision: Option<String>,
+
+    /// Optional pre-tokenized input, used instead of `text`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_ids: Option<Vec<u32>>,
 }
 
 fn default_chunk_style() -> String {
@@ -44,6 +55,16 @@ fn default_chunk_size() -> usize {
 impl HttpEmbedRequest {
     /// Validate the request
     pub fn validate(&self) -> Result<(), String> {
+        if let Some(input_ids) = &self.input_ids {
+            if !self.text.is_empty() {
+                return Err("Specify either text or input_ids, not both".to_string());
+            }
+            if input_ids.is_empty() {
+                return Err("input_ids cannot be empty".to_string());
+            }
+            return Ok(());
+        }
+
         if self.text.is_empty() {
             return Err("Text field cannot be empty".to_string());
         }
@@ -63,14 +84,53 @@ impl HttpEmbedRequest {
 ///
 /// HelixDB expects: {"embedding": [0.1, 0.2, 0.3, ...]}
 /// Note: Uses f64 (double precision) as required by HelixDB
+///
+/// When the request sets `precision`, the vector is sent packed instead:
+/// {"precision": "f16", "embedding_base64": "..."}
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpEmbedResponse {
     /// The embedding vector (f64 precision)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub embedding: Vec<f64>,
-}impl HttpEmbedResponse {
+
+    /// Precision of `embedding_base64`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision: Option<String>,
+
+    /// Base64 of the little-endian packed embedding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_base64: Option<String>,
+
+    /// Output fingerprint of the model that produced the embedding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_fingerprint: Option<String>,
+}
+
+impl HttpEmbedResponse {
     /// Create a new response
     pub fn new(embedding: Vec<f64>) -> Self {
-        Self { embedding }
+        Self {
+            embedding,
+            precision: None,
+            embedding_base64: None,
+            model_fingerprint: None,
+        }
+    }
+
+    /// Create a packed response at the given precision
+    pub fn packed(embedding: &[f32], precision: Precision) -> Self {
+        Self {
+            embedding: Vec::new(),
+            precision: Some(precision.as_str().to_string()),
+            embedding_base64: Some(BASE64_STANDARD.encode(precision::encode(embedding, precision))),
+            model_fingerprint: None,
+        }
+    }
+
+    /// Attach the producing model's output fingerprint
+    pub fn with_fingerprint(mut self, fingerprint: Option<String>) -> Self {
+        self.model_fingerprint = fingerprint;
+        self
     }
 }
 
@@ -147,6 +207,15 @@ impl HttpErrorResponse {
         }
     }
     
+    /// Create model unavailable error, for models that are loading, draining, failed or unloaded
+    pub fn model_unavailable(error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            code: Some("MODEL_UNAVAILABLE".to_string()),
+            details: None,
+        }
+    }
+    
     /// Create internal error
     pub fn internal_error(details: impl Into<String>) -> Self {
         Self {
@@ -189,6 +258,8 @@ mod tests {
             chunk_style: "recursive".to_string(),
             chunk_size: 100,
             model: None,
+            precision: None,
+            input_ids: None,
         };
         assert!(req.validate().is_ok());
 
@@ -198,6 +269,8 @@ mod tests {
             chunk_style: "recursive".to_string(),
             chunk_size: 100,
             model: None,
+            precision: None,
+            input_ids: None,
         };
         assert!(req.validate().is_err());
 
@@ -207,8 +280,18 @@ mod tests {
             chunk_style: "recursive".to_string(),
             chunk_size: 100,
             model: None,
+            precision: None,
+            input_ids: None,
         };
         assert!(req.validate().is_err());
+
+        // Pre-tokenized input replaces text
+        let req: HttpEmbedRequest = serde_json::from_str(r#"{"input_ids": [101, 7592, 102]}"#).unwrap();
+        assert!(req.validate().is_ok());
+        let req: HttpEmbedRequest = serde_json::from_str(r#"{"text": "hi", "input_ids": [101]}"#).unwrap();
+        assert!(req.validate().is_err());
+        let req: HttpEmbedRequest = serde_json::from_str(r#"{"input_ids": []}"#).unwrap();
+        assert!(req.validate().is_err());
     }
 
     #[test]
@@ -216,6 +299,12 @@ mod tests {
         let embedding = vec![0.1f64, 0.2f64, 0.3f64];
         let response = HttpEmbedResponse::new(embedding.clone());
         assert_eq!(response.embedding, embedding);
+
+        let packed = serde_json::to_value(HttpEmbedResponse::packed(&[0.5, -0.25], Precision::Bf16)).unwrap();
+        assert!(packed.get("embedding").is_none());
+        assert_eq!(packed["precision"], "bf16");
+        let bytes = BASE64_STANDARD.decode(packed["embedding_base64"].as_str().unwrap()).unwrap();
+        assert_eq!(precision::decode(&bytes, Precision::Bf16).unwrap(), vec![0.5, -0.25]);
     }
 
     #[test]
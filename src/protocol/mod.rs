@@ -6,7 +6,8 @@
 //! Protocol Format:
 //! - Magic bytes (4): [0x4F, 0x56, 0x4E, 0x54] = "OVNT"
 //! - Version (1): 0x01
-//! - Message type (1): 4 = Data
+//! - Message type (1): 4 = Data (embed), 5 = Tokenize, 6 = Detokenize, 7 = Count tokens,
+//!   8 = Model status
 //! - Length (4): u32 little-endian
 //! - Sender ID (16): UUID
 //! - Target ID option (17): 1 byte tag + 16 bytes UUID
@@ -15,8 +16,11 @@
 
 pub mod http;
 
+use crate::models::lifecycle::ModelStatus;
+use crate::models::precision::{self, Precision};
+use crate::models::EmbeddingError;
 use serde::{Deserialize, Serialize};
-use std::io::{self, Read, Write};
+use std::io;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use uuid::Uuid;
@@ -30,13 +34,33 @@ pub const VERSION: u8 = 0x01;
 /// Message type for data
 pub const MSG_TYPE_DATA: u8 = 4;
 
+/// Message type for tokenize requests and responses
+pub const MSG_TYPE_TOKENIZE: u8 = 5;
+
+/// Message type for detokenize requests and responses
+pub const MSG_TYPE_DETOKENIZE: u8 = 6;
+
+/// Message type for count-tokens requests and responses
+pub const MSG_TYPE_COUNT_TOKENS: u8 = 7;
+
+/// Message type for model status requests and responses
+pub const MSG_TYPE_MODEL_STATUS: u8 = 8;
+
 /// Embedding request message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbedRequest {
-    /// Text to embed
+    /// Text to embed (ignored when `input_ids` is set)
+    #[serde(default)]
     pub text: String,
     /// Optional model name (uses default if None)
     pub model: Option<String>,
+    /// Optional wire precision ("f32", "f16" or "bf16"); when set the server
+    /// answers with a `PackedEmbedResponse`
+    #[serde(default)]
+    pub precision: Option<String>,
+    /// Optional pre-tokenized input, embedded without running the model's tokenizer
+    #[serde(default)]
+    pub input_ids: Option<Vec<u32>>,
 }
 
 /// Embedding response message - SIMPLE MODE
@@ -67,6 +91,143 @@ impl EmbedResponse {
     }
 }
 
+/// Packed embedding response, sent when the request asks for a precision
+///
+/// `data` holds little-endian values of `precision`, so an f16 embedding
+/// costs two bytes per dimension instead of nine for a MessagePack f64.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackedEmbedResponse {
+    /// Precision of the packed values ("f32", "f16" or "bf16")
+    pub precision: String,
+    /// Packed embedding values
+    #[serde(with = "packed_bytes")]
+    pub data: Vec<u8>,
+    /// Output fingerprint of the model that produced the embedding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_fingerprint: Option<String>,
+}
+
+impl PackedEmbedResponse {
+    /// Pack an embedding at the given precision
+    pub fn new(embedding: &[f32], precision: Precision) -> Self {
+        Self {
+            precision: precision.as_str().to_string(),
+            data: precision::encode(embedding, precision),
+            model_fingerprint: None,
+        }
+    }
+
+    /// Attach the producing model's output fingerprint
+    pub fn with_fingerprint(mut self, fingerprint: Option<String>) -> Self {
+        self.model_fingerprint = fingerprint;
+        self
+    }
+
+    /// Unpack the embedding to f32
+    pub fn embedding(&self) -> Result<Vec<f32>, EmbeddingError> {
+        precision::decode(&self.data, Precision::from_config(&self.precision)?)
+    }
+}
+
+/// Serialize `Vec<u8>` as a MessagePack bin rather than an array of integers
+mod packed_bytes {
+    use serde::de::{SeqAccess, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a byte array")
+            }
+
+            fn visit_bytes<E>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+                Ok(bytes.to_vec())
+            }
+
+            fn visit_byte_buf<E>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+                Ok(bytes)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(bytes)
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+/// Tokenize request message, also used as the HTTP `/tokenize` body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenizeRequest {
+    /// Text to tokenize
+    pub text: String,
+    /// Optional model name (uses default if None)
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Add the special tokens the model sees (e.g. [CLS] and [SEP])
+    #[serde(default = "default_true")]
+    pub add_special_tokens: bool,
+}
+
+/// Detokenize request message, also used as the HTTP `/detokenize` body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetokenizeRequest {
+    /// Token ids to decode
+    pub ids: Vec<u32>,
+    /// Optional model name (uses default if None)
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Leave special tokens out of the decoded text
+    #[serde(default = "default_true")]
+    pub skip_special_tokens: bool,
+}
+
+/// Detokenize response message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetokenizeResponse {
+    pub text: String,
+}
+
+/// Count-tokens request message, also used as the HTTP `/count_tokens` body
+pub type CountTokensRequest = TokenizeRequest;
+
+/// Count-tokens response message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountTokensResponse {
+    pub count: usize,
+}
+
+/// Model status request message; without a model, every configured model is reported
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelStatusRequest {
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Model status response message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelStatusResponse {
+    pub models: Vec<ModelStatus>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
 /// Error response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorResponse {
@@ -76,6 +237,7 @@ pub struct ErrorResponse {
 /// Protocol message envelope
 #[derive(Debug)]
 pub struct ProtocolMessage {
+    pub msg_type: u8,
     pub sender_id: Uuid,
     pub target_id: Option<Uuid>,
     pub message_id: Uuid,
@@ -83,9 +245,15 @@ pub struct ProtocolMessage {
 }
 
 impl ProtocolMessage {
-    /// Create a new protocol message
+    /// Create a new data (embed) message
     pub fn new(sender_id: Uuid, target_id: Option<Uuid>, payload: Vec<u8>) -> Self {
+        Self::with_type(MSG_TYPE_DATA, sender_id, target_id, payload)
+    }
+
+    /// Create a new message of the given type
+    pub fn with_type(msg_type: u8, sender_id: Uuid, target_id: Option<Uuid>, payload: Vec<u8>) -> Self {
         Self {
+            msg_type,
             sender_id,
             target_id,
             message_id: Uuid::new_v4(),
@@ -102,7 +270,7 @@ impl ProtocolMessage {
         stream.write_u8(VERSION).await?;
 
         // Message type
-        stream.write_u8(MSG_TYPE_DATA).await?;
+        stream.write_u8(self.msg_type).await?;
 
         // Length (payload size)
         stream.write_u32_le(self.payload.len() as u32).await?;
@@ -150,7 +318,7 @@ impl ProtocolMessage {
         }
 
         // Read message type
-        let _msg_type = stream.read_u8().await?;
+        let msg_type = stream.read_u8().await?;
 
         // Read length
         let length = stream.read_u32_le().await?;
@@ -180,6 +348,7 @@ impl ProtocolMessage {
         stream.read_exact(&mut payload).await?;
 
         Ok(Self {
+            msg_type,
             sender_id,
             target_id,
             message_id,
@@ -208,6 +377,26 @@ pub fn deserialize_response(data: &[u8]) -> Result<EmbedResponse, rmp_serde::dec
     rmp_serde::from_slice(data)
 }
 
+/// Serialize packed response to MessagePack
+pub fn serialize_packed_response(response: &PackedEmbedResponse) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+    rmp_serde::to_vec(response)
+}
+
+/// Deserialize packed response from MessagePack
+pub fn deserialize_packed_response(data: &[u8]) -> Result<PackedEmbedResponse, rmp_serde::decode::Error> {
+    rmp_serde::from_slice(data)
+}
+
+/// Serialize any protocol message (tokenize, detokenize, count tokens) to MessagePack
+pub fn serialize_message<T: Serialize>(message: &T) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+    rmp_serde::to_vec(message)
+}
+
+/// Deserialize any protocol message from MessagePack
+pub fn deserialize_message<T: serde::de::DeserializeOwned>(data: &[u8]) -> Result<T, rmp_serde::decode::Error> {
+    rmp_serde::from_slice(data)
+}
+
 /// Serialize error to MessagePack
 pub fn serialize_error(error: &ErrorResponse) -> Result<Vec<u8>, rmp_serde::encode::Error> {
     rmp_serde::to_vec(error)
@@ -222,6 +411,8 @@ mod tests {
         let request = EmbedRequest {
             text: "Hello, world!".to_string(),
             model: Some("All MiniLM L6 v2".to_string()),
+            precision: None,
+            input_ids: None,
         };
 
         let serialized = serialize_request(&request).unwrap();
@@ -251,4 +442,47 @@ mod tests {
         };
         assert_eq!(resp3.get_embedding(), &embedding);
     }
+
+    #[test]
+    fn test_packed_response_round_trip() {
+        let response = PackedEmbedResponse::new(&[0.5, -0.25], Precision::F16);
+        let serialized = serialize_packed_response(&response).unwrap();
+        // 2 values x 2 bytes, plus the bin and string headers
+        assert!(serialized.len() < 16);
+
+        let deserialized = deserialize_packed_response(&serialized).unwrap();
+        assert_eq!(deserialized.precision, "f16");
+        assert_eq!(deserialized.embedding().unwrap(), vec![0.5, -0.25]);
+    }
+
+    #[test]
+    fn test_packed_response_fingerprint() {
+        // Without a fingerprint the message keeps the two-field layout older clients expect
+        let response = PackedEmbedResponse::new(&[0.5, -0.25], Precision::F16);
+        let serialized = serialize_packed_response(&response).unwrap();
+        assert_eq!(serialized[0], 0x92, "expected a two-element array");
+        assert!(deserialize_packed_response(&serialized).unwrap().model_fingerprint.is_none());
+
+        let response = response.with_fingerprint(Some("abc123".to_string()));
+        let deserialized = deserialize_packed_response(&serialize_packed_response(&response).unwrap()).unwrap();
+        assert_eq!(deserialized.model_fingerprint.as_deref(), Some("abc123"));
+        assert_eq!(deserialized.embedding().unwrap(), vec![0.5, -0.25]);
+    }
+
+    #[test]
+    fn test_tokenize_request_defaults() {
+        // Clients may send just the text
+        let request: TokenizeRequest = deserialize_message(&rmp_serde::to_vec(&("hello",)).unwrap()).unwrap();
+        assert!(request.add_special_tokens);
+        assert!(request.model.is_none());
+    }
+
+    #[test]
+    fn test_request_without_precision_field() {
+        // Requests from clients that predate the precision field
+        let legacy = rmp_serde::to_vec(&("hello", None::<String>)).unwrap();
+        let request = deserialize_request(&legacy).unwrap();
+        assert_eq!(request.text, "hello");
+        assert!(request.precision.is_none());
+    }
 }
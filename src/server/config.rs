@@ -77,7 +77,7 @@ impl Default for ServerConfig {
             },
             embedding: EmbeddingConfig {
                 models_config: "embeddingmodels.toml".to_string(),
-                default_model: "All MiniLM L6 v2".to_string(),
+                default_model: "all-MiniLM-L6-v2".to_string(),
                 max_batch_size: 32,
                 request_timeout_secs: 30,
             },
@@ -12,17 +12,21 @@ use serde_json;
 use tokio::net::TcpSocket;
 use tracing::{debug, error, info};
 
-use crate::models::EmbeddingModelsManager;
+use crate::models::precision::Precision;
+use crate::models::{EmbeddingError, EmbeddingModelsManager};
 use crate::protocol::http::{
     HealthResponse, HttpEmbedRequest, HttpEmbedResponse, HttpErrorResponse,
 };
+use crate::protocol::{
+    CountTokensRequest, CountTokensResponse, DetokenizeRequest, DetokenizeResponse, ModelStatusResponse,
+    TokenizeRequest,
+};
 use crate::server::config::ServerConfig;
 
 /// Shared state for Hyper server
 #[derive(Clone)]
 struct ServerState {
     embedding_manager: Arc<EmbeddingModelsManager>,
-    config: Arc<ServerConfig>,
 }
 
 /// Start ultra-fast Hyper HTTP server
@@ -35,10 +39,7 @@ pub async fn start_hyper_http_server(
     info!("🚀 Starting Ultra-Fast Hyper HTTP Server");
     info!("📡 Binding to {}", bind_address);
     
-    let state = ServerState {
-        embedding_manager,
-        config,
-    };
+    let state = ServerState { embedding_manager };
     
     // Create service factory
     let make_svc = make_service_fn(move |_| {
@@ -81,6 +82,9 @@ pub async fn start_hyper_http_server(
     info!("🔄 HTTP keep-alive enabled");
     info!("📍 Endpoints:");
     info!("   POST /embed      - Generate embeddings (FAST!)");
+    info!("   POST /tokenize   - Tokenize text with a model's tokenizer");
+    info!("   POST /detokenize - Decode token ids back to text");
+    info!("   POST /count_tokens - Count tokens for a model");
     info!("   GET  /health     - Health check");
     info!("   GET  /           - Server info");
     
@@ -107,7 +111,12 @@ async fn handle_request(
     // Fast path routing - no complex middleware
     let response = match (&method, path.as_str()) {
         (&Method::POST, "/embed") => handle_embed(req, state).await,
+        (&Method::POST, "/tokenize") => handle_tokenize(req, state).await,
+        (&Method::POST, "/detokenize") => handle_detokenize(req, state).await,
+        (&Method::POST, "/count_tokens") => handle_count_tokens(req, state).await,
         (&Method::GET, "/health") => handle_health(state).await,
+        (&Method::GET, "/metrics") => handle_metrics(state).await,
+        (&Method::GET, "/models") => handle_models(state).await,
         (&Method::GET, "/") => handle_root(state).await,
         (&Method::OPTIONS, _) => handle_options(),
         _ => handle_not_found(),
@@ -152,13 +161,38 @@ async fn handle_root(state: ServerState) -> Response<Body> {
                 "path": "/embed",
                 "description": "Generate embeddings for text (10x faster than Axum!)"
             },
+            "tokenize": {
+                "method": "POST",
+                "path": "/tokenize",
+                "description": "Tokenize text exactly as the model does"
+            },
+            "detokenize": {
+                "method": "POST",
+                "path": "/detokenize",
+                "description": "Decode token ids back to text"
+            },
+            "count_tokens": {
+                "method": "POST",
+                "path": "/count_tokens",
+                "description": "Count tokens for a model"
+            },
             "health": {
                 "method": "GET",
                 "path": "/health",
                 "description": "Health check endpoint"
+            },
+            "metrics": {
+                "method": "GET",
+                "path": "/metrics",
+                "description": "Model metrics, including memory residency and evictions"
+            },
+            "models": {
+                "method": "GET",
+                "path": "/models",
+                "description": "Lifecycle state and transition history of every model"
             }
         },
-        "model": state.config.embedding.default_model
+        "model": state.embedding_manager.config().global.default_model
     });
     
     Response::builder()
@@ -172,12 +206,10 @@ async fn handle_root(state: ServerState) -> Response<Body> {
 async fn handle_health(state: ServerState) -> Response<Body> {
     debug!("🏥 Health check requested");
     
-    let model_name = &state.config.embedding.default_model;
-    
-    // Quick health check with test embedding
-    match state.embedding_manager.embed_text("test").await {
-        Ok(embedding) => {
-            let response = HealthResponse::healthy(model_name, embedding.len());
+    // Ready once the default model is loaded and warmed up; no test embedding needed
+    match state.embedding_manager.ready_default_model().await {
+        Ok(info) => {
+            let response = HealthResponse::healthy(&info.name, info.dimension);
             Response::builder()
                 .status(StatusCode::OK)
                 .header("content-type", "application/json")
@@ -196,9 +228,32 @@ async fn handle_health(state: ServerState) -> Response<Body> {
     }
 }
 
+/// Metrics endpoint; not found unless `monitoring.metrics_enabled` is set
+async fn handle_metrics(state: ServerState) -> Response<Body> {
+    match state.embedding_manager.get_metrics().await {
+        Some(metrics) => Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&metrics).unwrap()))
+            .unwrap(),
+        None => handle_not_found(),
+    }
+}
+
+/// Model lifecycle endpoint
+async fn handle_models(state: ServerState) -> Response<Body> {
+    json_response(&ModelStatusResponse {
+        models: state.embedding_manager.model_statuses().await,
+    })
+}
+
 /// Embedding endpoint - THE FAST PATH
 async fn handle_embed(req: Request<Body>, state: ServerState) -> Response<Body> {
     let start_time = std::time::Instant::now();
+    let save_intermediates = req.headers()
+        .get("x-save-intermediates")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value == "1" || value.eq_ignore_ascii_case("true"));
     
     // Parse JSON body - direct, no extractors
     let read_start = std::time::Instant::now();
@@ -229,7 +284,9 @@ async fn handle_embed(req: Request<Body>, state: ServerState) -> Response<Body>
     // Validate request
     let validate_start = std::time::Instant::now();
     if let Err(msg) = request.validate() {
-        let error = if request.text.is_empty() {
+        let error = if request.input_ids.is_some() {
+            HttpErrorResponse::new(msg)
+        } else if request.text.is_empty() {
             HttpErrorResponse::empty_text()
         } else if request.text.len() > 8192 {
             HttpErrorResponse::text_too_long(request.text.len())
@@ -238,33 +295,61 @@ async fn handle_embed(req: Request<Body>, state: ServerState) -> Response<Body>
         };
         return error_response(StatusCode::BAD_REQUEST, error);
     }
+    let packed_precision = match request.precision.as_deref().map(Precision::from_config).transpose() {
+        Ok(precision) => precision,
+        Err(e) => {
+            return error_response(StatusCode::BAD_REQUEST, HttpErrorResponse::new(e.to_string()));
+        }
+    };
     info!("⏱️  Validation took: {:?}", validate_start.elapsed());
     
     // Generate embedding - the actual fast part!
     let embed_start = std::time::Instant::now();
-    let embedding_result = if let Some(model_name) = &request.model {
-        state.embedding_manager.embed_text_with_model(&request.text, model_name).await
-    } else {
-        state.embedding_manager.embed_text(&request.text).await
+    let embedding_result = match (&request.input_ids, &request.model) {
+        (Some(input_ids), Some(model_name)) => {
+            state.embedding_manager.embed_token_ids_with_model(input_ids, model_name).await
+        }
+        (Some(input_ids), None) => state.embedding_manager.embed_token_ids(input_ids).await,
+        (None, model_name) if save_intermediates => {
+            state.embedding_manager.embed_text_saving_intermediates(&request.text, model_name.as_deref()).await
+        }
+        (None, Some(model_name)) => state.embedding_manager.embed_text_with_model(&request.text, model_name).await,
+        (None, None) => state.embedding_manager.embed_text(&request.text).await,
     };
     info!("⏱️  Embedding generation took: {:?}", embed_start.elapsed());
     
     match embedding_result {
         Ok(embedding) => {
             let serialize_start = std::time::Instant::now();
-            // Convert f32 embedding to f64 as required by HelixDB
-            let embedding_f64: Vec<f64> = embedding.into_iter().map(|x| x as f64).collect();
-            let response = HttpEmbedResponse::new(embedding_f64);
+            let response = match packed_precision {
+                Some(precision) => HttpEmbedResponse::packed(&embedding, precision),
+                None => {
+                    // Convert f32 embedding to f64 as required by HelixDB
+                    let embedding_f64: Vec<f64> = embedding.into_iter().map(|x| x as f64).collect();
+                    HttpEmbedResponse::new(embedding_f64)
+                }
+            };
+            let fingerprint = state.embedding_manager.model_fingerprint(request.model.as_deref()).await;
+            let response = response.with_fingerprint(fingerprint.clone());
             let json_body = serde_json::to_string(&response).unwrap();
             info!("⏱️  JSON serialization took: {:?}", serialize_start.elapsed());
             info!("⏱️  TOTAL request took: {:?}", start_time.elapsed());
 
-            Response::builder()
+            let mut builder = Response::builder()
                 .status(StatusCode::OK)
-                .header("content-type", "application/json")
-                .body(Body::from(json_body))
-                .unwrap()
+                .header("content-type", "application/json");
+            if let Some(fingerprint) = fingerprint {
+                builder = builder.header("x-model-fingerprint", fingerprint);
+            }
+            builder.body(Body::from(json_body)).unwrap()
+        }
+        Err(EmbeddingError::InvalidInput { message }) => {
+            error_response(StatusCode::BAD_REQUEST, HttpErrorResponse::new(message))
         }
+        Err(e @ EmbeddingError::ModelQuarantined { .. }) => {
+            error_response(StatusCode::SERVICE_UNAVAILABLE, HttpErrorResponse::internal_error(e.to_string()))
+        }
+        Err(e @ EmbeddingError::ModelUnavailable { .. }) => model_error_response(e),
         Err(e) => {
             error!("❌ Embedding generation failed: {:?}", e);
             error_response(
@@ -275,6 +360,94 @@ async fn handle_embed(req: Request<Body>, state: ServerState) -> Response<Body>
     }
 }
 
+/// Tokenize endpoint
+async fn handle_tokenize(req: Request<Body>, state: ServerState) -> Response<Body> {
+    let request: TokenizeRequest = match read_json(req).await {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+
+    match state.embedding_manager
+        .tokenize(&request.text, request.add_special_tokens, request.model.as_deref())
+        .await
+    {
+        Ok(tokenized) => json_response(&tokenized),
+        Err(e) => model_error_response(e),
+    }
+}
+
+/// Detokenize endpoint
+async fn handle_detokenize(req: Request<Body>, state: ServerState) -> Response<Body> {
+    let request: DetokenizeRequest = match read_json(req).await {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+
+    match state.embedding_manager
+        .decode(&request.ids, request.skip_special_tokens, request.model.as_deref())
+        .await
+    {
+        Ok(text) => json_response(&DetokenizeResponse { text }),
+        Err(e) => model_error_response(e),
+    }
+}
+
+/// Count-tokens endpoint
+async fn handle_count_tokens(req: Request<Body>, state: ServerState) -> Response<Body> {
+    let request: CountTokensRequest = match read_json(req).await {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+
+    match state.embedding_manager
+        .count_tokens(&request.text, request.add_special_tokens, request.model.as_deref())
+        .await
+    {
+        Ok(count) => json_response(&CountTokensResponse { count }),
+        Err(e) => model_error_response(e),
+    }
+}
+
+/// Read and parse a JSON request body, or produce the 400 response to send instead
+async fn read_json<T: serde::de::DeserializeOwned>(req: Request<Body>) -> Result<T, Response<Body>> {
+    let body_bytes = to_bytes(req.into_body()).await.map_err(|_| {
+        error_response(StatusCode::BAD_REQUEST, HttpErrorResponse::new("Failed to read request body"))
+    })?;
+
+    serde_json::from_slice(&body_bytes)
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, HttpErrorResponse::new(format!("Invalid JSON: {}", e))))
+}
+
+/// Map model lookup and input failures to HTTP statuses
+fn model_error_response(error: EmbeddingError) -> Response<Body> {
+    match error {
+        EmbeddingError::InvalidInput { message } => {
+            error_response(StatusCode::BAD_REQUEST, HttpErrorResponse::new(message))
+        }
+        EmbeddingError::ModelNotFound { model_name } => error_response(
+            StatusCode::NOT_FOUND,
+            HttpErrorResponse::new(format!("Model '{}' not found", model_name)),
+        ),
+        ref e @ EmbeddingError::ModelUnavailable { retry_after_secs, .. } => {
+            let mut response = error_response(StatusCode::SERVICE_UNAVAILABLE, HttpErrorResponse::model_unavailable(e.to_string()));
+            if let Some(secs) = retry_after_secs {
+                response.headers_mut().insert("retry-after", secs.into());
+            }
+            response
+        }
+        e => error_response(StatusCode::INTERNAL_SERVER_ERROR, HttpErrorResponse::internal_error(e.to_string())),
+    }
+}
+
+/// Helper to create JSON success responses
+fn json_response<T: serde::Serialize>(body: &T) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::to_string(body).unwrap()))
+        .unwrap()
+}
+
 /// Helper to create error responses
 fn error_response(status: StatusCode, error: HttpErrorResponse) -> Response<Body> {
     Response::builder()
@@ -287,6 +460,7 @@ fn error_response(status: StatusCode, error: HttpErrorResponse) -> Response<Body
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::model::mock::{mock_config, mock_model_toml};
 
     #[test]
     fn test_error_responses() {
@@ -294,4 +468,160 @@ mod tests {
         let response = error_response(StatusCode::BAD_REQUEST, error);
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
+
+    async fn mock_state(mock_settings: &str) -> ServerState {
+        mock_state_with("", mock_settings).await
+    }
+
+    async fn mock_state_with(model_settings: &str, mock_settings: &str) -> ServerState {
+        let models_config = mock_config("mock-model", &[mock_model_toml(
+            "mock-model",
+            &format!("max_sequence_length = 256\nembedding_dimension = 8\n{}\n[models.mock-model.mock]\n{}", model_settings, mock_settings),
+        )]);
+        let mut manager = EmbeddingModelsManager::from_config(models_config).unwrap();
+        manager.initialize().await.unwrap();
+
+        ServerState {
+            embedding_manager: Arc::new(manager),
+        }
+    }
+
+    fn embed_request(body: &str) -> Request<Body> {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/embed")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_embed_endpoint_with_mock_model() {
+        let state = mock_state("").await;
+
+        let response = handle_request(embed_request(r#"{"text": "hello"}"#), state.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let header = response.headers()["x-model-fingerprint"].to_str().unwrap().to_string();
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let parsed: HttpEmbedResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(parsed.embedding.len(), 8);
+        assert_eq!(parsed.model_fingerprint.as_deref(), Some(header.as_str()));
+        assert_eq!(state.embedding_manager.model_fingerprint(None).await, Some(header));
+
+        let response = handle_request(embed_request(r#"{"text": ""}"#), state).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_embed_endpoint_packed_precision() {
+        let state = mock_state("").await;
+
+        let response = handle_request(embed_request(r#"{"text": "hello", "precision": "f16"}"#), state.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let parsed: HttpEmbedResponse = serde_json::from_slice(&body).unwrap();
+        assert!(parsed.embedding.is_empty());
+        assert_eq!(parsed.precision.as_deref(), Some("f16"));
+
+        let response = handle_request(embed_request(r#"{"text": "hello", "precision": "f8"}"#), state).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_embed_endpoint_with_input_ids() {
+        let state = mock_state("").await;
+
+        let response = handle_request(embed_request(r#"{"input_ids": [101, 7592, 102]}"#), state.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let parsed: HttpEmbedResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(parsed.embedding.len(), 8);
+
+        let too_long = format!(r#"{{"input_ids": {:?}}}"#, vec![1u32; 300]);
+        let response = handle_request(embed_request(&too_long), state).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_embed_endpoint_reports_model_failures() {
+        let state = mock_state("failure_rate = 1.0").await;
+
+        let response = handle_request(embed_request(r#"{"text": "hello"}"#), state).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_tokenizer_endpoints() {
+        let tokenizer_path = std::env::temp_dir().join(format!("hyper-tokenizer-{}.json", std::process::id()));
+        crate::models::tokenization::tests::test_tokenizer().save(&tokenizer_path, false).unwrap();
+        let state = mock_state_with(&format!("tokenizer_path = {:?}", tokenizer_path.to_str().unwrap()), "").await;
+
+        let request = |uri: &str, body: &str| {
+            Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = handle_request(request("/tokenize", r#"{"text": "hello world"}"#), state.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let tokenized: crate::models::TokenizedText = serde_json::from_slice(&body).unwrap();
+        assert_eq!(tokenized.ids, vec![1, 3, 4, 2]);
+
+        let response = handle_request(request("/count_tokens", r#"{"text": "hello world", "add_special_tokens": false}"#), state.clone()).await.unwrap();
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(serde_json::from_slice::<CountTokensResponse>(&body).unwrap().count, 2);
+
+        let response = handle_request(request("/detokenize", r#"{"ids": [1, 3, 4, 2]}"#), state.clone()).await.unwrap();
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(serde_json::from_slice::<DetokenizeResponse>(&body).unwrap().text, "hello world");
+
+        let response = handle_request(request("/detokenize", r#"{"ids": [99]}"#), state.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = handle_request(request("/tokenize", r#"{"text": "hi", "model": "missing"}"#), state).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        std::fs::remove_file(tokenizer_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_warmed_default_model() {
+        let state = mock_state("").await;
+        let info = state.embedding_manager.get_model_info("mock-model").await.unwrap();
+        assert!(info.warmup.is_some());
+
+        let request = Request::builder().method(Method::GET).uri("/health").body(Body::empty()).unwrap();
+        let response = handle_request(request, state).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let health: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(health["embedding_dimension"], 8);
+    }
+
+    #[tokio::test]
+    async fn test_models_endpoint_and_unavailable_model() {
+        let state = mock_state("").await;
+        state.embedding_manager.registry().unload_model("mock-model").await.unwrap();
+
+        let request = Request::builder().method(Method::GET).uri("/models").body(Body::empty()).unwrap();
+        let response = handle_request(request, state.clone()).await.unwrap();
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let statuses: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(statuses["models"][0]["state"], "unloaded");
+        assert_eq!(statuses["models"][0]["history"].as_array().unwrap().len(), 6);
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/embed")
+            .body(Body::from(r#"{"text": "hello"}"#))
+            .unwrap();
+        let response = handle_request(request, state).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let error: HttpErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.code.as_deref(), Some("MODEL_UNAVAILABLE"));
+        assert!(error.error.contains("'mock-model' is unloaded"));
+    }
 }
@@ -8,10 +8,15 @@ use tokio::sync::Semaphore;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use crate::models::EmbeddingModelsManager;
+use crate::models::precision::Precision;
+use crate::models::{EmbeddingError, EmbeddingModelsManager};
 use crate::protocol::{
-    deserialize_request, serialize_error, serialize_response, EmbedRequest, EmbedResponse,
-    ErrorResponse, ProtocolMessage,
+    deserialize_message, deserialize_request, serialize_error, serialize_message,
+    serialize_packed_response, serialize_response, CountTokensRequest, CountTokensResponse,
+    DetokenizeRequest, DetokenizeResponse, EmbedRequest, EmbedResponse, ErrorResponse,
+    ModelStatusRequest, ModelStatusResponse, PackedEmbedResponse, ProtocolMessage,
+    TokenizeRequest, MSG_TYPE_COUNT_TOKENS, MSG_TYPE_DETOKENIZE, MSG_TYPE_MODEL_STATUS,
+    MSG_TYPE_TOKENIZE,
 };
 use crate::server::config::ServerConfig;
 
@@ -34,6 +39,14 @@ impl EmbeddingServer {
         embedding_manager.initialize().await?;
         info!("✅ Embedding models loaded successfully");
 
+        Self::with_manager(config, Arc::new(embedding_manager)).await
+    }
+
+    /// Create a server around an already initialized embedding manager
+    pub async fn with_manager(
+        config: ServerConfig,
+        embedding_manager: Arc<EmbeddingModelsManager>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         // Create TCP listener
         let listener = TcpListener::bind(&config.network.bind_address).await?;
         info!("📡 Server bound to {}", config.network.bind_address);
@@ -44,15 +57,23 @@ impl EmbeddingServer {
         let server_id = Uuid::new_v4();
         info!("🆔 Server ID: {}", server_id);
 
+        // On-demand models are unloaded again once idle
+        EmbeddingModelsManager::spawn_idle_unloader(&embedding_manager).await;
+
         Ok(Self {
             config: Arc::new(config),
-            embedding_manager: Arc::new(embedding_manager),
+            embedding_manager,
             listener: Some(listener),
             connection_limiter,
             server_id,
         })
     }
 
+    /// Get the address the TCP listener is bound to (None once started)
+    pub fn local_addr(&self) -> Option<std::net::SocketAddr> {
+        self.listener.as_ref().and_then(|listener| listener.local_addr().ok())
+    }
+
     /// Get a reference to the embedding manager (for HTTP server sharing)
     pub fn get_embedding_manager(&self) -> Arc<EmbeddingModelsManager> {
         Arc::clone(&self.embedding_manager)
@@ -139,6 +160,36 @@ impl EmbeddingServer {
                 addr, request_msg.message_id
             );
 
+            // Tokenizer messages are answered with the same message type
+            if matches!(
+                request_msg.msg_type,
+                MSG_TYPE_TOKENIZE | MSG_TYPE_DETOKENIZE | MSG_TYPE_COUNT_TOKENS
+            ) {
+                let response_payload =
+                    Self::handle_tokenizer_message(&request_msg, &embedding_manager).await?;
+                let response_msg = ProtocolMessage::with_type(
+                    request_msg.msg_type,
+                    server_id,
+                    Some(request_msg.sender_id),
+                    response_payload,
+                );
+                response_msg.write_to_stream(&mut stream).await?;
+                continue;
+            }
+
+            if request_msg.msg_type == MSG_TYPE_MODEL_STATUS {
+                let response_payload =
+                    Self::handle_model_status_message(&request_msg, &embedding_manager).await?;
+                let response_msg = ProtocolMessage::with_type(
+                    MSG_TYPE_MODEL_STATUS,
+                    server_id,
+                    Some(request_msg.sender_id),
+                    response_payload,
+                );
+                response_msg.write_to_stream(&mut stream).await?;
+                continue;
+            }
+
             // Deserialize request
             let embed_request: EmbedRequest = match deserialize_request(&request_msg.payload) {
                 Ok(req) => req,
@@ -160,23 +211,51 @@ impl EmbeddingServer {
                 embed_request.text.len()
             );
 
+            // Packed responses are sent when the client asks for a precision
+            let packed_precision = match embed_request.precision.as_deref().map(Precision::from_config).transpose() {
+                Ok(precision) => precision,
+                Err(e) => {
+                    let error_payload = serialize_error(&ErrorResponse { error: e.to_string() })?;
+                    let response_msg =
+                        ProtocolMessage::new(server_id, Some(request_msg.sender_id), error_payload);
+                    response_msg.write_to_stream(&mut stream).await?;
+                    continue;
+                }
+            };
+
             // Generate embedding
-            let embedding_result = if let Some(model_name) = &embed_request.model {
-                embedding_manager
-                    .embed_text_with_model(&embed_request.text, model_name)
-                    .await
-            } else {
-                embedding_manager.embed_text(&embed_request.text).await
+            let embedding_result = match (&embed_request.input_ids, &embed_request.model) {
+                (Some(input_ids), Some(model_name)) => {
+                    embedding_manager
+                        .embed_token_ids_with_model(input_ids, model_name)
+                        .await
+                }
+                (Some(input_ids), None) => embedding_manager.embed_token_ids(input_ids).await,
+                (None, Some(model_name)) => {
+                    embedding_manager
+                        .embed_text_with_model(&embed_request.text, model_name)
+                        .await
+                }
+                (None, None) => embedding_manager.embed_text(&embed_request.text).await,
             };
 
             // Prepare response
             let response_payload = match embedding_result {
                 Ok(embedding) => {
                     debug!("✅ Generated embedding with {} dimensions", embedding.len());
-                    // Convert f32 embedding to f64 as required
-                    let embedding_f64: Vec<f64> = embedding.into_iter().map(|x| x as f64).collect();
-                    let response = EmbedResponse::new(embedding_f64);
-                    serialize_response(&response)?
+                    if let Some(precision) = packed_precision {
+                        let fingerprint = embedding_manager.model_fingerprint(embed_request.model.as_deref()).await;
+                        serialize_packed_response(&PackedEmbedResponse::new(&embedding, precision).with_fingerprint(fingerprint))?
+                    } else {
+                        // Convert f32 embedding to f64 as required
+                        let embedding_f64: Vec<f64> = embedding.into_iter().map(|x| x as f64).collect();
+                        let response = EmbedResponse::new(embedding_f64);
+                        serialize_response(&response)?
+                    }
+                }
+                Err(e @ EmbeddingError::ModelUnavailable { .. }) => {
+                    warn!("⚠️  {}", e);
+                    serialize_error(&ErrorResponse { error: e.to_string() })?
                 }
                 Err(e) => {
                     error!("❌ Embedding generation failed: {:?}", e);
@@ -197,4 +276,133 @@ impl EmbeddingServer {
 
         Ok(())
     }
+
+    /// Answer a model status message with the lifecycle of one or all models
+    async fn handle_model_status_message(
+        request_msg: &ProtocolMessage,
+        embedding_manager: &EmbeddingModelsManager,
+    ) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        let request: ModelStatusRequest = match deserialize_message(&request_msg.payload) {
+            Ok(request) => request,
+            Err(e) => return serialize_error(&ErrorResponse { error: format!("Invalid request format: {}", e) }),
+        };
+
+        let models = match &request.model {
+            Some(name) => match embedding_manager.model_status(Some(name)).await {
+                Some(status) => vec![status],
+                None => return serialize_error(&ErrorResponse { error: format!("Model '{}' not found", name) }),
+            },
+            None => embedding_manager.model_statuses().await,
+        };
+        serialize_message(&ModelStatusResponse { models })
+    }
+
+    /// Answer a tokenize, detokenize or count-tokens message
+    async fn handle_tokenizer_message(
+        request_msg: &ProtocolMessage,
+        embedding_manager: &EmbeddingModelsManager,
+    ) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        let invalid_request = |e: rmp_serde::decode::Error| format!("Invalid request format: {}", e);
+
+        let result: Result<Vec<u8>, String> = async {
+            match request_msg.msg_type {
+                MSG_TYPE_TOKENIZE => {
+                    let request: TokenizeRequest =
+                        deserialize_message(&request_msg.payload).map_err(invalid_request)?;
+                    let tokenized = embedding_manager
+                        .tokenize(&request.text, request.add_special_tokens, request.model.as_deref())
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    serialize_message(&tokenized).map_err(|e| e.to_string())
+                }
+                MSG_TYPE_DETOKENIZE => {
+                    let request: DetokenizeRequest =
+                        deserialize_message(&request_msg.payload).map_err(invalid_request)?;
+                    let text = embedding_manager
+                        .decode(&request.ids, request.skip_special_tokens, request.model.as_deref())
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    serialize_message(&DetokenizeResponse { text }).map_err(|e| e.to_string())
+                }
+                _ => {
+                    let request: CountTokensRequest =
+                        deserialize_message(&request_msg.payload).map_err(invalid_request)?;
+                    let count = embedding_manager
+                        .count_tokens(&request.text, request.add_special_tokens, request.model.as_deref())
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    serialize_message(&CountTokensResponse { count }).map_err(|e| e.to_string())
+                }
+            }
+        }
+        .await;
+
+        match result {
+            Ok(payload) => Ok(payload),
+            Err(error) => {
+                warn!("⚠️  Tokenizer request failed: {}", error);
+                serialize_error(&ErrorResponse { error })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::model::mock::{mock_config, mock_model_toml};
+    use crate::protocol::{deserialize_packed_response, deserialize_response, serialize_request};
+
+    #[tokio::test]
+    async fn test_ovnt_round_trip_with_mock_model() {
+        let models_config = mock_config("mock-model", &[mock_model_toml("mock-model", "max_sequence_length = 256\nembedding_dimension = 16")]);
+        let mut manager = EmbeddingModelsManager::from_config(models_config).unwrap();
+        manager.initialize().await.unwrap();
+
+        let mut config = ServerConfig::default();
+        config.network.bind_address = "127.0.0.1:0".to_string();
+        let manager = Arc::new(manager);
+        let fingerprint = manager.model_fingerprint(None).await;
+        assert!(fingerprint.is_some());
+        let mut server = EmbeddingServer::with_manager(config, manager).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let server_handle = tokio::spawn(async move { server.start().await.map_err(|e| e.to_string()) });
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = EmbedRequest {
+            text: "hello over ovnt".to_string(),
+            model: None,
+            precision: None,
+            input_ids: None,
+        };
+        let message = ProtocolMessage::new(Uuid::new_v4(), None, serialize_request(&request).unwrap());
+        message.write_to_stream(&mut stream).await.unwrap();
+
+        let response_msg = ProtocolMessage::read_from_stream(&mut stream).await.unwrap();
+        let response = deserialize_response(&response_msg.payload).unwrap();
+        assert_eq!(response.get_embedding().len(), 16);
+
+        let request = EmbedRequest {
+            text: "hello over ovnt".to_string(),
+            model: None,
+            precision: Some("f32".to_string()),
+            input_ids: None,
+        };
+        let message = ProtocolMessage::new(Uuid::new_v4(), None, serialize_request(&request).unwrap());
+        message.write_to_stream(&mut stream).await.unwrap();
+        let response_msg = ProtocolMessage::read_from_stream(&mut stream).await.unwrap();
+        let response = deserialize_packed_response(&response_msg.payload).unwrap();
+        assert_eq!(response.embedding().unwrap().len(), 16);
+        assert_eq!(response.model_fingerprint, fingerprint);
+
+        let request = ModelStatusRequest { model: Some("mock-model".to_string()) };
+        let message = ProtocolMessage::with_type(MSG_TYPE_MODEL_STATUS, Uuid::new_v4(), None, serialize_message(&request).unwrap());
+        message.write_to_stream(&mut stream).await.unwrap();
+        let response_msg = ProtocolMessage::read_from_stream(&mut stream).await.unwrap();
+        let response: ModelStatusResponse = deserialize_message(&response_msg.payload).unwrap();
+        assert_eq!(response.models[0].state, crate::models::lifecycle::ModelState::Ready);
+        assert_eq!(response.models[0].fingerprint, fingerprint);
+
+        server_handle.abort();
+    }
 }
@@ -0,0 +1,6 @@
+//! Static Embedding Module for Embedding Server
+//!
+//! This module provides lookup-table (Model2Vec / GloVe style) embedding functionality
+
+pub mod static_engine;
+pub use static_engine::{StaticEmbeddingEngine, TokenWeighting};
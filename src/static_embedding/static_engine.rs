@@ -0,0 +1,311 @@
+//! # Static Embedding Engine
+//!
+//! Lookup-table embeddings in the style of Model2Vec and GloVe: every token id
+//! maps to a precomputed vector, and a text embedding is the weighted mean of
+//! its token vectors. There is no neural network in the request path, so an
+//! embedding costs a tokenizer call plus a few vector additions.
+//!
+//! ## Weighting
+//!
+//! - `none` - plain mean of the token vectors
+//! - `sif` - smooth inverse frequency, `a / (a + p(w))`, with `p(w)` taken
+//!   from a Zipf distribution over token ids (Model2Vec vocabularies are
+//!   sorted by frequency, so the id doubles as the frequency rank)
+//! - `zipf` - `ln(1 + rank)`, down-weighting the most frequent tokens
+
+use crate::models::config::StaticEmbeddingConfig;
+use crate::models::weights::{self, Matrix};
+use crate::models::tokenizer_loader::load_tokenizer;
+use crate::models::{pooling, EmbeddingError};
+use safetensors::SafeTensors;
+use std::path::Path;
+use tokenizers::Tokenizer;
+use tracing::{debug, info};
+
+/// Token weighting scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenWeighting {
+    None,
+    Sif,
+    Zipf,
+}
+
+impl TokenWeighting {
+    /// Parse the `weighting` value from embeddingmodels.toml
+    pub fn from_config(weighting: &str) -> Result<Self, EmbeddingError> {
+        match weighting.to_lowercase().as_str() {
+            "none" | "mean" => Ok(TokenWeighting::None),
+            "sif" => Ok(TokenWeighting::Sif),
+            "zipf" => Ok(TokenWeighting::Zipf),
+            other => Err(EmbeddingError::ConfigError {
+                message: format!("Unknown static embedding weighting '{}' (expected none, sif or zipf)", other),
+            }),
+        }
+    }
+}
+
+/// Lookup-table embedding engine
+#[derive(Debug)]
+pub struct StaticEmbeddingEngine {
+    /// Tokenizer mapping text to rows of the embedding matrix
+    tokenizer: Tokenizer,
+    /// Token-to-vector matrix [vocab_size, dimension]
+    embeddings: Matrix,
+    /// Per-token weight, indexed by token id
+    token_weights: Vec<f32>,
+    /// L2-normalize the pooled vector
+    normalize: bool,
+    /// Maximum number of tokens used per text
+    max_seq_length: usize,
+}
+
+impl StaticEmbeddingEngine {
+    /// Load a static embedding engine from disk
+    ///
+    /// # Arguments
+    /// * `model_path` - Path to the token-to-vector matrix (.safetensors or .npy)
+    /// * `tokenizer_path` - Path to the tokenizer configuration file (tokenizer.json)
+    /// * `config` - Static backend settings
+    /// * `max_seq_length` - Maximum number of tokens used per text
+    pub fn new(
+        model_path: &str,
+        tokenizer_path: &str,
+        config: &StaticEmbeddingConfig,
+        max_seq_length: usize,
+    ) -> Result<Self, EmbeddingError> {
+        info!("Initializing static embedding engine with vectors: {}", model_path);
+
+        let embeddings = load_vectors(model_path, config.tensor_name.as_deref())?;
+
+        let tokenizer = load_tokenizer(tokenizer_path)?;
+
+        let engine = Self::from_parts(tokenizer, embeddings, config, max_seq_length)?;
+        info!("Static embedding engine initialized with {} vectors of dimension {}",
+              engine.embeddings.rows, engine.embeddings.cols);
+        Ok(engine)
+    }
+
+    /// Build an engine from an already loaded tokenizer and matrix
+    pub fn from_parts(
+        tokenizer: Tokenizer,
+        embeddings: Matrix,
+        config: &StaticEmbeddingConfig,
+        max_seq_length: usize,
+    ) -> Result<Self, EmbeddingError> {
+        let weighting = TokenWeighting::from_config(&config.weighting)?;
+        let token_weights = Self::compute_token_weights(weighting, embeddings.rows, config.sif_coefficient);
+
+        Ok(Self {
+            tokenizer,
+            embeddings,
+            token_weights,
+            normalize: config.normalize,
+            max_seq_length,
+        })
+    }
+
+    /// Precompute one weight per vocabulary entry
+    fn compute_token_weights(weighting: TokenWeighting, vocab_size: usize, sif_coefficient: f64) -> Vec<f32> {
+        match weighting {
+            TokenWeighting::None => vec![1.0; vocab_size],
+            TokenWeighting::Zipf => (0..vocab_size).map(|id| ((id + 2) as f32).ln()).collect(),
+            TokenWeighting::Sif => {
+                // p(rank) = 1 / (rank * H_n) under Zipf's law, ranks starting at 1
+                let harmonic: f64 = (1..=vocab_size).map(|rank| 1.0 / rank as f64).sum();
+                (0..vocab_size)
+                    .map(|id| {
+                        let probability = 1.0 / ((id + 1) as f64 * harmonic);
+                        (sif_coefficient / (sif_coefficient + probability)) as f32
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Embedding dimension (matrix width)
+    pub fn dimension(&self) -> usize {
+        self.embeddings.cols
+    }
+
+    /// Number of rows in the embedding matrix, the largest accepted token id plus one
+    pub fn vocab_size(&self) -> usize {
+        self.embeddings.rows
+    }
+
+    /// Tokenizer used to prepare model inputs
+    pub fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
+
+    /// Embed a single text
+    pub fn embed_text(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        let encoding = self.tokenizer.encode(text, false)
+            .map_err(|e| EmbeddingError::EmbeddingFailed {
+                error: format!("Tokenization failed: {}", e),
+            })?;
+
+        self.embed_token_ids(encoding.get_ids())
+    }
+
+    /// Embed already tokenized input, bypassing the tokenizer
+    pub fn embed_token_ids(&self, input_ids: &[u32]) -> Result<Vec<f32>, EmbeddingError> {
+        let mut pooled = vec![0.0f32; self.embeddings.cols];
+        let mut total_weight = 0.0f32;
+
+        for &id in input_ids.iter().take(self.max_seq_length) {
+            let id = id as usize;
+            if id >= self.embeddings.rows {
+                continue;
+            }
+            let weight = self.token_weights[id];
+            for (acc, value) in pooled.iter_mut().zip(self.embeddings.row(id)) {
+                *acc += weight * value;
+            }
+            total_weight += weight;
+        }
+
+        if total_weight == 0.0 {
+            return Err(EmbeddingError::InvalidInput {
+                message: "No known tokens found in input".to_string(),
+            });
+        }
+
+        for value in &mut pooled {
+            *value /= total_weight;
+        }
+
+        if self.normalize {
+            pooling::normalize_embedding(&pooled)
+        } else {
+            Ok(pooled)
+        }
+    }
+
+    /// Embed a batch of texts
+    pub fn embed_texts(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        if texts.is_empty() {
+            return Err(EmbeddingError::InvalidInput {
+                message: "Cannot embed empty text list".to_string(),
+            });
+        }
+
+        debug!("Generating static embeddings for {} texts", texts.len());
+        texts.iter().map(|text| self.embed_text(text)).collect()
+    }
+}
+
+/// Load the token vectors, from the only tensor of a safetensors file unless one is named
+///
+/// Files holding several tensors fall back to "embeddings", where Model2Vec
+/// stores its matrix.
+fn load_vectors(model_path: &str, tensor_name: Option<&str>) -> Result<Matrix, EmbeddingError> {
+    let path = Path::new(model_path);
+    if tensor_name.is_some() || path.extension().and_then(|ext| ext.to_str()) != Some("safetensors") {
+        return weights::load_matrix(path, tensor_name);
+    }
+
+    let bytes = std::fs::read(path).map_err(|e| EmbeddingError::ModelLoadFailed {
+        error: format!("Failed to read weights file {}: {}", path.display(), e),
+    })?;
+    let tensors = SafeTensors::deserialize(&bytes).map_err(|e| EmbeddingError::ModelLoadFailed {
+        error: format!("Invalid safetensors file {}: {}", path.display(), e),
+    })?;
+
+    let mut names = tensors.names();
+    let name = match names.as_slice() {
+        [name] => name.as_str(),
+        _ if names.iter().any(|name| *name == "embeddings") => "embeddings",
+        _ => {
+            names.sort();
+            return Err(EmbeddingError::ModelLoadFailed {
+                error: format!(
+                    "Safetensors file {} has tensors [{}] and none named 'embeddings'; set tensor_name",
+                    path.display(),
+                    names.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", ")
+                ),
+            });
+        }
+    };
+    weights::tensor_matrix(&tensors, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tokenizers::models::wordlevel::WordLevel;
+    use tokenizers::pre_tokenizers::whitespace::Whitespace;
+
+    fn test_engine(weighting: &str) -> StaticEmbeddingEngine {
+        let vocab: HashMap<String, u32> = [("[UNK]", 0), ("hello", 1), ("world", 2)]
+            .into_iter()
+            .map(|(token, id)| (token.to_string(), id))
+            .collect();
+        let model = WordLevel::builder().vocab(vocab).unk_token("[UNK]".to_string()).build().unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Whitespace {});
+
+        let embeddings = Matrix::new(3, 2, vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0]).unwrap();
+        let config = StaticEmbeddingConfig {
+            weighting: weighting.to_string(),
+            ..Default::default()
+        };
+        StaticEmbeddingEngine::from_parts(tokenizer, embeddings, &config, 128).unwrap()
+    }
+
+    #[test]
+    fn test_weighted_mean() {
+        let engine = test_engine("none");
+        let embedding = engine.embed_text("hello world").unwrap();
+        let expected = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((embedding[0] - expected).abs() < 1e-6);
+        assert!((embedding[1] - expected).abs() < 1e-6);
+
+        // Rarer tokens (higher ids) get more weight under SIF
+        let embedding = test_engine("sif").embed_text("hello world").unwrap();
+        assert!(embedding[1] > embedding[0]);
+    }
+
+    #[test]
+    fn test_token_ids_match_text() {
+        let engine = test_engine("none");
+        assert_eq!(engine.embed_token_ids(&[1, 2]).unwrap(), engine.embed_text("hello world").unwrap());
+        assert_eq!(engine.vocab_size(), 3);
+        assert!(engine.embed_token_ids(&[]).is_err());
+        // Ids beyond the matrix are skipped; if nothing is left the input is invalid
+        assert!(matches!(engine.embed_token_ids(&[3, 7]), Err(EmbeddingError::InvalidInput { .. })));
+        assert!(matches!(engine.embed_texts(&["".to_string()]), Err(EmbeddingError::InvalidInput { .. })));
+    }
+
+    #[test]
+    fn test_invalid_weighting() {
+        assert!(TokenWeighting::from_config("tfidf").is_err());
+        assert!(test_engine("none").embed_texts(&[]).is_err());
+    }
+
+    #[test]
+    fn test_vectors_from_single_or_named_tensor() {
+        let path = std::env::temp_dir().join(format!("static-vectors-{}.safetensors", std::process::id()));
+        let data: Vec<u8> = [1.0f32, 2.0, 3.0, 4.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let view = || safetensors::tensor::TensorView::new(safetensors::Dtype::F32, vec![2, 2], &data).unwrap();
+        let path_str = path.to_str().unwrap();
+
+        safetensors::serialize_to_file([("word_vectors", view())], &None, &path).unwrap();
+        assert_eq!(load_vectors(path_str, None).unwrap().row(1), &[3.0, 4.0]);
+        assert!(load_vectors(path_str, Some("embeddings")).is_err());
+
+        safetensors::serialize_to_file([("embeddings", view()), ("other", view())], &None, &path).unwrap();
+        assert_eq!(load_vectors(path_str, None).unwrap().rows, 2);
+
+        // Without an "embeddings" tensor the error names what the file holds
+        safetensors::serialize_to_file([("vectors", view()), ("other", view())], &None, &path).unwrap();
+        let error = load_vectors(path_str, None).unwrap_err().to_string();
+        assert!(error.contains("[other, vectors]"), "{}", error);
+
+        // A corrupt file is reported as such, not as a missing tensor
+        std::fs::write(&path, b"not a safetensors file").unwrap();
+        let error = load_vectors(path_str, None).unwrap_err().to_string();
+        assert!(error.contains("Invalid safetensors file"), "{}", error);
+        std::fs::remove_file(path).unwrap();
+    }
+}